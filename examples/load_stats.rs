@@ -0,0 +1,59 @@
+//! Measures the startup time and the peak memory used for loading the assets.
+//!
+//! Usage: `cargo run --release --example load_stats [GAME_DIR]`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rolf3d::*;
+
+/// Wraps the system allocator, to keep track of the current and the peak heap usage.
+struct CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn main() {
+    let dir = std::env::args().nth(1).unwrap_or(".".to_string());
+    let assets = match GameAssets::load_from(&dir) {
+        Ok(assets) => assets,
+        Err(err) => {
+            println!("ERROR in ROLF3D: {err}");
+            std::process::exit(1);
+        }
+    };
+    let kb = |bytes: usize| bytes / 1024;
+    println!("{}", assets.load_stats);
+    println!(
+        "Heap after loading: {} KB (peak: {} KB)",
+        kb(CURRENT.load(Ordering::Relaxed)),
+        kb(PEAK.load(Ordering::Relaxed))
+    );
+
+    // now decode all walls and sprites, like a long game session would
+    let cnt = assets.walls.iter().chain(assets.sprites.iter()).count();
+    println!(
+        "Heap after decoding all {cnt} walls and sprites: {} KB (peak: {} KB)",
+        kb(CURRENT.load(Ordering::Relaxed)),
+        kb(PEAK.load(Ordering::Relaxed))
+    );
+}
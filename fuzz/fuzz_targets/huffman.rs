@@ -0,0 +1,15 @@
+//! Fuzz the Huffman decoder, used for the chunks in VGAGRAPH.
+//! The first 1024 bytes are the dictionary (like VGADICT), the rest is the compressed chunk.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rolf3d::*;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 1024 {
+        return;
+    }
+    let huff_dict: Vec<u16> = data[0..1024].chunks_exact(2).map(buf_to_u16).collect();
+    let _ = huff_decode_chunk(&data[1024..], &huff_dict);
+});
@@ -0,0 +1,18 @@
+//! Fuzz the Carmack + RLEW decoders, used for the map planes in GAMEMAPS.
+//! The first 2 bytes are the RLEW tag, the rest is the compressed plane.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rolf3d::*;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let rlew_tag = buf_to_u16(data);
+    let _ = decompress_map_plane(&data[2..], rlew_tag);
+    // also fuzz RLEW on its own, with words not produced by Carmack
+    let words: Vec<u16> = data[2..].chunks_exact(2).map(buf_to_u16).collect();
+    let _ = rlew_decompress(&words, rlew_tag);
+});
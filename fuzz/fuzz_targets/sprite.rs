@@ -0,0 +1,12 @@
+//! Fuzz the sprite decoder, used for the sprite chunks in VSWAP.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rolf3d::*;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(pixels) = decode_sprite(data) {
+        assert_eq!(64 * 64, pixels.len());
+    }
+});
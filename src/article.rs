@@ -0,0 +1,408 @@
+//! Text articles - the "Read This!" help pages and the end-of-episode texts, stored in VGAGRAPH chunks.
+//!
+//! They use a small markup language - commands start with `^` (and are case-insensitive):
+//! * `^P` starts a page (the rest of its line is ignored) and `^E` ends the article
+//! * `^Cxx` changes the text color (2 hex digits)
+//! * `^Gy,x,pic` draws a pic (by its VGAGRAPH chunk index) and pushes the margins away from it
+//! * `^Ty,x,pic,tics` waits a number of tics (70 per second), then draws a pic
+//! * `^Ly,x` moves the text cursor, `^By,x,w,h` clears a rectangle and `^>` moves to the middle of the screen
+//! * `^;` is a comment line
+//!
+//! Parsing and layout follow the original code exactly - quirks included.
+//! -> see [WL_TEXT.C](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/WL_TEXT.C)
+
+use crate::FontData;
+
+/// The articles are laid out on the 320x200 VGA screen.
+pub const ARTICLE_SCREEN_WIDTH: i32 = 320;
+pub const ARTICLE_SCREEN_HEIGHT: i32 = 200;
+
+/// The background color of the article pages.
+pub const ARTICLE_BACK_COLOR: u8 = 0x11;
+
+/// The timed pics (`^T`) count their delay in tics - 70 per second.
+pub const ARTICLE_TICS_PER_SECOND: f64 = 70.0;
+
+/// One element of an article page, as written in the markup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArticleElem {
+    /// A word, followed by a number of spaces
+    Word {
+        text: String,
+        spaces: usize,
+    },
+    Tab,
+    NewLine,
+    Color(u8),
+    /// `^G` - a pic, which also pushes the text margins
+    Pic {
+        x: i32,
+        y: i32,
+        chunk: usize,
+    },
+    /// `^T` - a pic, drawn after a delay
+    TimedPic {
+        x: i32,
+        y: i32,
+        chunk: usize,
+        tics: u32,
+    },
+    /// `^L` - move the text cursor (y is rounded to a text row)
+    Locate {
+        x: i32,
+        y: i32,
+    },
+    /// `^B` - clear a rectangle, with the background color
+    Bar {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    /// `^>` - move the text cursor to the middle of the screen
+    Center,
+}
+
+/// One item of a laid out page - everything is positioned on the 320x200 screen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PageItem {
+    Text {
+        x: i32,
+        y: i32,
+        color: u8,
+        text: String,
+    },
+    Pic {
+        x: i32,
+        y: i32,
+        chunk: usize,
+    },
+    Bar {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    /// The items after this one are shown only after a delay (in tics)
+    Wait {
+        tics: u32,
+    },
+}
+
+/// A text article: a list of pages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Article {
+    pages: Vec<Vec<ArticleElem>>,
+}
+
+impl Article {
+    /// Check if a VGAGRAPH chunk looks like an article - i.e. it starts with `^P`.
+    pub fn is_article(bytes: &[u8]) -> bool {
+        let mut text = bytes.iter().skip_while(|b| **b <= 32);
+        text.next() == Some(&b'^') && text.next().is_some_and(|b| b.eq_ignore_ascii_case(&b'P'))
+    }
+
+    /// Parse an article from its markup. It must start with `^P`, and it ends with `^E` (or with the data).
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if !Self::is_article(bytes) {
+            return Err("Article does not start with ^P".to_string());
+        }
+        let mut parser = MarkupParser { bytes, pos: 0 };
+        let mut pages: Vec<Vec<ArticleElem>> = vec![];
+        while let Some(ch) = parser.peek() {
+            parser.pos += 1;
+            let elem = match ch {
+                b'^' => {
+                    let Some(cmd) = parser.peek() else {
+                        break;
+                    };
+                    parser.pos += 1;
+                    match cmd.to_ascii_uppercase() {
+                        b'P' => {
+                            parser.rip_to_eol();
+                            pages.push(vec![]);
+                            continue;
+                        }
+                        b'E' => break,
+                        b'C' => {
+                            let hi = parser.hex_digit();
+                            let lo = parser.hex_digit();
+                            ArticleElem::Color(hi * 16 + lo)
+                        }
+                        b'G' => {
+                            let [y, x, chunk] = parser.numbers()?;
+                            parser.rip_to_eol();
+                            ArticleElem::Pic {
+                                x,
+                                y,
+                                chunk: chunk as usize,
+                            }
+                        }
+                        b'T' => {
+                            let [y, x, chunk, tics] = parser.numbers()?;
+                            parser.rip_to_eol();
+                            ArticleElem::TimedPic {
+                                x,
+                                y,
+                                chunk: chunk as usize,
+                                tics: tics as u32,
+                            }
+                        }
+                        b'L' => {
+                            let [y, x] = parser.numbers()?;
+                            parser.rip_to_eol();
+                            ArticleElem::Locate { x, y }
+                        }
+                        b'B' => {
+                            let [y, x, width, height] = parser.numbers()?;
+                            parser.rip_to_eol();
+                            ArticleElem::Bar { x, y, width, height }
+                        }
+                        b';' => {
+                            parser.rip_to_eol();
+                            continue;
+                        }
+                        b'>' => ArticleElem::Center,
+                        _ => {
+                            // unknown command => the original just prints it, as a word (with the caret)
+                            parser.word(parser.pos - 2)?
+                        }
+                    }
+                }
+                b'\t' => ArticleElem::Tab,
+                b'\n' => ArticleElem::NewLine,
+                0..=32 => continue,
+                _ => parser.word(parser.pos - 1)?,
+            };
+            // the article starts with ^P, so there is always a page
+            pages.last_mut().unwrap().push(elem);
+        }
+        Ok(Self { pages })
+    }
+
+    #[inline]
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// The elements of a page, as written in the markup.
+    #[inline]
+    pub fn page(&self, page_idx: usize) -> &[ArticleElem] {
+        &self.pages[page_idx]
+    }
+
+    /// Lay out a page: wrap the words between the margins (pushed by the pics), using the given font,
+    /// and add the page number at the bottom. `pic_size` gets the size of a pic, by its chunk index.
+    /// Like in the original, the text which does not fit on the page is dropped.
+    pub fn layout_page(
+        &self,
+        page_idx: usize,
+        font: &FontData,
+        pic_size: impl Fn(usize) -> Option<(u16, u16)>,
+    ) -> Vec<PageItem> {
+        let mut layout = PageLayout::new();
+        for elem in &self.pages[page_idx] {
+            if !layout.add(elem, font, &pic_size) {
+                // overflowed the page
+                break;
+            }
+        }
+        let page_num = format!("pg {} of {}", page_idx + 1, self.pages.len());
+        layout.items.push(PageItem::Text {
+            x: PAGE_NUM_X,
+            y: PAGE_NUM_Y,
+            color: PAGE_NUM_COLOR,
+            text: page_num,
+        });
+        layout.items
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+// the layout constants from WL_TEXT.C
+const WORD_LIMIT: usize = 80;
+const FONT_HEIGHT: i32 = 10;
+const TOP_MARGIN: i32 = 16;
+const BOTTOM_MARGIN: i32 = 32;
+const LEFT_MARGIN: i32 = 16;
+const RIGHT_MARGIN: i32 = 16;
+const PIC_MARGIN: i32 = 8;
+const TEXT_ROWS: usize = ((ARTICLE_SCREEN_HEIGHT - TOP_MARGIN - BOTTOM_MARGIN) / FONT_HEIGHT) as usize;
+const SPACE_WIDTH: i32 = 7;
+const SCREEN_MID: i32 = ARTICLE_SCREEN_WIDTH / 2;
+const PAGE_NUM_X: i32 = 213;
+const PAGE_NUM_Y: i32 = 183;
+const PAGE_NUM_COLOR: u8 = 0x4F;
+
+struct MarkupParser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> MarkupParser<'a> {
+    #[inline]
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Skip the rest of the line, including the line end.
+    fn rip_to_eol(&mut self) {
+        while let Some(ch) = self.peek() {
+            self.pos += 1;
+            if ch == b'\n' {
+                break;
+            }
+        }
+    }
+
+    /// Read one hex digit - like in the original, invalid digits count as 0 (but are still consumed).
+    fn hex_digit(&mut self) -> u8 {
+        let digit = self.peek().and_then(|ch| (ch as char).to_digit(16)).unwrap_or(0);
+        self.pos += 1;
+        digit as u8
+    }
+
+    /// Read a word which starts at `start`, up to the next whitespace, + the spaces after it.
+    fn word(&mut self, start: usize) -> Result<ArticleElem, String> {
+        while self.peek().is_some_and(|b| b > 32) {
+            self.pos += 1;
+        }
+        if self.pos - start >= WORD_LIMIT {
+            return Err(format!("Word too long at offset 0x{start:X}"));
+        }
+        let text = String::from_utf8_lossy(&self.bytes[start..self.pos]).to_string();
+        let mut spaces = 0;
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+            spaces += 1;
+        }
+        Ok(ArticleElem::Word { text, spaces })
+    }
+
+    /// Read some decimal numbers - anything between them (e.g. commas) is skipped.
+    fn numbers<const N: usize>(&mut self) -> Result<[i32; N], String> {
+        let mut numbers = [0; N];
+        for number in numbers.iter_mut() {
+            while self.peek().is_some_and(|ch| !ch.is_ascii_digit()) {
+                self.pos += 1;
+            }
+            let start = self.pos;
+            while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+                self.pos += 1;
+            }
+            let digits = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or("");
+            *number = digits
+                .parse()
+                .map_err(|_| format!("Missing number at offset 0x{start:X}"))?;
+        }
+        Ok(numbers)
+    }
+}
+
+/// The state of the page layout - the text cursor and the margins of each text row.
+struct PageLayout {
+    items: Vec<PageItem>,
+    left_margins: [i32; TEXT_ROWS],
+    right_margins: [i32; TEXT_ROWS],
+    px: i32,
+    py: i32,
+    row: usize,
+    color: u8,
+}
+
+impl PageLayout {
+    fn new() -> Self {
+        Self {
+            items: vec![],
+            left_margins: [LEFT_MARGIN; TEXT_ROWS],
+            right_margins: [ARTICLE_SCREEN_WIDTH - RIGHT_MARGIN; TEXT_ROWS],
+            px: LEFT_MARGIN,
+            py: TOP_MARGIN,
+            row: 0,
+            color: 0,
+        }
+    }
+
+    /// Add an element to the page. Returns false if the page has overflowed.
+    fn add(&mut self, elem: &ArticleElem, font: &FontData, pic_size: &impl Fn(usize) -> Option<(u16, u16)>) -> bool {
+        match elem {
+            ArticleElem::Word { text, spaces } => {
+                let width = font.text_width(text);
+                while self.px + width > self.right_margins[self.row] {
+                    if !self.new_line() {
+                        return false;
+                    }
+                }
+                self.items.push(PageItem::Text {
+                    x: self.px,
+                    y: self.py,
+                    color: self.color,
+                    text: text.clone(),
+                });
+                self.px += width + (*spaces as i32) * SPACE_WIDTH;
+            }
+            ArticleElem::Tab => self.px = (self.px + 8) & !7,
+            ArticleElem::NewLine => return self.new_line(),
+            ArticleElem::Color(color) => self.color = *color,
+            ArticleElem::Pic { x, y, chunk } => {
+                // the pic is drawn on a byte boundary => so are its margins
+                let x = x & !7;
+                self.items.push(PageItem::Pic {
+                    x,
+                    y: *y,
+                    chunk: *chunk,
+                });
+                // push the margin on the side of the pic, for all the text rows next to it
+                let (width, height) = pic_size(*chunk).unwrap_or((0, 0));
+                let (width, height) = (width as i32, height as i32);
+                let pic_mid = x + width / 2;
+                let top = ((y - TOP_MARGIN) / FONT_HEIGHT).max(0);
+                let bottom = ((y + height - TOP_MARGIN) / FONT_HEIGHT).min(TEXT_ROWS as i32 - 1);
+                for row in top..=bottom {
+                    if pic_mid > SCREEN_MID {
+                        self.right_margins[row as usize] = x - PIC_MARGIN;
+                    } else {
+                        self.left_margins[row as usize] = x + width + PIC_MARGIN;
+                    }
+                }
+                self.px = self.px.max(self.left_margins[self.row]);
+            }
+            ArticleElem::TimedPic { x, y, chunk, tics } => {
+                self.items.push(PageItem::Wait { tics: *tics });
+                self.items.push(PageItem::Pic {
+                    x: x & !7,
+                    y: *y,
+                    chunk: *chunk,
+                });
+            }
+            ArticleElem::Locate { x, y } => {
+                let row = ((y - TOP_MARGIN) / FONT_HEIGHT).clamp(0, TEXT_ROWS as i32 - 1);
+                self.row = row as usize;
+                self.py = TOP_MARGIN + row * FONT_HEIGHT;
+                self.px = *x;
+            }
+            ArticleElem::Bar { x, y, width, height } => self.items.push(PageItem::Bar {
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+            }),
+            ArticleElem::Center => self.px = SCREEN_MID,
+        }
+        true
+    }
+
+    /// Move to the next text row. Returns false if the page has overflowed.
+    fn new_line(&mut self) -> bool {
+        self.row += 1;
+        if self.row == TEXT_ROWS {
+            return false;
+        }
+        self.px = self.left_margins[self.row];
+        self.py += FONT_HEIGHT;
+        true
+    }
+}
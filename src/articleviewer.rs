@@ -0,0 +1,215 @@
+//! ArticleViewer - shows the text articles (the help pages and the end-of-episode texts), like the original game.
+
+use crate::*;
+use sdl2::keyboard::Keycode;
+use std::rc::Rc;
+
+pub struct ArticleViewer {
+    assets: Rc<GameAssets>,
+    article: Option<Article>,
+    page_idx: usize,
+    layout: Vec<PageItem>,
+    /// How long the current page has been shown - for the timed pics
+    page_time: f64,
+}
+
+impl ArticleViewer {
+    pub fn new(assets: Rc<GameAssets>) -> Self {
+        Self {
+            assets,
+            article: None,
+            page_idx: 0,
+            layout: vec![],
+            page_time: 0.0,
+        }
+    }
+
+    /// True if the game has "Read This!" help pages.
+    #[inline]
+    pub fn has_help(&self) -> bool {
+        self.assets
+            .help_article
+            .as_ref()
+            .is_some_and(|article| article.page_count() > 0)
+    }
+
+    /// Show the "Read This!" help pages. Returns false if the game has none.
+    pub fn show_help(&mut self) -> bool {
+        let article = self.assets.help_article.clone();
+        self.show(article)
+    }
+
+    /// Show the text at the end of an episode. Returns false if the game has none.
+    pub fn show_episode_end(&mut self, episode: u8) -> bool {
+        let article = self.assets.end_articles.get(episode as usize).cloned();
+        self.show(article)
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.article.is_some()
+    }
+
+    pub fn handle_inputs(&mut self, inputs: &mut InputManager, elapsed_time: f64) {
+        let Some(article) = &self.article else {
+            return;
+        };
+        self.page_time += elapsed_time;
+        let page_cnt = article.page_count();
+        if inputs.consume_key(Keycode::Escape) {
+            self.article = None;
+        } else if [Keycode::Up, Keycode::PageUp, Keycode::Left]
+            .into_iter()
+            .any(|key| inputs.consume_key(key))
+        {
+            if self.page_idx > 0 {
+                self.go_to_page(self.page_idx - 1);
+            }
+        } else if [Keycode::Return, Keycode::Down, Keycode::PageDown, Keycode::Right]
+            .into_iter()
+            .any(|key| inputs.consume_key(key))
+            && self.page_idx + 1 < page_cnt
+        {
+            self.go_to_page(self.page_idx + 1);
+        }
+    }
+
+    pub fn paint(&self, scrbuf: &mut ScreenBuffer) {
+        if self.article.is_none() {
+            return;
+        }
+        // the page is made for the 320x200 VGA mode => stretch it over the whole (4:3) screen, like a CRT monitor did
+        let page = render_article_page(&self.layout, &self.assets, self.page_time);
+        let (sw, sh) = (scrbuf.scr_width(), scrbuf.scr_height());
+        scrbuf.fill_rect(0, 0, sw, sh, ARTICLE_BACK_COLOR);
+        scrbuf.draw_scaled_pic(0, 0, sw, sh, &page);
+    }
+
+    fn show(&mut self, article: Option<Article>) -> bool {
+        self.article = article.filter(|article| article.page_count() > 0);
+        if self.article.is_some() {
+            self.go_to_page(0);
+        }
+        self.is_open()
+    }
+
+    fn go_to_page(&mut self, page_idx: usize) {
+        let Some(article) = &self.article else {
+            return;
+        };
+        let pics = &self.assets.pics;
+        let pic_size = |chunk: usize| article_pic(pics, chunk).map(|pic| pic.size());
+        self.layout = article.layout_page(page_idx, &self.assets.font1, pic_size);
+        self.page_idx = page_idx;
+        self.page_time = 0.0;
+    }
+}
+
+/// Render a laid out article page (see `Article::layout_page`) as a 320x200 pic, with the window frame around it.
+/// `page_time` is how long the page has been shown (in seconds) - the items after a `PageItem::Wait`
+/// appear only after the wait is over.
+pub fn render_article_page(layout: &[PageItem], assets: &GameAssets, page_time: f64) -> GfxData {
+    let mut canvas = Canvas::new();
+    let frame = [
+        (PicType::HelpTopWindow, 0, 0),
+        (PicType::HelpLeftWindow, 0, 8),
+        (PicType::HelpRightWindow, 312, 8),
+        (PicType::HelpBottomInfo, 8, 176),
+    ];
+    for (typ, x, y) in frame {
+        canvas.draw_pic(x, y, assets.pics.pic(typ, 0));
+    }
+
+    let mut wait_until = 0.0;
+    for item in layout {
+        match item {
+            PageItem::Text { x, y, color, text } => canvas.draw_text(*x, *y, text, *color, &assets.font1),
+            PageItem::Pic { x, y, chunk } => {
+                if let Some(pic) = article_pic(&assets.pics, *chunk) {
+                    canvas.draw_pic(*x, *y, pic);
+                }
+            }
+            PageItem::Bar { x, y, width, height } => canvas.fill_rect(*x, *y, *width, *height, ARTICLE_BACK_COLOR),
+            PageItem::Wait { tics } => {
+                wait_until += (*tics as f64) / ARTICLE_TICS_PER_SECOND;
+                if page_time < wait_until {
+                    break;
+                }
+            }
+        }
+    }
+    canvas.into_gfx()
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The articles refer to the pics by their VGAGRAPH chunk index.
+fn article_pic(pics: &PicDict, chunk: usize) -> Option<&GfxData> {
+    let pic = pics.pic_by_file_index(chunk.checked_sub(FIRST_PIC_CHUNK)?)?;
+    (pic.size() != (0, 0)).then_some(pic)
+}
+
+/// A 320x200 image, stored column by column (like `GfxData`).
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        let len = (ARTICLE_SCREEN_WIDTH * ARTICLE_SCREEN_HEIGHT) as usize;
+        Self {
+            pixels: vec![ARTICLE_BACK_COLOR; len],
+        }
+    }
+
+    #[inline]
+    fn put_pixel(&mut self, x: i32, y: i32, c: u8) {
+        if x >= 0 && y >= 0 && x < ARTICLE_SCREEN_WIDTH && y < ARTICLE_SCREEN_HEIGHT {
+            self.pixels[(x * ARTICLE_SCREEN_HEIGHT + y) as usize] = c;
+        }
+    }
+
+    fn fill_rect(&mut self, x: i32, y: i32, w: i32, h: i32, c: u8) {
+        for dx in 0..w {
+            for dy in 0..h {
+                self.put_pixel(x + dx, y + dy, c);
+            }
+        }
+    }
+
+    fn draw_pic(&mut self, x: i32, y: i32, pic: &GfxData) {
+        let (w, h) = pic.size();
+        let mut texels = pic.texels().iter();
+        for dx in 0..w as i32 {
+            for dy in 0..h as i32 {
+                self.put_pixel(x + dx, y + dy, *texels.next().unwrap());
+            }
+        }
+    }
+
+    fn draw_text(&mut self, x: i32, y: i32, text: &str, color: u8, font: &FontData) {
+        let height = font.font_height() as i32;
+        let mut x = x;
+        for ch in text.bytes() {
+            let Some((width, pixels)) = font.glyph(ch) else {
+                if ch == b' ' {
+                    x += font.space_width() as i32;
+                }
+                continue;
+            };
+            for (idx, pixel) in pixels.iter().enumerate() {
+                if *pixel != 0 {
+                    let idx = idx as i32;
+                    self.put_pixel(x + idx / height, y + idx % height, color);
+                }
+            }
+            x += width as i32;
+        }
+    }
+
+    fn into_gfx(self) -> GfxData {
+        GfxData::new_pic(ARTICLE_SCREEN_WIDTH as u16, ARTICLE_SCREEN_HEIGHT as u16, self.pixels)
+    }
+}
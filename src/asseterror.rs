@@ -0,0 +1,55 @@
+//! Errors reported while loading the game assets.
+
+use std::fmt;
+
+/// An error found while loading the game assets.
+/// Besides the reason, it says which file, which chunk (if any) and which offset failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetError {
+    /// The asset files were not found in any of the searched directories.
+    FilesNotFound { dirs: String },
+    /// An asset file exists, but it could not be read.
+    Io { file: String, reason: String },
+    /// An asset file contains invalid data.
+    BadData {
+        file: String,
+        chunk: Option<usize>,
+        offset: usize,
+        reason: String,
+    },
+}
+
+impl AssetError {
+    /// Shortcut for building a `BadData` error.
+    pub fn bad_data(file: &str, chunk: Option<usize>, offset: usize, reason: impl Into<String>) -> Self {
+        AssetError::BadData {
+            file: file.to_string(),
+            chunk,
+            offset,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::FilesNotFound { dirs } => write!(f, "Game asset files not found in: {dirs}"),
+            AssetError::Io { file, reason } => write!(f, "{file}: {reason}"),
+            AssetError::BadData {
+                file,
+                chunk: Some(chunk),
+                offset,
+                reason,
+            } => write!(f, "{file}, chunk #{chunk} @ 0x{offset:X}: {reason}"),
+            AssetError::BadData {
+                file,
+                chunk: None,
+                offset,
+                reason,
+            } => write!(f, "{file} @ 0x{offset:X}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
@@ -1,5 +1,5 @@
 //! Wolf3d/SOD asset loader
-//! Handles maps and graphics (no sound), Huffman, de-Carmackization, RLEW etc.
+//! Handles maps, graphics and sounds, Huffman, de-Carmackization, RLEW etc.
 //!
 //! ## Some useful links:
 //! * WIKI-s:
@@ -13,39 +13,137 @@
 //!     * [VSWAP - PML_OpenPageFile](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_PM.C#L500)
 //!     * [CAL_SetupGrFile](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L872)
 //!     * [CAL_HuffExpand](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L409)
+//!     * [Sounds - SD_Startup](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.C)
+
+use std::cell::Cell;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use crate::assets::*;
+use crate::codecs::*;
 use crate::utils::*;
-use crate::PicDict;
+use crate::{
+    digi_sound_index, load_gfx_replacements, open_asset_source, Article, AssetError, AssetSource, Demo, DirSource,
+    Fingerprint, GameVersion, LayeredSource, LevelColorTable, Palette, PicDict, PicTable, SoundEffect,
+    REPLACEMENTS_DIR,
+};
 
 /// Holds all the assets loaded from the game files.
 pub struct GameAssets {
     pub maps: Vec<MapData>,
-    pub walls: Vec<GfxData>,
-    pub sprites: Vec<GfxData>,
+    pub walls: GfxPages,
+    pub sprites: GfxPages,
     pub font1: FontData,
     pub font2: FontData,
     pub pics: PicDict,
+    /// The game palette (some pics may have their own - see `PicDict::pic_palette`).
+    pub palette: Palette,
+    /// The "Read This!" help pages, if the game has them.
+    pub help_article: Option<Article>,
+    /// The texts shown at the end of each episode.
+    pub end_articles: Vec<Article>,
+    /// The recorded demos, played in the attract loop.
+    pub demos: Vec<Demo>,
+    /// The ceiling and floor colors of each map.
+    pub level_colors: LevelColorTable,
+    pub pc_sounds: Vec<PcSound>,
+    pub adlib_sounds: Vec<AdLibSound>,
+    pub music: Vec<ImfMusic>,
+    pub digi_sounds: Vec<DigiSound>,
     pub game_type: &'static str,
+    /// The exact release of the game files (identified by their fingerprint).
+    pub version: GameVersion,
     pub is_sod: bool,
+    /// How long the loading took, and how much was read.
+    pub load_stats: LoadStats,
 }
 
 impl GameAssets {
-    pub fn load() -> Result<Self, String> {
-        // use a single, large buffer for all file loads
-        // (just me, complicating things ...)
-        let mut mutbuf = vec![0_u8; 2 * 1024 * 1024];
-
-        // load all asset files
-        let game_type = detect_game_type()?;
-        let maps = load_maps(game_type, &mut mutbuf)?;
-        let (walls, sprites) = load_vswap(game_type, &mut mutbuf)?;
-        let (font1, font2, pics) = load_pics(game_type, &mut mutbuf)?;
-        let pics = PicDict::new(game_type, pics);
-
-        // check if "Spear of Destiny"
-        let ch = game_type.bytes().next().unwrap_or(0);
-        let is_sod = ('S' as u8) == ch;
+    /// Load the assets from the first default directory which has them: the current directory, `data`, `assets`
+    /// or `/usr/share/games/wolf3d`.
+    pub fn load() -> Result<Self, AssetError> {
+        Self::load_from_dirs(DEFAULT_DIRS)
+    }
+
+    /// Load the assets from the given directory - and only from it (a wrong path must not load another game).
+    /// File names are matched ignoring their case (e.g. `VSWAP.WL6` or `vswap.wl6`).
+    pub fn load_from(path: &str) -> Result<Self, AssetError> {
+        Self::load_with_mods(path, &[])
+    }
+
+    /// Load the assets from the given directory (like `load_from`), with some mods on top of them.
+    /// Each mod is a directory or a ZIP/PK3 archive, which overrides some of the game files (see `LayeredSource`).
+    /// Later mods override the earlier ones.
+    pub fn load_with_mods(path: &str, mods: &[&str]) -> Result<Self, AssetError> {
+        let source = game_dir_source(path, mods)?;
+        match detect_game_files(&source) {
+            Some(files) => Self::load_game_files(&files),
+            None => Err(AssetError::FilesNotFound { dirs: path.to_string() }),
+        }
+    }
+
+    /// Load the assets from the first directory (in the given order) which contains all the asset files.
+    pub fn load_from_dirs(dirs: &[&str]) -> Result<Self, AssetError> {
+        for dir in dirs {
+            let source = game_dir_source(dir, &[])?;
+            if let Some(files) = detect_game_files(&source) {
+                return Self::load_game_files(&files);
+            }
+        }
+        Err(AssetError::FilesNotFound { dirs: dirs.join(", ") })
+    }
+
+    /// Load the assets from any asset source (e.g. a `LayeredSource`, with mods over the base game).
+    pub fn load_from_source(source: &dyn AssetSource) -> Result<Self, AssetError> {
+        match detect_game_files(source) {
+            Some(files) => Self::load_game_files(&files),
+            None => Err(AssetError::FilesNotFound {
+                dirs: source.describe(),
+            }),
+        }
+    }
+
+    fn load_game_files(files: &GameFiles) -> Result<Self, AssetError> {
+        let start_time = Instant::now();
+
+        // load all asset files - each file is read at its real size, and dropped as soon as it is parsed
+        // (except for the VSWAP walls and sprites, which are decoded lazily - see `GfxPages`)
+        let game_type = files.ext;
+        let vswap = load_file(files, VSWAP)?;
+        let version = identify_game_version(files, &vswap)?;
+        println!("[ROLF3D] Game version: {version}");
+        let is_sod = version.is_sod();
+
+        let maps = load_maps(files)?;
+        let (mut walls, mut sprites, digi_sounds) = load_vswap(files, vswap)?;
+        let palette = load_game_palette(files, is_sod)?;
+        let VgaGraph {
+            font1,
+            font2,
+            pics,
+            other_chunks,
+        } = load_pics(files)?;
+        let pic_table = load_pic_table(files, &version)?;
+        let first_other_chunk = FIRST_PIC_CHUNK + pics.len();
+        let mut pics = PicDict::new(&pic_table, pics);
+        load_pic_palettes(files, &pic_table, &mut pics, &other_chunks, first_other_chunk)?;
+        let (help_article, end_articles) = load_articles(files, &other_chunks, first_other_chunk, &version)?;
+        let demos = load_demos(&other_chunks, first_other_chunk, maps.len());
+        let level_colors = load_level_colors(files)?;
+        let (pc_sounds, adlib_sounds, music) = load_audio(files)?;
+
+        // replace walls and sprites, if there are replacement images (e.g. in the mods directory)
+        let cnt = load_gfx_replacements(files.source, &mut walls, &mut sprites, &palette)?;
+        if cnt > 0 {
+            println!("[ROLF3D] Replaced {cnt} walls/sprites");
+        }
+        let load_stats = LoadStats {
+            elapsed: start_time.elapsed(),
+            ..files.stats.get()
+        };
+        println!("[ROLF3D] {load_stats}");
 
         // build the asset holder
         Ok(Self {
@@ -55,8 +153,19 @@ impl GameAssets {
             font1,
             font2,
             pics,
+            palette,
+            help_article,
+            end_articles,
+            demos,
+            level_colors,
+            pc_sounds,
+            adlib_sounds,
+            music,
+            digi_sounds,
             game_type,
+            version,
             is_sod,
+            load_stats,
         })
     }
 
@@ -68,19 +177,91 @@ impl GameAssets {
         let delta = (4 - (weapon as usize)) * 5;
         self.sprites.len() - delta
     }
+
+    /// Get the digitized version of a sound effect, if the game has one.
+    pub fn digi_sound(&self, sound: SoundEffect) -> Option<&DigiSound> {
+        digi_sound_index(self.game_type, sound)
+            .and_then(|idx| self.digi_sounds.get(idx))
+            .filter(|digi| !digi.is_empty())
+    }
+}
+
+/// The contents of the VGAGRAPH file: the 2 fonts, the pics (in file order, unlike in `PicDict`)
+/// and the (Huffman-decoded) chunks which come after the pics.
+pub struct VgaGraph {
+    pub font1: FontData,
+    pub font2: FontData,
+    pub pics: Vec<GfxData>,
+    /// The first one has the 8x8 tiles, then come game specific chunks (screens, palettes, texts, demos etc)
+    pub other_chunks: Vec<Vec<u8>>,
+}
+
+impl VgaGraph {
+    /// Load only VGADICT, VGAHEAD and VGAGRAPH, from the given directory.
+    pub fn load_from(dir: &str) -> Result<Self, AssetError> {
+        let source = DirSource::new(dir);
+        let files = detect_game_files(&source).ok_or_else(|| AssetError::FilesNotFound { dirs: dir.to_string() })?;
+        load_pics(&files)
+    }
+}
+
+/// The VGAGRAPH chunk of the first pic: chunks #0..#2 are the pic sizes and the 2 fonts.
+/// (the text articles refer to pics by their chunk index)
+pub const FIRST_PIC_CHUNK: usize = 3;
+
+/// The size of one 8x8 tile. The chunk with the 8x8 tiles is the only one with an implicit size:
+/// it has as many tiles as the game needs (e.g. 35 in WL1).
+/// -> see CA_CacheGrChunk in ID_CA.C
+pub const TILE8_SIZE: usize = 8 * 8;
+
+/// Statistics about loading the assets - to measure the startup time and the memory needed for loading.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadStats {
+    /// The total time spent loading (and decoding) the assets
+    pub elapsed: Duration,
+    pub files_read: usize,
+    pub bytes_read: usize,
+    /// The size of the largest file - at most this much is held in memory as raw file bytes, while loading
+    pub largest_file: usize,
+}
+
+impl fmt::Display for LoadStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Loaded {} files ({} KB, the largest has {} KB) in {} ms",
+            self.files_read,
+            self.bytes_read / 1024,
+            self.largest_file / 1024,
+            self.elapsed.as_millis()
+        )
+    }
 }
 
 //----------------------
 //  Internal stuff
 //----------------------
 
-/// All the supported asset file extensions.<br>
-/// **Note:** WL3 is *not supported* because I don't know its VGAGRAPH order of pics :/
-const EXTENSIONS: &[&'static str] = &["WL6", "WL1", "SOD", "SD1", "SD2", "SD3", "SDM"];
-// TODO - when checking/loading files, must also try lowercased file names (for LINUX compatibility)
+/// All the supported asset file extensions.
+const EXTENSIONS: &[&'static str] = &["WL6", "WL3", "WL1", "SOD", "SD1", "SD2", "SD3", "SDM"];
+
+/// The (optional) file which overrides the built-in pic table - see `PicTable::parse`.
+const PIC_TABLE_FILE: &str = "PICDICT";
+
+/// The (optional) file which overrides the built-in level colors - see `LevelColorTable::apply`.
+const LEVEL_COLORS_FILE: &str = "LEVELCOL";
+
+/// The (optional) files which override the built-in game palette, in the order they are searched.
+/// See `Palette::parse` for the supported formats.
+const PALETTE_FILES: &[&str] = &["GAMEPAL.OBJ", "GAMEPAL.PAL", "GAMEPAL.LMP"];
+
+/// Directories to search for the asset files, when no directory is given (see `GameAssets::load`).
+const DEFAULT_DIRS: &[&str] = &[".", "data", "assets", "/usr/share/games/wolf3d"];
 
 /// All the supported asset file names.
-const FILES: &[&'static str] = &["MAPHEAD", "GAMEMAPS", "VGADICT", "VGAHEAD", "VGAGRAPH", "VSWAP"];
+const FILES: &[&'static str] = &[
+    "MAPHEAD", "GAMEMAPS", "VGADICT", "VGAHEAD", "VGAGRAPH", "VSWAP", "AUDIOHED", "AUDIOT",
+];
 
 // Indexes of each name in the above array - to reuse the strings :)
 const MAPHEAD: usize = 0;
@@ -89,272 +270,451 @@ const VGADICT: usize = 2;
 const VGAHEAD: usize = 3;
 const VGAGRAPH: usize = 4;
 const VSWAP: usize = 5;
+const AUDIOHED: usize = 6;
+const AUDIOT: usize = 7;
+
+/// Files which may be missing - the game can still be played without sound :)
+const OPTIONAL_FILES: &[usize] = &[AUDIOHED, AUDIOT];
+
+/// The asset files found for a game type: the source + the extension + the full name of each file in `FILES`.
+/// Optional files which were not found have no name.
+struct GameFiles<'a> {
+    source: &'a dyn AssetSource,
+    ext: &'static str,
+    names: Vec<Option<String>>,
+    stats: Cell<LoadStats>,
+}
+
+impl GameFiles<'_> {
+    #[inline]
+    fn has_file(&self, nameidx: usize) -> bool {
+        self.names[nameidx].is_some()
+    }
+
+    #[inline]
+    fn filename(&self, nameidx: usize) -> String {
+        format!("{}.{}", FILES[nameidx], self.ext)
+    }
+}
+
+/// The source for a game directory: the directory itself, then the replacements directory (if it exists)
+/// and then the mods, in order.
+fn game_dir_source(dir: &str, mods: &[&str]) -> Result<LayeredSource, AssetError> {
+    let mut source = LayeredSource::new();
+    source.push(Box::new(DirSource::new(dir)));
+    let mods_dir = Path::new(dir).join(REPLACEMENTS_DIR);
+    if mods_dir.is_dir() {
+        source.push(Box::new(DirSource::new(mods_dir)));
+    }
+    for path in mods {
+        source.push(open_asset_source(Path::new(path))?);
+    }
+    Ok(source)
+}
 
 /// Detect the game type, by checking if all asset files for each supported extension are found.
-fn detect_game_type() -> Result<&'static str, String> {
+/// File names are matched ignoring their case.
+fn detect_game_files(source: &dyn AssetSource) -> Option<GameFiles<'_>> {
     for ext in EXTENSIONS {
-        let all_game_files_exist = FILES.iter().all(|f| {
-            let filename = format!("{}.{}", f, ext);
-            file_exist(&filename)
-        });
-        if all_game_files_exist {
-            return Ok(*ext);
+        let names: Vec<Option<String>> = FILES.iter().map(|f| source.locate(&format!("{}.{}", f, ext))).collect();
+        let all_found = names
+            .iter()
+            .enumerate()
+            .all(|(idx, name)| name.is_some() || OPTIONAL_FILES.contains(&idx));
+        if all_found {
+            println!("[ROLF3D] Found {ext} game files in: {}", source.describe());
+            return Some(GameFiles {
+                source,
+                ext,
+                names,
+                stats: Cell::new(LoadStats::default()),
+            });
         }
     }
-    Err(String::from("Game asset files not found"))
+    None
+}
+
+/// Identify the exact release of the game files, by their fingerprint.
+fn identify_game_version(files: &GameFiles, vswap: &[u8]) -> Result<GameVersion, AssetError> {
+    let vgahead = load_file(files, VGAHEAD)?;
+    let maphead = load_file(files, MAPHEAD)?;
+    let Some(fingerprint) = Fingerprint::new(&vgahead, &maphead, vswap) else {
+        let vswap = AssetFile::new(files, VSWAP, vswap);
+        return Err(vswap.error(None, 0, "VSWAP header is truncated"));
+    };
+    Ok(GameVersion::identify(files.ext, fingerprint))
+}
+
+/// Get the pic table for the game: from the `PICDICT.ext` file next to the game files, if there is one,
+/// otherwise the built-in table for the release.
+fn load_pic_table(files: &GameFiles, version: &GameVersion) -> Result<PicTable, AssetError> {
+    let filename = format!("{PIC_TABLE_FILE}.{}", files.ext);
+    let Some(file) = files.source.locate(&filename) else {
+        return Ok(PicTable::builtin(files.ext, version.release));
+    };
+    let bytes = files.source.read(&filename)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let table = PicTable::parse(&text)
+        .map_err(|(line, reason)| AssetError::bad_data(&file, None, 0, format!("line {line}: {reason}")))?;
+    println!("[ROLF3D] Loaded the pic table from: {file}");
+    Ok(table)
+}
+
+/// Get the level colors: the built-in table for the game, with the changes from the `LEVELCOL.ext` file
+/// next to the game files, if there is one.
+fn load_level_colors(files: &GameFiles) -> Result<LevelColorTable, AssetError> {
+    let mut table = LevelColorTable::builtin(files.ext);
+    let filename = format!("{LEVEL_COLORS_FILE}.{}", files.ext);
+    let Some(file) = files.source.locate(&filename) else {
+        return Ok(table);
+    };
+    let bytes = files.source.read(&filename)?;
+    let text = String::from_utf8_lossy(&bytes);
+    table
+        .apply(&text)
+        .map_err(|(line, reason)| AssetError::bad_data(&file, None, 0, format!("line {line}: {reason}")))?;
+    println!("[ROLF3D] Loaded the level colors from: {file}");
+    Ok(table)
+}
+
+/// Get the game palette: from a palette file next to the game files (see `PALETTE_FILES`), if there is one,
+/// otherwise the built-in palette.
+fn load_game_palette(files: &GameFiles, is_sod: bool) -> Result<Palette, AssetError> {
+    let Some((filename, file)) = PALETTE_FILES
+        .iter()
+        .find_map(|name| files.source.locate(name).map(|file| (*name, file)))
+    else {
+        return Ok(Palette::builtin(is_sod));
+    };
+    let bytes = files.source.read(filename)?;
+    let palette = Palette::parse(&bytes).map_err(|reason| AssetError::bad_data(&file, None, 0, reason))?;
+    println!("[ROLF3D] Loaded the palette from: {file}");
+    Ok(palette)
+}
+
+/// Load the palettes of the pics which have their own, from the VGAGRAPH chunks given in the pic table.
+/// `first_other_chunk` is the chunk index of `other_chunks[0]`.
+fn load_pic_palettes(
+    files: &GameFiles,
+    table: &PicTable,
+    pics: &mut PicDict,
+    other_chunks: &[Vec<u8>],
+    first_other_chunk: usize,
+) -> Result<(), AssetError> {
+    for &(typ, chunk_idx) in table.palette_chunks() {
+        let bytes = chunk_idx
+            .checked_sub(first_other_chunk)
+            .and_then(|idx| other_chunks.get(idx))
+            .ok_or_else(|| {
+                let vgagraph = AssetFile::new(files, VGAGRAPH, &[]);
+                vgagraph.error(Some(chunk_idx), 0, format!("No palette chunk for {typ}"))
+            })?;
+        let palette = Palette::from_vga_bytes(bytes).map_err(|reason| {
+            let vgagraph = AssetFile::new(files, VGAGRAPH, bytes);
+            vgagraph.error(Some(chunk_idx), 0, reason)
+        })?;
+        pics.set_pic_palette(typ, palette);
+    }
+    Ok(())
+}
+
+/// Find and parse the text articles among the VGAGRAPH chunks - they are the chunks which start with `^P`.
+/// In Wolf3D, the first one is the help, and the others are the end-of-episode texts
+/// (SOD has no help, and the GT/Activision releases do not show it - see `GameVersion::has_help`).
+fn load_articles(
+    files: &GameFiles,
+    other_chunks: &[Vec<u8>],
+    first_other_chunk: usize,
+    version: &GameVersion,
+) -> Result<(Option<Article>, Vec<Article>), AssetError> {
+    let mut articles = vec![];
+    for (idx, bytes) in other_chunks.iter().enumerate() {
+        if Article::is_article(bytes) {
+            let article = Article::parse(bytes).map_err(|reason| {
+                let vgagraph = AssetFile::new(files, VGAGRAPH, bytes);
+                vgagraph.error(Some(first_other_chunk + idx), 0, reason)
+            })?;
+            articles.push(article);
+        }
+    }
+    let help = if version.is_sod() || articles.is_empty() {
+        None
+    } else {
+        // the GT/Activision releases have the help chunk too - they just do not show it
+        Some(articles.remove(0)).filter(|_| version.has_help())
+    };
+    Ok((help, articles))
+}
+
+/// Find and parse the demos among the VGAGRAPH chunks (see `Demo::is_demo`).
+/// The demos for maps which are not in the game files (e.g. a demo of the full game, in the shareware) are skipped.
+fn load_demos(other_chunks: &[Vec<u8>], first_other_chunk: usize, cnt_maps: usize) -> Vec<Demo> {
+    let mut demos = vec![];
+    for (idx, bytes) in other_chunks.iter().enumerate() {
+        if let Ok(demo) = Demo::parse(bytes) {
+            if demo.map < cnt_maps {
+                demos.push(demo);
+            } else {
+                let chunk_idx = first_other_chunk + idx;
+                println!(
+                    "[ROLF3D] Skipped the demo in chunk {chunk_idx}: there is no map {}",
+                    demo.map
+                );
+            }
+        }
+    }
+    demos
 }
 
 //----------------------
 // Page loader (VSWAP)
 //----------------------
 
-/// Load and parse the VSWAP.ext file.
-/// Returns 2 vectors of graphics: the walls and the sprites.
-fn load_vswap(ext: &str, mutbuf: &mut [u8]) -> Result<(Vec<GfxData>, Vec<GfxData>), String> {
-    let vs_len = load_file(VSWAP, ext, mutbuf)?;
-    let vswap = &mutbuf[0..vs_len];
+/// The VSWAP pages: walls, sprites and digitized sounds.
+type LoadedPages = (GfxPages, GfxPages, Vec<DigiSound>);
+
+/// Parse the VSWAP.ext file (its bytes are already loaded).
+/// Returns 2 lists of graphics: the walls and the sprites + the digitized sounds.
+/// The walls and sprites are only checked here, and decoded on first use.
+fn load_vswap(files: &GameFiles, bytes: Vec<u8>) -> Result<LoadedPages, AssetError> {
+    let vswap = AssetFile::new(files, VSWAP, &bytes);
     // read the 3 counters
-    let cnt_chunks_in_file = buf_to_u16(&vswap[0..2]) as usize;
-    let idx_sprite_start = buf_to_u16(&vswap[2..4]) as usize;
-    let idx_sound_start = buf_to_u16(&vswap[4..6]) as usize;
+    let cnt_chunks_in_file = vswap.u16_at(0, None)? as usize;
+    let idx_sprite_start = vswap.u16_at(2, None)? as usize;
+    let idx_sound_start = vswap.u16_at(4, None)? as usize;
+    if idx_sprite_start > idx_sound_start || idx_sound_start > cnt_chunks_in_file {
+        return Err(vswap.error(None, 2, "Invalid sprite/sound start indexes"));
+    }
     // read the offsets to each chunk
     let mut idx = 6;
     let mut vec_offsets = Vec::with_capacity(cnt_chunks_in_file);
     for _ in 0..cnt_chunks_in_file {
-        let ofs = buf_to_u32(&vswap[idx..]) as usize;
+        let ofs = vswap.u32_at(idx, None)? as usize;
         vec_offsets.push(ofs);
         idx += 4;
     }
     // read the lengths for each chunk
     let mut vec_lengths = Vec::with_capacity(cnt_chunks_in_file);
     for _ in 0..cnt_chunks_in_file {
-        let len = buf_to_u16(&vswap[idx..]) as usize;
+        let len = vswap.u16_at(idx, None)? as usize;
         vec_lengths.push(len);
         idx += 2;
     }
-    // first come the wall chunks
-    let mut vec_walls = Vec::with_capacity(idx_sprite_start);
-    let mut cnt = 0;
-    for i in 0..idx_sprite_start {
-        let ofs = vec_offsets[i];
-        let len = vec_lengths[i];
+    // check the wall and sprite chunks - all walls (flats) should be 64x64
+    let mut gfx_end = 0;
+    for i in 0..idx_sound_start {
+        let (ofs, len) = (vec_offsets[i], vec_lengths[i]);
         if ofs > 0 && len > 0 {
-            // sanity check - all walls (flats) should be 64x64
-            assert_eq!(64 * 64, len);
-            // read the wall - it is stored as columns
-            let pixels = vswap[ofs..ofs + len].iter().cloned().collect();
-            vec_walls.push(GfxData::new_sprite(pixels));
-            cnt += 1;
-        } else {
-            vec_walls.push(GfxData::new_empty());
+            if i < idx_sprite_start && len != 64 * 64 {
+                return Err(vswap.error(Some(i), ofs, format!("Wall has {len} bytes instead of 64x64")));
+            }
+            vswap.slice(ofs, len, Some(i))?;
+            gfx_end = gfx_end.max(ofs + len);
         }
     }
-    println!("[ROLF3D] Loaded {cnt}/{} wall flats", vec_walls.len());
+    // keep only the graphics part of the file, for decoding the pages later
+    let raw: Rc<[u8]> = Rc::from(&bytes[0..gfx_end]);
+    let lazy_pages = |range: std::ops::Range<usize>, decoder: PageDecoder| {
+        let mut pages = GfxPages::new(&vswap.name, Rc::clone(&raw));
+        for i in range {
+            let (ofs, len) = (vec_offsets[i], vec_lengths[i]);
+            if ofs > 0 && len > 0 {
+                pages.push_lazy(i, ofs, len, decoder);
+            } else {
+                pages.push(GfxData::new_empty());
+            }
+        }
+        pages
+    };
 
-    // next come the sprites
-    cnt = 0;
-    let mut vec_sprites = Vec::with_capacity(idx_sound_start - idx_sprite_start);
-    for i in idx_sprite_start..idx_sound_start {
-        let ofs = vec_offsets[i];
-        let len = vec_lengths[i];
-        if ofs > 0 && len > 0 {
-            let pixels = parse_sprite(&vswap[ofs..ofs + len]);
-            vec_sprites.push(GfxData::new_sprite(pixels));
-            cnt += 1;
+    // first come the wall chunks, then the sprites
+    let walls = lazy_pages(0..idx_sprite_start, decode_wall_page);
+    let sprites = lazy_pages(idx_sprite_start..idx_sound_start, decode_sprite_page);
+    let cnt_gfx = |range: std::ops::Range<usize>| range.filter(|&i| vec_offsets[i] > 0 && vec_lengths[i] > 0).count();
+    println!(
+        "[ROLF3D] Found {}/{} wall flats",
+        cnt_gfx(0..idx_sprite_start),
+        walls.len()
+    );
+    println!(
+        "[ROLF3D] Found {}/{} sprites",
+        cnt_gfx(idx_sprite_start..idx_sound_start),
+        sprites.len()
+    );
+
+    // finally, the sound pages
+    let digi_sounds = parse_digi_sounds(&vswap, &vec_offsets, &vec_lengths, idx_sound_start)?;
+    let cnt = digi_sounds.iter().filter(|digi| !digi.is_empty()).count();
+    println!("[ROLF3D] Loaded {cnt}/{} digitized sounds", digi_sounds.len());
+
+    Ok((walls, sprites, digi_sounds))
+}
+
+/// Walls are stored as columns, just like `GfxData`.
+fn decode_wall_page(bytes: &[u8]) -> Result<GfxData, DecodeError> {
+    Ok(GfxData::new_sprite(bytes.to_vec()))
+}
+
+fn decode_sprite_page(bytes: &[u8]) -> Result<GfxData, DecodeError> {
+    decode_sprite(bytes).map(GfxData::new_sprite)
+}
+
+/// Stitch the sound pages into digitized sounds.
+/// The last page is the sound info table: for each sound, its start page (relative to the first sound page)
+/// and its length (only the lower 16 bits). Each sound lasts until the start page of the next one.
+/// -> see SD_SetupDigi in Wolf4SDL (id_sd.cpp)
+fn parse_digi_sounds(
+    vswap: &AssetFile,
+    offsets: &[usize],
+    lengths: &[usize],
+    idx_sound_start: usize,
+) -> Result<Vec<DigiSound>, AssetError> {
+    if idx_sound_start >= offsets.len() {
+        // no sound pages at all
+        return Ok(vec![]);
+    }
+    let idx_info = offsets.len() - 1;
+    let info = vswap.slice(offsets[idx_info], lengths[idx_info], Some(idx_info))?;
+    let cnt_digi = info.len() / 4;
+    let start_page = |i: usize| idx_sound_start + buf_to_u16(&info[4 * i..]) as usize;
+
+    let mut sounds = Vec::with_capacity(cnt_digi);
+    for i in 0..cnt_digi {
+        let first_page = start_page(i);
+        if first_page >= idx_info {
+            // the rest of the info table is unused
+            break;
+        }
+        let next_page = if i + 1 < cnt_digi { start_page(i + 1) } else { idx_info };
+        let end_page = if next_page > first_page && next_page <= idx_info {
+            next_page
         } else {
-            vec_sprites.push(GfxData::new_empty());
+            idx_info
+        };
+
+        // sounds which are not in the file (e.g. in the shareware version) have no pages
+        if (first_page..end_page).any(|page| offsets[page] == 0) {
+            sounds.push(DigiSound::default());
+            continue;
         }
-    }
-    println!("[ROLF3D] Loaded {cnt}/{} sprites", vec_sprites.len());
-
-    Ok((vec_walls, vec_sprites))
-}
-
-fn parse_sprite(compressed: &[u8]) -> Vec<u8> {
-    let mut pixels = vec![0xFF; 64 * 64];
-    // the first 2 words = the left and right extents of the sprite
-    let left_extent = buf_to_u16(&compressed[0..2]) as usize;
-    let right_extent = buf_to_u16(&compressed[2..4]) as usize;
-
-    // the next N words are the offsets for each column,
-    // then come the textels packed together (one byte each),
-    // and then come the "commands" for each column
-    // (one word each, zero-terminated for each sub-column)
-
-    // moving index into the offsets to the command area for each column
-    let mut ofsidx = 4;
-    // moving index into the texel area
-    let mut texidx = 4 + 2 * (right_extent - left_extent + 1);
-
-    // compute texels for each column
-    // -> see https://devinsmith.net/backups/bruce/wolf3d.html
-    for x in left_extent..=right_extent {
-        // offset to the column start, into the destination vector
-        let destidx = x * 64;
-        // read the offset into the command area for this column
-        let mut column_ofs = buf_to_u16(&compressed[ofsidx..]) as usize;
-        ofsidx += 2;
-        // keep reading commands for the column
-        // each command is 3 words: end_y * 2, ignored, start_y * 2
-        loop {
-            let end_y = (buf_to_u16(&compressed[column_ofs..]) / 2) as usize;
-            if end_y == 0 {
-                break;
-            }
-            let start_y = (buf_to_u16(&compressed[column_ofs + 4..]) / 2) as usize;
-            column_ofs += 6;
-            for y in start_y..end_y {
-                let tex = compressed[texidx];
-                assert_ne!(0xFF, tex);
-                pixels[destidx + y] = tex;
-                texidx += 1;
-            }
+
+        // stitch the pages together
+        let mut samples = vec![];
+        for page in first_page..end_page {
+            samples.extend_from_slice(vswap.slice(offsets[page], lengths[page], Some(page))?);
+        }
+
+        // the pages are padded, but the info table only has the lower 16 bits of the length
+        // => patch the lower 16 bits of the total size with the length from the info table
+        let info_len = buf_to_u16(&info[4 * i + 2..]) as usize;
+        let mut size = samples.len();
+        if size > 0xFFFF && (size & 0xFFFF) < info_len {
+            size -= 0x10000;
         }
+        let size = (size & !0xFFFF) | info_len;
+        if size > samples.len() {
+            let reason = format!("Digitized sound #{i} is longer than its pages: {size} bytes");
+            return Err(vswap.error(Some(idx_info), offsets[idx_info] + 4 * i, reason));
+        }
+        samples.truncate(size);
+        sounds.push(DigiSound { samples });
     }
 
-    pixels
+    Ok(sounds)
 }
 
 //---------------------------------
 // Map loader - MAPHEAD, GAMEMAPS
 //---------------------------------
 
-fn load_maps(ext: &str, mutbuf: &mut [u8]) -> Result<Vec<MapData>, String> {
+fn load_maps(files: &GameFiles) -> Result<Vec<MapData>, AssetError> {
     // load files
-    let mh_len = load_file(MAPHEAD, ext, mutbuf)?;
-    let gm_len = load_file(GAMEMAPS, ext, &mut mutbuf[mh_len..])?;
-    let maphead = &mutbuf[0..mh_len];
-    let gamemaps = &mutbuf[mh_len..mh_len + gm_len];
-    let rlew_tag = buf_to_u16(&maphead[0..2]);
+    let mh_bytes = load_file(files, MAPHEAD)?;
+    let gm_bytes = load_file(files, GAMEMAPS)?;
+    let maphead = AssetFile::new(files, MAPHEAD, &mh_bytes);
+    let gamemaps = AssetFile::new(files, GAMEMAPS, &gm_bytes);
+    let rlew_tag = maphead.u16_at(0, None)?;
     // read each map
     let mut maps = vec![];
     let mut idx = 2;
-    while (idx + 3) < maphead.len() {
-        let mapidx = buf_to_i32(&maphead[idx..]);
+    while (idx + 3) < maphead.bytes.len() {
+        let mapidx = maphead.u32_at(idx, None)? as i32;
         if mapidx <= 0 {
             break;
         }
 
         // ok to read map
-        let map = load_one_map(mapidx as usize, &gamemaps, rlew_tag)?;
+        let map = load_one_map(maps.len(), mapidx as usize, &gamemaps, rlew_tag)?;
         maps.push(map);
         idx += 4;
     }
 
-    println!("[ROLF3D] Loaded {} maps of type {}", maps.len(), ext);
+    println!("[ROLF3D] Loaded {} maps of type {}", maps.len(), files.ext);
     Ok(maps)
 }
 
-fn load_one_map(hdridx: usize, gamemaps: &[u8], rlew_tag: u16) -> Result<MapData, String> {
+fn load_one_map(mapno: usize, hdridx: usize, gamemaps: &AssetFile, rlew_tag: u16) -> Result<MapData, AssetError> {
     // parse map header
-    if gamemaps.len() <= (hdridx + 26) {
-        return Err(format!("Invalid map header index: {hdridx}"));
-    }
+    let header = gamemaps.slice(hdridx, 38, Some(mapno))?;
 
     // offsets and compressed lengths for each of the 3 planes
-    // * ignore plane 3, it is always ZERO
-    // * also ignore compressed lengths (encoded data also contains len of decoded data)
-    let ofs_plane_1 = buf_to_i32(&gamemaps[hdridx..]);
-    let ofs_plane_2 = buf_to_i32(&gamemaps[hdridx + 4..]);
+    // * plane 3 is optional (it is always ZERO in the original games, but extended editors use it)
+    // * ignore the other compressed lengths (encoded data also contains len of decoded data)
+    let ofs_plane_1 = buf_to_i32(&header[0..]);
+    let ofs_plane_2 = buf_to_i32(&header[4..]);
+    let ofs_plane_3 = buf_to_i32(&header[8..]);
+    let len_plane_3 = buf_to_u16(&header[16..]);
 
     // map size and name
-    let width = buf_to_u16(&gamemaps[hdridx + 18..]);
-    let height = buf_to_u16(&gamemaps[hdridx + 20..]);
-    let name = buf_to_ascii(&gamemaps[hdridx + 22..], 16);
+    let width = buf_to_u16(&header[18..]);
+    let height = buf_to_u16(&header[20..]);
+    let name = buf_to_ascii(&header[22..], 16);
 
     // parse each plane
     if ofs_plane_1 <= 0 || ofs_plane_2 <= 0 {
-        return Err(format!("Missing plane in GAMEMAPS for {name} @ 0x{hdridx:04X}"));
+        return Err(gamemaps.error(Some(mapno), hdridx, format!("Missing plane for map {name}")));
     }
     let ofs1 = ofs_plane_1 as usize;
     let ofs2 = ofs_plane_2 as usize;
-    let walls = decompress_map_plane(&gamemaps[ofs1..], rlew_tag);
-    let things = decompress_map_plane(&gamemaps[ofs2..], rlew_tag);
-
-    // return the map data
-    Ok(MapData::new(name, width, height, walls, things))
-}
-
-/// Use Carmack and RLEW decompression, to extract the map plane
-fn decompress_map_plane(chunk: &[u8], rlew_tag: u16) -> Vec<u16> {
-    // first de-Carmack ...
-    // first word of the Carmack-ed chunk is the decompressed length, in bytes
-    let intermed_word_cnt = (buf_to_u16(chunk) / 2) as usize;
-    let mut intermediate = Vec::with_capacity(intermed_word_cnt);
-    let mut idx = 2;
-    while intermediate.len() < intermed_word_cnt {
-        let b1 = chunk[idx];
-        let b2 = chunk[idx + 1];
-        if (b2 == 0xA7 || b2 == 0xA8) && b1 == 0 {
-            // Carmack-style escape sequence
-            let b1 = chunk[idx + 2];
-            let w = (b1 as u16) | ((b2 as u16) << 8);
-            intermediate.push(w);
-            idx += 3;
-        } else if b2 == 0xA7 {
-            // Carmack-style near pointer
-            let offs = intermediate.len() - (chunk[idx + 2] as usize);
-            idx += 3;
-            for i in 0..b1 as usize {
-                intermediate.push(intermediate[offs + i]);
-            }
-        } else if b2 == 0xA8 {
-            // Carmack-style far pointer
-            let offs = buf_to_u16(&chunk[idx + 2..]) as usize;
-            idx += 4;
-            for i in 0..b1 as usize {
-                intermediate.push(intermediate[offs + i]);
-            }
-        } else {
-            // normal word
-            let val = (b1 as u16) | ((b2 as u16) << 8);
-            intermediate.push(val);
-            idx += 2;
-        }
-    }
+    let walls = decompress_map_plane(gamemaps.slice_from(ofs1, Some(mapno))?, rlew_tag)
+        .map_err(|e| gamemaps.decode_error(Some(mapno), ofs1, e))?;
+    let things = decompress_map_plane(gamemaps.slice_from(ofs2, Some(mapno))?, rlew_tag)
+        .map_err(|e| gamemaps.decode_error(Some(mapno), ofs2, e))?;
 
-    // ... and then decompress RLEW
-    // first word of the RLEW-ed data is the decompressed length, in bytes
-    let decoded_word_cnt = (intermediate[0] / 2) as usize;
-    let mut plane = Vec::with_capacity(decoded_word_cnt);
-    let mut idx = 1;
-    while plane.len() < decoded_word_cnt {
-        let next = intermediate[idx];
-        if next == rlew_tag {
-            // RLEW sequence
-            let cnt = intermediate[idx + 1];
-            let val = intermediate[idx + 2];
-            idx += 3;
-            for _ in 0..cnt {
-                plane.push(val);
-            }
-        } else {
-            // normal word
-            idx += 1;
-            plane.push(next);
-        }
+    let map = MapData::new(name, width, height, walls, things)
+        .map_err(|reason| gamemaps.error(Some(mapno), hdridx, reason))?;
+    if ofs_plane_3 <= 0 || len_plane_3 == 0 {
+        return Ok(map);
     }
-
-    plane
+    let ofs3 = ofs_plane_3 as usize;
+    let infos = decompress_map_plane(gamemaps.slice_from(ofs3, Some(mapno))?, rlew_tag)
+        .map_err(|e| gamemaps.decode_error(Some(mapno), ofs3, e))?;
+    map.with_info_plane(infos)
+        .map_err(|reason| gamemaps.error(Some(mapno), hdridx, reason))
 }
 
 //--------------------------------------------
 // Pic loader - VGADICT, VGAHEAD, VGAGRAPH
 //--------------------------------------------
 
-fn load_pics(ext: &str, mutbuf: &mut [u8]) -> Result<(FontData, FontData, Vec<GfxData>), String> {
+fn load_pics(files: &GameFiles) -> Result<VgaGraph, AssetError> {
     // load the 3 files ...
-    let len1 = load_file(VGADICT, ext, mutbuf)?;
-    assert_eq!(1024, len1);
-    let len2 = load_file(VGAHEAD, ext, &mut mutbuf[len1..])?;
-    let len3 = load_file(VGAGRAPH, ext, &mut mutbuf[len1 + len2..])?;
-    // ... then split into buffers ...
-    let vgadict = &mutbuf[0..len1];
-    let vgahead = &mutbuf[len1..len1 + len2];
-    let vgagraph = &mutbuf[len1 + len2..len1 + len2 + len3];
+    let dict_bytes = load_file(files, VGADICT)?;
+    let head_bytes = load_file(files, VGAHEAD)?;
+    let graph_bytes = load_file(files, VGAGRAPH)?;
+    let (len1, len2, len3) = (dict_bytes.len(), head_bytes.len(), graph_bytes.len());
+    // ... then wrap them ...
+    let vgadict = AssetFile::new(files, VGADICT, &dict_bytes);
+    let vgahead = AssetFile::new(files, VGAHEAD, &head_bytes);
+    let vgagraph = AssetFile::new(files, VGAGRAPH, &graph_bytes);
+    if len1 != 1024 {
+        return Err(vgadict.error(None, 0, format!("Huffman dictionary has {len1} bytes instead of 1024")));
+    }
     // ... and prepare the results vector
+    if len2 < 3 * 4 || len2 % 3 != 0 {
+        return Err(vgahead.error(None, 0, format!("Invalid file length: {len2} bytes")));
+    }
     let cnt_chunks = (len2 / 3) - 1;
-    assert_eq!(cnt_chunks * 3 + 3, len2);
 
     // the VGADICT file is an array of WORD pairs - first for bit=0, second for bit=1:
     //     struct huffnode { unsigned bit0, bit1; }  --> 0-255 is a character, > is a pointer to a node
@@ -362,19 +722,25 @@ fn load_pics(ext: &str, mutbuf: &mut [u8]) -> Result<(FontData, FontData, Vec<Gf
     // we just collect them as a list of words, and each pair of words corresponds to (bit0, bit1)
     let mut huffnodes = Vec::with_capacity(len1 / 2);
     for i in 0..len1 / 2 {
-        huffnodes.push(buf_to_u16(&vgadict[2 * i..]));
+        huffnodes.push(vgadict.u16_at(2 * i, None)?);
     }
-    assert_eq!(512, huffnodes.len());
 
     // the VGAHEAD file is an array of 3-byte, little endian offsets into VGAGRAPH
     // the first is 0, the last one is the offset of the end of the VGAGRAPH file
     // we just collect them as a list of offsets
     let mut offsets = Vec::with_capacity(1 + cnt_chunks);
     for i in 0..=cnt_chunks {
-        let b1 = vgahead[3 * i] as usize;
-        let b2 = vgahead[3 * i + 1] as usize;
-        let b3 = vgahead[3 * i + 2] as usize;
+        let b1 = vgahead.bytes[3 * i] as usize;
+        let b2 = vgahead.bytes[3 * i + 1] as usize;
+        let b3 = vgahead.bytes[3 * i + 2] as usize;
         let ofs = b1 | (b2 << 8) | (b3 << 16);
+        if ofs > len3 {
+            return Err(vgahead.error(
+                Some(i),
+                3 * i,
+                format!("Chunk offset 0x{ofs:X} is past the end of VGAGRAPH"),
+            ));
+        }
         offsets.push(ofs);
     }
 
@@ -386,8 +752,8 @@ fn load_pics(ext: &str, mutbuf: &mut [u8]) -> Result<(FontData, FontData, Vec<Gf
     // It contains NUMPIC entries: for each PIC entry => 2 words: (width, height)
     let o1 = offsets[0];
     let o2 = offsets[1];
-    let bytes = &vgagraph[o1..o2];
-    let decoded = huff_decode_chunk(bytes, &huffnodes);
+    let bytes = vgagraph.slice(o1, o2.saturating_sub(o1), Some(0))?;
+    let decoded = huff_decode_chunk(bytes, &huffnodes).map_err(|e| vgagraph.decode_error(Some(0), o1, e))?;
     let cnt_words = decoded.len() / 2;
     let mut pic_sizes = Vec::with_capacity(cnt_words);
     for i in 0..cnt_words {
@@ -399,60 +765,99 @@ fn load_pics(ext: &str, mutbuf: &mut [u8]) -> Result<(FontData, FontData, Vec<Gf
     let o1 = offsets[1];
     let o2 = offsets[2];
     // font #1
-    let bytes = &vgagraph[o1..];
-    let fontdata = huff_decode_chunk(bytes, &huffnodes);
-    let font1 = parse_font(&fontdata);
+    let bytes = vgagraph.slice_from(o1, Some(1))?;
+    let fontdata = huff_decode_chunk(bytes, &huffnodes).map_err(|e| vgagraph.decode_error(Some(1), o1, e))?;
+    let font1 = parse_font(&fontdata).map_err(|(ofs, reason)| vgagraph.error(Some(1), o1 + ofs, reason))?;
     // font #2
-    let bytes = &vgagraph[o2..];
-    let fontdata = huff_decode_chunk(bytes, &huffnodes);
-    let font2 = parse_font(&fontdata);
+    let bytes = vgagraph.slice_from(o2, Some(2))?;
+    let fontdata = huff_decode_chunk(bytes, &huffnodes).map_err(|e| vgagraph.decode_error(Some(2), o2, e))?;
+    let font2 = parse_font(&fontdata).map_err(|(ofs, reason)| vgagraph.error(Some(2), o2 + ofs, reason))?;
 
     // decode each pic - they start from chunk #3
     let cnt_pics = cnt_words / 2;
+    if cnt_pics + 3 > cnt_chunks {
+        return Err(vgagraph.error(Some(0), o1, format!("Too many pics: {cnt_pics}")));
+    }
     let mut pics = Vec::with_capacity(cnt_pics);
     for i in 0..cnt_pics {
         let o1 = offsets[i + 3];
-        let bytes = &vgagraph[o1..];
+        let bytes = vgagraph.slice_from(o1, Some(i + 3))?;
         let width = pic_sizes[2 * i];
         let height = pic_sizes[2 * i + 1];
-        let mut pixels = huff_decode_chunk(bytes, &huffnodes);
+        let mut pixels = huff_decode_chunk(bytes, &huffnodes).map_err(|e| vgagraph.decode_error(Some(i + 3), o1, e))?;
+        if width % 4 != 0 || (width as usize) * (height as usize) != pixels.len() {
+            let reason = format!("Pic size {width}x{height} does not match its {} bytes", pixels.len());
+            return Err(vgagraph.error(Some(i + 3), o1, reason));
+        }
         munge_pic(width, height, &mut pixels);
         let pic = GfxData::new_pic(width, height, pixels);
         pics.push(pic);
     }
     println!("[ROLF3D] Loaded {cnt_pics} pics");
 
-    Ok((font1, font2, pics))
+    // the rest of the chunks - the first one (8x8 tiles) has an implicit size, and empty chunks are allowed
+    let mut other_chunks = Vec::with_capacity(cnt_chunks - cnt_pics - 3);
+    for i in cnt_pics + 3..cnt_chunks {
+        let o1 = offsets[i];
+        let bytes = vgagraph.slice(o1, offsets[i + 1].saturating_sub(o1), Some(i))?;
+        let decoded = if bytes.is_empty() {
+            Ok(vec![])
+        } else if i == cnt_pics + 3 {
+            // drop the garbage decoded from the padding bits => keep only whole tiles
+            huff_decode_all(bytes, &huffnodes).map(|mut tiles| {
+                tiles.truncate(tiles.len() / TILE8_SIZE * TILE8_SIZE);
+                tiles
+            })
+        } else {
+            huff_decode_chunk(bytes, &huffnodes)
+        };
+        other_chunks.push(decoded.map_err(|e| vgagraph.decode_error(Some(i), o1, e))?);
+    }
+
+    Ok(VgaGraph {
+        font1,
+        font2,
+        pics,
+        other_chunks,
+    })
 }
 
 /// Parse a font data from Huffman-decoded bytes.
-fn parse_font(fontbytes: &[u8]) -> FontData {
+/// In case of errors, returns the offset inside the font bytes + the reason.
+fn parse_font(fontbytes: &[u8]) -> Result<FontData, (usize, String)> {
     // fontstruct { int height; int location[256]; char width[256]; }
-    let font_height = buf_to_u16(&fontbytes);
+    const FONT_HEADER_LEN: usize = 2 + 512 + 256;
+    if fontbytes.len() < FONT_HEADER_LEN {
+        return Err((0, format!("Font is too short: {} bytes", fontbytes.len())));
+    }
+    let font_height = buf_to_u16(fontbytes);
     let space_width = fontbytes[32 + 512 + 2] as u16;
     let mut offs_widths = Vec::with_capacity(95);
-    let mut pixels = Vec::with_capacity((font_height * font_height * 100) as usize);
+    let mut pixels = vec![];
     for j in 33..128 {
         let loc = buf_to_u16(&fontbytes[2 + 2 * j..]) as usize;
-        let char_width = fontbytes[j + 514] as u16;
+        let char_width = fontbytes[j + 514] as usize;
         if loc == 0 || char_width == 0 {
             break;
         }
         // put the offset and width together, in the same vector
-        offs_widths.push(pixels.len() as u16);
-        offs_widths.push(char_width);
+        let offset =
+            u16::try_from(pixels.len()).map_err(|_| (loc, format!("Character #{j} does not fit in the font")))?;
+        offs_widths.push(offset);
+        offs_widths.push(char_width as u16);
         // pixels are flipped (rows first) => un-flip them
-        let len = (font_height * char_width) as usize;
-        let flipped = &fontbytes[loc..loc + len];
+        let height = font_height as usize;
+        let flipped = fontbytes
+            .get(loc..loc + height * char_width)
+            .ok_or_else(|| (loc, format!("Character #{j} is past the end of the font")))?;
         for x in 0..char_width {
-            for y in 0..font_height {
-                let idx = (y * char_width + x) as usize;
-                pixels.push(flipped[idx]);
+            for y in 0..height {
+                pixels.push(flipped[y * char_width + x]);
             }
         }
     }
 
-    FontData::new(font_height, space_width, offs_widths, pixels)
+    Ok(FontData::new(font_height, space_width, offs_widths, pixels))
 }
 
 /// PICs are separated into planes => de-separate it
@@ -460,7 +865,6 @@ fn parse_font(fontbytes: &[u8]) -> FontData {
 fn munge_pic(width: u16, height: u16, pixels: &mut Vec<u8>) {
     let width = width as usize;
     let plane_width = width / 4;
-    assert_eq!(plane_width * 4, width);
 
     // first munge => this will result in a "flipped" pic ...
     // (I am not smart enough to munge and flip in the same loop :/)
@@ -486,58 +890,219 @@ fn munge_pic(width: u16, height: u16, pixels: &mut Vec<u8>) {
     }
 }
 
-/// Huffman decoding for pictures
-/// -> see [CAL_SetupGrFile](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L872)
-/// and [CAL_HuffExpand](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L409)
-fn huff_decode_chunk(bytes: &[u8], huff_dict: &[u16]) -> Vec<u8> {
-    // read the decoded size (4 bytes)
-    let decoded_size = buf_to_u32(bytes) as usize;
-    let mut decoded = Vec::with_capacity(decoded_size);
-
-    // ok to decode data
-    // TODO (!!) there is a "screen hack" in the original source
-    let mut src_idx = 4;
-    // root node of Huffman tree is always at index 254 (and 255 is unused)
-    let mut huff_idx = 254;
-    let mut current_byte = bytes[src_idx];
-    let mut bit_mask = 1;
-    while decoded.len() < decoded_size && src_idx < bytes.len() {
-        // check current bit
-        let is_zero_bit = (current_byte & bit_mask) == 0;
-        let huff_target = if is_zero_bit {
-            huff_dict[2 * huff_idx]
-        } else {
-            huff_dict[2 * huff_idx + 1]
-        };
-        // advance to next bit / byte
-        if bit_mask == 0x80 {
-            bit_mask = 1;
-            src_idx += 1;
-            current_byte = bytes[src_idx];
-        } else {
-            bit_mask = bit_mask << 1;
+//------------------------------------
+// Audio loader - AUDIOHED, AUDIOT
+//------------------------------------
+
+/// Number of sounds, if the end of the PC sounds cannot be detected.
+/// -> see NUMSOUNDS in AUDIOWL6.H and AUDIOSOD.H
+const NUMSOUNDS_WOLF: usize = 87;
+const NUMSOUNDS_SPEAR: usize = 81;
+
+/// Marker at the end of each group of sound chunks (PC, AdLib, digitized)
+const END_OF_GROUP_MARKER: &[u8] = b"!ID!";
+
+/// The audio chunks: PC speaker sounds, AdLib sounds and IMF music.
+type LoadedAudio = (Vec<PcSound>, Vec<AdLibSound>, Vec<ImfMusic>);
+
+/// Split AUDIOT into PC speaker sounds, AdLib sounds and IMF music.
+/// AUDIOT has 3 groups of NUMSOUNDS chunks (PC, AdLib, digitized) followed by the music chunks.
+/// The digitized sounds are unused (they are stored in VSWAP instead).
+fn load_audio(files: &GameFiles) -> Result<LoadedAudio, AssetError> {
+    if !files.has_file(AUDIOHED) || !files.has_file(AUDIOT) {
+        println!("[ROLF3D] No audio files found => no sounds and music");
+        return Ok((vec![], vec![], vec![]));
+    }
+
+    // load files
+    let ah_bytes = load_file(files, AUDIOHED)?;
+    let at_bytes = load_file(files, AUDIOT)?;
+    let ah_len = ah_bytes.len();
+    let audiohed = AssetFile::new(files, AUDIOHED, &ah_bytes);
+    let audiot = AssetFile::new(files, AUDIOT, &at_bytes);
+
+    // AUDIOHED contains the offset of each chunk + the end of the last chunk
+    let cnt_offsets = ah_len / 4;
+    if cnt_offsets < 2 {
+        return Err(audiohed.error(None, 0, format!("Invalid file length: {ah_len} bytes")));
+    }
+    let mut chunks = Vec::with_capacity(cnt_offsets - 1);
+    let mut start = audiohed.u32_at(0, None)? as usize;
+    for i in 1..cnt_offsets {
+        let end = audiohed.u32_at(4 * i, None)? as usize;
+        if end < start {
+            return Err(audiohed.error(Some(i - 1), 4 * i, "Chunk offsets are not in order"));
         }
-        // act based on huff data
-        if huff_target < 256 {
-            // it's a byte value
-            decoded.push((huff_target & 0xFF) as u8);
-            huff_idx = 254;
+        chunks.push((start, audiot.slice(start, end - start, Some(i - 1))?));
+        start = end;
+    }
+
+    // find how many sounds there are, in each group
+    let cnt_sounds = chunks
+        .iter()
+        .position(|(_, bytes)| bytes.ends_with(END_OF_GROUP_MARKER))
+        .map(|idx| idx + 1)
+        .unwrap_or(if files.ext.starts_with('S') {
+            NUMSOUNDS_SPEAR
         } else {
-            // it's an index to another node
-            huff_idx = (huff_target & 0xFF) as usize;
-        }
+            NUMSOUNDS_WOLF
+        });
+    if chunks.len() < 3 * cnt_sounds {
+        let reason = format!("Too few chunks ({}) for {cnt_sounds} sounds", chunks.len());
+        return Err(audiohed.error(None, 0, reason));
+    }
+
+    // parse each group
+    let mut pc_sounds = Vec::with_capacity(cnt_sounds);
+    for (i, (ofs, bytes)) in chunks[0..cnt_sounds].iter().enumerate() {
+        let snd = parse_pc_sound(bytes).map_err(|(o, reason)| audiot.error(Some(i), ofs + o, reason))?;
+        pc_sounds.push(snd);
+    }
+    let mut adlib_sounds = Vec::with_capacity(cnt_sounds);
+    for (i, (ofs, bytes)) in chunks[cnt_sounds..2 * cnt_sounds].iter().enumerate() {
+        let chunk = cnt_sounds + i;
+        let snd = parse_adlib_sound(bytes).map_err(|(o, reason)| audiot.error(Some(chunk), ofs + o, reason))?;
+        adlib_sounds.push(snd);
+    }
+    let mut music = Vec::with_capacity(chunks.len() - 3 * cnt_sounds);
+    for (i, (ofs, bytes)) in chunks[3 * cnt_sounds..].iter().enumerate() {
+        let chunk = 3 * cnt_sounds + i;
+        let mus = parse_imf_music(bytes).map_err(|(o, reason)| audiot.error(Some(chunk), ofs + o, reason))?;
+        music.push(mus);
     }
 
-    assert_eq!(decoded_size, decoded.len());
-    decoded
+    println!(
+        "[ROLF3D] Loaded {} PC sounds, {} AdLib sounds, {} music tracks",
+        pc_sounds.iter().filter(|s| !s.is_empty()).count(),
+        adlib_sounds.iter().filter(|s| !s.is_empty()).count(),
+        music.iter().filter(|m| !m.is_empty()).count(),
+    );
+    Ok((pc_sounds, adlib_sounds, music))
+}
+
+/// Parse the common header of PC and AdLib sounds: length (4 bytes) + priority (2 bytes).
+/// In case of errors, all the parsers below return the offset inside the chunk + the reason.
+fn parse_sound_header(bytes: &[u8], header_len: usize) -> Result<(usize, u16), (usize, String)> {
+    if bytes.len() < header_len {
+        return Err((0, format!("Sound is too short: {} bytes", bytes.len())));
+    }
+    let length = buf_to_u32(bytes) as usize;
+    let priority = buf_to_u16(&bytes[4..]);
+    if header_len + length > bytes.len() {
+        return Err((0, format!("Sound length {length} is past the end of the chunk")));
+    }
+    Ok((length, priority))
+}
+
+fn parse_pc_sound(bytes: &[u8]) -> Result<PcSound, (usize, String)> {
+    if bytes.is_empty() {
+        return Ok(PcSound::default());
+    }
+    // SoundCommon { longword length; word priority; } + data
+    let (length, priority) = parse_sound_header(bytes, 6)?;
+    let tones = bytes[6..6 + length].to_vec();
+    Ok(PcSound { priority, tones })
+}
+
+fn parse_adlib_sound(bytes: &[u8]) -> Result<AdLibSound, (usize, String)> {
+    if bytes.is_empty() {
+        return Ok(AdLibSound::default());
+    }
+    // SoundCommon + Instrument + block + data
+    const HEADER_LEN: usize = 6 + AdLibInstrument::SIZE + 1;
+    let (length, priority) = parse_sound_header(bytes, HEADER_LEN)?;
+    let instrument = AdLibInstrument::from_bytes(&bytes[6..]);
+    let block = bytes[HEADER_LEN - 1];
+    let notes = bytes[HEADER_LEN..HEADER_LEN + length].to_vec();
+    Ok(AdLibSound {
+        priority,
+        instrument,
+        block,
+        notes,
+    })
+}
+
+fn parse_imf_music(bytes: &[u8]) -> Result<ImfMusic, (usize, String)> {
+    if bytes.is_empty() {
+        return Ok(ImfMusic::default());
+    }
+    // MusicGroup { word length; word values[]; }
+    // -> the length is in bytes, and each command has 4 bytes: register, value, delay (word)
+    if bytes.len() < 2 {
+        return Err((0, "Music is too short".to_string()));
+    }
+    let length = buf_to_u16(bytes) as usize;
+    if 2 + length > bytes.len() {
+        return Err((0, format!("Music length {length} is past the end of the chunk")));
+    }
+    let commands = bytes[2..2 + length]
+        .chunks_exact(4)
+        .map(|cmd| ImfCommand {
+            reg: cmd[0],
+            value: cmd[1],
+            delay: buf_to_u16(&cmd[2..]),
+        })
+        .collect();
+    Ok(ImfMusic { commands })
 }
 
 //--------------
 //  Misc ...
 //--------------
 
-#[inline]
-fn load_file(nameidx: usize, ext: &str, outbuf: &mut [u8]) -> Result<usize, String> {
-    let fname = format!("{}.{}", FILES[nameidx], ext);
-    read_file_to_bytes(&fname, outbuf)
+/// Read an entire asset file, at its real size.
+fn load_file(files: &GameFiles, nameidx: usize) -> Result<Vec<u8>, AssetError> {
+    let bytes = files.source.read(&files.filename(nameidx))?;
+    let mut stats = files.stats.get();
+    stats.files_read += 1;
+    stats.bytes_read += bytes.len();
+    stats.largest_file = stats.largest_file.max(bytes.len());
+    files.stats.set(stats);
+    Ok(bytes)
+}
+
+/// The contents of an asset file, with bounds-checked access.
+/// All the access methods report an `AssetError` for this file, instead of panicking.
+struct AssetFile<'a> {
+    name: String,
+    bytes: &'a [u8],
+}
+
+impl<'a> AssetFile<'a> {
+    fn new(files: &GameFiles, nameidx: usize, bytes: &'a [u8]) -> Self {
+        let name = files.names[nameidx].clone().unwrap_or_else(|| files.filename(nameidx));
+        Self { name, bytes }
+    }
+
+    fn error(&self, chunk: Option<usize>, offset: usize, reason: impl Into<String>) -> AssetError {
+        AssetError::bad_data(&self.name, chunk, offset, reason)
+    }
+
+    /// Convert a decoder error into an asset error - `base` is the offset of the decoded chunk.
+    fn decode_error(&self, chunk: Option<usize>, base: usize, err: DecodeError) -> AssetError {
+        self.error(chunk, base + err.offset, err.reason)
+    }
+
+    fn slice(&self, offset: usize, len: usize, chunk: Option<usize>) -> Result<&'a [u8], AssetError> {
+        let end = offset.checked_add(len).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => Ok(&self.bytes[offset..end]),
+            None => Err(self.error(chunk, offset, format!("Cannot read {len} bytes past the end of file"))),
+        }
+    }
+
+    #[inline]
+    fn slice_from(&self, offset: usize, chunk: Option<usize>) -> Result<&'a [u8], AssetError> {
+        self.slice(offset, self.bytes.len().saturating_sub(offset), chunk)
+    }
+
+    #[inline]
+    fn u16_at(&self, offset: usize, chunk: Option<usize>) -> Result<u16, AssetError> {
+        self.slice(offset, 2, chunk).map(buf_to_u16)
+    }
+
+    #[inline]
+    fn u32_at(&self, offset: usize, chunk: Option<usize>) -> Result<u32, AssetError> {
+        self.slice(offset, 4, chunk).map(buf_to_u32)
+    }
 }
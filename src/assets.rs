@@ -4,11 +4,16 @@
 //  -> looks like each map has 64*64 = 4096 words, between 0x00 and 0xFF
 //      => it's JUST a 2D array :))
 //  -> plane #1 seems to contain WALLS, plane #2 seems to contain THINGS
-//  -> plane #3 seems to ALWAYS have 0-s => NOT USED ?!?, check SOD, WL6 etc
+//  -> plane #3 ALWAYS has 0-s in the original games, but extended map editors (FloEdit, ChaosEdit)
+//      use it for extra info => it is kept as the "info" plane
 
 //-----------------------
 
-use crate::{palette_to_rgb, ScreenBuffer};
+use std::cell::OnceCell;
+use std::ops::Index;
+use std::rc::Rc;
+
+use crate::{AssetError, DecodeError, Palette, ScreenBuffer};
 
 /// Graphics - contains walls, sprites and miscellaneous (fonts, PICs etc)
 /// Each pic is stored as columns, then rows (flipped)
@@ -27,7 +32,7 @@ impl GfxData {
 
     #[inline]
     pub fn new_pic(width: u16, height: u16, texels: Vec<u8>) -> Self {
-        assert_eq!((width * height) as usize, texels.len());
+        assert_eq!((width as usize) * (height as usize), texels.len());
         Self { width, height, texels }
     }
 
@@ -45,6 +50,12 @@ impl GfxData {
         (self.width, self.height)
     }
 
+    /// All the texels, column by column.
+    #[inline]
+    pub fn texels(&self) -> &[u8] {
+        &self.texels
+    }
+
     pub fn texel(&self, dx: f64, dy: f64) -> u8 {
         if self.width == 0 || self.height == 0 {
             13 // missing texture => PINK
@@ -58,10 +69,10 @@ impl GfxData {
         }
     }
 
-    pub fn grayscale(&mut self, grays: &[u8]) {
+    pub fn grayscale(&mut self, palette: &Palette, grays: &[u8]) {
         for idx in 0..self.texels.len() {
             let c = self.texels[idx];
-            let gray_level = palette_to_rgb(c, false).grayscale() as usize;
+            let gray_level = palette.color(c).grayscale() as usize;
             let gidx = gray_level * grays.len() / 256;
             self.texels[idx] = grays[gidx];
         }
@@ -70,6 +81,141 @@ impl GfxData {
 
 //-----------------------
 
+/// Decodes one VSWAP page (the raw bytes of the page) into a graphic.
+pub type PageDecoder = fn(&[u8]) -> Result<GfxData, DecodeError>;
+
+/// A list of graphics from VSWAP - the walls or the sprites.
+/// The pages are decoded lazily, on first use, from the raw VSWAP bytes (which are shared by walls and sprites).
+/// Pages which cannot be decoded are errors for `try_get`, and empty graphics for `get` (with an error message).
+pub struct GfxPages {
+    /// The name of the VSWAP file (for errors)
+    file: String,
+    raw: Rc<[u8]>,
+    pages: Vec<GfxPage>,
+    /// Returned by `get` for the pages which cannot be decoded
+    empty: GfxData,
+}
+
+impl GfxPages {
+    /// Create an empty list, for the pages in the given raw bytes of a VSWAP file.
+    pub fn new(file: &str, raw: Rc<[u8]>) -> Self {
+        Self {
+            file: file.to_string(),
+            raw,
+            pages: vec![],
+            empty: GfxData::new_empty(),
+        }
+    }
+
+    /// Add a page which will be decoded on first use. The page is `raw[offset..offset+length]`
+    /// (it must be inside the raw bytes), and `chunk` is its VSWAP chunk index (for messages).
+    pub fn push_lazy(&mut self, chunk: usize, offset: usize, length: usize, decoder: PageDecoder) {
+        assert!(offset + length <= self.raw.len());
+        self.pages.push(GfxPage {
+            source: Some((chunk, offset, length, decoder)),
+            gfx: OnceCell::new(),
+        });
+    }
+
+    /// Add an already decoded graphic.
+    pub fn push(&mut self, gfx: GfxData) {
+        self.pages.push(GfxPage {
+            source: None,
+            gfx: OnceCell::from(Ok(gfx)),
+        });
+    }
+
+    /// Replace a page with another graphic.
+    pub fn set(&mut self, idx: usize, gfx: GfxData) {
+        self.pages[idx] = GfxPage {
+            source: None,
+            gfx: OnceCell::from(Ok(gfx)),
+        };
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Get a page (decoding it, if this is its first use).
+    /// Fails if there is no such page, or if it cannot be decoded.
+    pub fn try_get(&self, idx: usize) -> Result<&GfxData, AssetError> {
+        let Some(page) = self.pages.get(idx) else {
+            let reason = format!("Page index {idx} is out of range (0..{})", self.len());
+            return Err(AssetError::bad_data(&self.file, None, 0, reason));
+        };
+        let decoded = page.gfx.get_or_init(|| {
+            let Some((chunk, offset, length, decoder)) = page.source else {
+                return Ok(GfxData::new_empty());
+            };
+            decoder(&self.raw[offset..offset + length]).map_err(|err| {
+                let err = AssetError::bad_data(&self.file, Some(chunk), offset + err.offset, err.reason);
+                println!("[ROLF3D] Cannot decode page: {err}");
+                err
+            })
+        });
+        decoded.as_ref().map_err(|err| err.clone())
+    }
+
+    /// Get a page (decoding it, if this is its first use).
+    /// Pages which cannot be decoded are returned as empty graphics - use `try_get` to tell them apart.
+    pub fn get(&self, idx: usize) -> Option<&GfxData> {
+        if idx >= self.len() {
+            return None;
+        }
+        Some(self.try_get(idx).unwrap_or(&self.empty))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GfxData> {
+        (0..self.len()).map(|idx| &self[idx])
+    }
+
+    /// How many pages were decoded so far (including the ones which were never lazy).
+    pub fn decoded_count(&self) -> usize {
+        self.pages.iter().filter(|page| page.gfx.get().is_some()).count()
+    }
+
+    /// The size of the raw VSWAP bytes kept for decoding the pages.
+    #[inline]
+    pub fn raw_size(&self) -> usize {
+        self.raw.len()
+    }
+}
+
+impl From<Vec<GfxData>> for GfxPages {
+    fn from(list: Vec<GfxData>) -> Self {
+        let mut pages = Self::new("", Rc::from([]));
+        list.into_iter().for_each(|gfx| pages.push(gfx));
+        pages
+    }
+}
+
+impl Index<usize> for GfxPages {
+    type Output = GfxData;
+
+    #[inline]
+    fn index(&self, idx: usize) -> &GfxData {
+        match self.get(idx) {
+            Some(gfx) => gfx,
+            None => panic!("Page index {idx} is out of range (0..{})", self.len()),
+        }
+    }
+}
+
+struct GfxPage {
+    /// Where to decode the page from: (chunk index, offset, length, decoder)
+    source: Option<(usize, usize, usize, PageDecoder)>,
+    gfx: OnceCell<Result<GfxData, AssetError>>,
+}
+
+//-----------------------
+
 pub struct FontData {
     font_height: u16,
     space_width: u16,
@@ -87,6 +233,29 @@ impl FontData {
         }
     }
 
+    #[inline]
+    pub fn font_height(&self) -> u16 {
+        self.font_height
+    }
+
+    #[inline]
+    pub fn space_width(&self) -> u16 {
+        self.space_width
+    }
+
+    /// Get the width and the pixels (column by column) of a character, if the font has it.
+    /// Non-zero pixels are drawn, zero pixels are transparent.
+    pub fn glyph(&self, ch: u8) -> Option<(u16, &[u8])> {
+        if ch <= 32 {
+            return None;
+        }
+        let idx = ((ch - 33) as usize) * 2;
+        let ofs = *self.offs_widths.get(idx)? as usize;
+        let width = *self.offs_widths.get(idx + 1)?;
+        let len = (width as usize) * (self.font_height as usize);
+        Some((width, &self.pixels[ofs..ofs + len]))
+    }
+
     pub fn text_width(&self, text: &str) -> i32 {
         let mut dx = 0;
         for ch in text.bytes() {
@@ -141,41 +310,54 @@ impl FontData {
 
 //-----------------------
 
-/// Map data - contains walls/doors and things.
-/// Note: all levels have a size of 64x64, but we keep the width and height
-/// as explicit values here, for flexibility.
+/// The maximum number of cells in a map. Each plane starts with its decompressed length,
+/// as a u16 number of bytes => a plane can have at most 0x7FFF words (e.g. 128x255 or 181x181).
+pub const MAX_MAP_CELLS: usize = 0xFFFF / 2;
+
+/// Map data - contains walls/doors, things and the (optional) info plane.
+/// Note: all the original levels have a size of 64x64, but maps made with extended editors
+/// (e.g. FloEdit, ChaosEdit) can be bigger - up to `MAX_MAP_CELLS`.
 pub struct MapData {
     pub name: String,
     pub width: u16,
     pub height: u16,
     tiles: Vec<u16>,
     things: Vec<u16>,
+    infos: Vec<u16>,
 }
 
 impl MapData {
-    pub fn new(name: String, width: u16, height: u16, tiles: Vec<u16>, things: Vec<u16>) -> Self {
-        // some silly checks - seem to be valid for WL1, WL6 and SOD
-        assert!(name.len() > 0);
-        // check that maps are always 64 x 64
-        assert_eq!(64, width);
-        assert_eq!(64, height);
-        // check that planes have exactly 64*64 = 4096 words
-        assert_eq!(4096, tiles.len());
-        assert_eq!(4096, things.len());
-        // check that all wall IDs are <= 0xFF
-        let wallsok = tiles.iter().all(|w| *w <= 0xFF);
-        assert!(wallsok);
-        // check that all thing IDs are <= 0x1FF
-        let thingsok = things.iter().all(|t| *t <= 0x1FF);
-        assert!(thingsok);
+    /// Create a map from its tiles and things - its info plane is all ZERO (see `with_info_plane`).
+    /// The planes contain `width * height` words, row by row.
+    pub fn new(name: String, width: u16, height: u16, tiles: Vec<u16>, things: Vec<u16>) -> Result<Self, String> {
+        if name.is_empty() {
+            return Err("Map has no name".to_string());
+        }
+        let cnt_cells = (width as usize) * (height as usize);
+        if cnt_cells == 0 || cnt_cells > MAX_MAP_CELLS {
+            return Err(format!("Map {name} has an invalid size: {width}x{height}"));
+        }
+        if tiles.len() != cnt_cells || things.len() != cnt_cells {
+            return Err(format!("Map {name} has planes with a wrong size"));
+        }
 
-        Self {
+        Ok(Self {
             name,
             width,
             height,
             tiles,
             things,
+            infos: vec![0; cnt_cells],
+        })
+    }
+
+    /// Set the info plane (plane #3) - unused by the original games, but extended editors store extra info there.
+    pub fn with_info_plane(mut self, infos: Vec<u16>) -> Result<Self, String> {
+        if infos.len() != self.tiles.len() {
+            return Err(format!("Map {} has an info plane with a wrong size", self.name));
         }
+        self.infos = infos;
+        Ok(self)
     }
 
     #[inline]
@@ -188,6 +370,29 @@ impl MapData {
         self.safe_item_from_array(x, y, &self.things)
     }
 
+    #[inline]
+    pub fn info(&self, x: i32, y: i32) -> u16 {
+        self.safe_item_from_array(x, y, &self.infos)
+    }
+
+    /// All the tiles (walls, doors, floor codes), row by row.
+    #[inline]
+    pub fn tiles(&self) -> &[u16] {
+        &self.tiles
+    }
+
+    /// All the things (actors, items, decorations), row by row.
+    #[inline]
+    pub fn things(&self) -> &[u16] {
+        &self.things
+    }
+
+    /// The whole info plane (plane #3), row by row.
+    #[inline]
+    pub fn infos(&self) -> &[u16] {
+        &self.infos
+    }
+
     fn safe_item_from_array(&self, x: i32, y: i32, vect: &Vec<u16>) -> u16 {
         let w = self.width as i32;
         let h = self.height as i32;
@@ -199,3 +404,153 @@ impl MapData {
         }
     }
 }
+
+//-----------------------
+
+/// Sound effects (PC speaker and AdLib) are played at 140 ticks/second.
+pub const SOUND_TICK_RATE: u32 = 140;
+
+/// IMF music is played at 700 ticks/second.
+pub const IMF_TICK_RATE: u32 = 700;
+
+/// Digitized sounds are unsigned 8-bit mono PCM, at (about) 7 kHz.
+pub const DIGI_SAMPLE_RATE: u32 = 7000;
+
+/// PC speaker sound effect.
+/// Each byte is a tone, played for one tick (0 = silence).
+/// -> see [PCSound](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.H)
+#[derive(Clone, Default)]
+pub struct PcSound {
+    pub priority: u16,
+    pub tones: Vec<u8>,
+}
+
+impl PcSound {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tones.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tones.is_empty()
+    }
+}
+
+/// AdLib instrument - the OPL2 register values for the modulator (`m_xxx`) and carrier (`c_xxx`) operators.
+/// -> see [Instrument](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.H)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdLibInstrument {
+    pub m_char: u8,
+    pub c_char: u8,
+    pub m_scale: u8,
+    pub c_scale: u8,
+    pub m_attack: u8,
+    pub c_attack: u8,
+    pub m_sus: u8,
+    pub c_sus: u8,
+    pub m_wave: u8,
+    pub c_wave: u8,
+    pub n_conn: u8,
+    pub voice: u8,
+    pub mode: u8,
+}
+
+impl AdLibInstrument {
+    /// Size of an instrument, in the AUDIOT file (the last 3 bytes are unused).
+    pub const SIZE: usize = 16;
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert!(bytes.len() >= Self::SIZE);
+        Self {
+            m_char: bytes[0],
+            c_char: bytes[1],
+            m_scale: bytes[2],
+            c_scale: bytes[3],
+            m_attack: bytes[4],
+            c_attack: bytes[5],
+            m_sus: bytes[6],
+            c_sus: bytes[7],
+            m_wave: bytes[8],
+            c_wave: bytes[9],
+            n_conn: bytes[10],
+            voice: bytes[11],
+            mode: bytes[12],
+        }
+    }
+}
+
+/// AdLib sound effect - one instrument, playing one note per tick (0 = silence).
+/// Each note is the low byte of the OPL2 frequency, the octave is given by `block`.
+/// -> see [AdLibSound](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.H)
+#[derive(Clone, Default)]
+pub struct AdLibSound {
+    pub priority: u16,
+    pub instrument: AdLibInstrument,
+    pub block: u8,
+    pub notes: Vec<u8>,
+}
+
+impl AdLibSound {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+}
+
+/// One IMF music command: write `value` into the OPL2 register `reg`,
+/// then wait for `delay` ticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImfCommand {
+    pub reg: u8,
+    pub value: u8,
+    pub delay: u16,
+}
+
+/// IMF music - a list of OPL2 register writes.
+/// -> see [MusicGroup](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.H)
+#[derive(Clone, Default)]
+pub struct ImfMusic {
+    pub commands: Vec<ImfCommand>,
+}
+
+impl ImfMusic {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// The total duration of the music, in ticks.
+    pub fn duration_ticks(&self) -> u32 {
+        self.commands.iter().map(|c| c.delay as u32).sum()
+    }
+}
+
+/// Digitized sound - stored in the VSWAP sound pages.
+/// -> see [SD_PlayDigitized](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.C)
+#[derive(Clone, Default)]
+pub struct DigiSound {
+    pub samples: Vec<u8>,
+}
+
+impl DigiSound {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
@@ -0,0 +1,238 @@
+//! Asset sources - a small virtual filesystem, from which the asset files are loaded.
+//!
+//! There are 3 kinds of sources: directories, in-memory files and ZIP archives (also known as PK3 files).
+//! The sources can be stacked into a `LayeredSource`, where the files from upper layers override the ones below.
+//! So a mod (e.g. a level pack) only needs to contain the files it changes (e.g. GAMEMAPS + MAPHEAD,
+//! or some wall/sprite images - see `REPLACEMENTS_DIR`), and the rest come from the base game.
+//!
+//! File names are always matched ignoring their case, and subdirectories are separated by `/`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::{find_file_ignore_case, AssetError};
+
+/// A place from where asset files can be read.
+pub trait AssetSource {
+    /// Describe the source (e.g. its path), for messages.
+    fn describe(&self) -> String;
+
+    /// Find a file: returns its full name (e.g. its path), for messages, or `None` if the source does not have it.
+    fn locate(&self, filename: &str) -> Option<String>;
+
+    /// Read an entire file.
+    fn read(&self, filename: &str) -> Result<Vec<u8>, AssetError>;
+
+    #[inline]
+    fn contains(&self, filename: &str) -> bool {
+        self.locate(filename).is_some()
+    }
+}
+
+/// Open a mod or game directory, or a ZIP/PK3 archive, as an asset source.
+pub fn open_asset_source(path: &Path) -> Result<Box<dyn AssetSource>, AssetError> {
+    if path.is_dir() {
+        Ok(Box::new(DirSource::new(path)))
+    } else {
+        Ok(Box::new(ZipSource::open(path)?))
+    }
+}
+
+//----------------------
+//  Directory source
+//----------------------
+
+/// The files of a directory (including its subdirectories).
+pub struct DirSource {
+    dir: PathBuf,
+}
+
+impl DirSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl AssetSource for DirSource {
+    fn describe(&self) -> String {
+        self.dir.display().to_string()
+    }
+
+    fn locate(&self, filename: &str) -> Option<String> {
+        find_file_ignore_case(&self.dir, filename).map(|path| path.display().to_string())
+    }
+
+    fn read(&self, filename: &str) -> Result<Vec<u8>, AssetError> {
+        let Some(path) = find_file_ignore_case(&self.dir, filename) else {
+            return Err(not_found(self, filename));
+        };
+        std::fs::read(&path).map_err(|e| AssetError::Io {
+            file: path.display().to_string(),
+            reason: e.to_string(),
+        })
+    }
+}
+
+//----------------------
+//  In-memory source
+//----------------------
+
+/// Files held in memory - e.g. generated by an editor, or downloaded.
+pub struct MemorySource {
+    name: String,
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl MemorySource {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Add a file (or replace it, if it already exists).
+    pub fn insert(&mut self, filename: &str, bytes: Vec<u8>) {
+        self.files.insert(filename.to_ascii_uppercase(), bytes);
+    }
+}
+
+impl AssetSource for MemorySource {
+    fn describe(&self) -> String {
+        self.name.clone()
+    }
+
+    fn locate(&self, filename: &str) -> Option<String> {
+        let key = filename.to_ascii_uppercase();
+        self.files.contains_key(&key).then(|| format!("{}:{key}", self.name))
+    }
+
+    fn read(&self, filename: &str) -> Result<Vec<u8>, AssetError> {
+        self.files
+            .get(&filename.to_ascii_uppercase())
+            .cloned()
+            .ok_or_else(|| not_found(self, filename))
+    }
+}
+
+//----------------------
+//  ZIP/PK3 source
+//----------------------
+
+/// The files of a ZIP archive (PK3 files are also ZIP archives).
+pub struct ZipSource {
+    path: PathBuf,
+    archive: RefCell<zip::ZipArchive<BufReader<File>>>,
+    /// The upper-case file names => the actual names in the archive
+    names: HashMap<String, String>,
+}
+
+impl ZipSource {
+    pub fn open(path: &Path) -> Result<Self, AssetError> {
+        let file = File::open(path).map_err(|e| AssetError::Io {
+            file: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        let archive = zip::ZipArchive::new(BufReader::new(file))
+            .map_err(|e| AssetError::bad_data(&path.display().to_string(), None, 0, e.to_string()))?;
+        let names = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/'))
+            .map(|name| (name.to_ascii_uppercase(), name.to_string()))
+            .collect();
+        Ok(Self {
+            path: path.to_path_buf(),
+            archive: RefCell::new(archive),
+            names,
+        })
+    }
+}
+
+impl AssetSource for ZipSource {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn locate(&self, filename: &str) -> Option<String> {
+        let name = self.names.get(&filename.to_ascii_uppercase())?;
+        Some(format!("{}:{name}", self.path.display()))
+    }
+
+    fn read(&self, filename: &str) -> Result<Vec<u8>, AssetError> {
+        let Some(name) = self.names.get(&filename.to_ascii_uppercase()) else {
+            return Err(not_found(self, filename));
+        };
+        let io_error = |reason: String| AssetError::Io {
+            file: format!("{}:{name}", self.path.display()),
+            reason,
+        };
+        let mut archive = self.archive.borrow_mut();
+        let mut entry = archive.by_name(name).map_err(|e| io_error(e.to_string()))?;
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut bytes).map_err(|e| io_error(e.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+//----------------------
+//  Layered source
+//----------------------
+
+/// A stack of sources: files are searched from the top layer (the last one pushed) down.
+#[derive(Default)]
+pub struct LayeredSource {
+    layers: Vec<Box<dyn AssetSource>>,
+}
+
+impl LayeredSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer on top of the existing ones - its files override the ones below.
+    pub fn push(&mut self, layer: Box<dyn AssetSource>) {
+        self.layers.push(layer);
+    }
+
+    #[inline]
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+}
+
+impl AssetSource for LayeredSource {
+    fn describe(&self) -> String {
+        let names: Vec<String> = self.layers.iter().map(|layer| layer.describe()).collect();
+        names.join(" + ")
+    }
+
+    fn locate(&self, filename: &str) -> Option<String> {
+        self.layers.iter().rev().find_map(|layer| layer.locate(filename))
+    }
+
+    fn read(&self, filename: &str) -> Result<Vec<u8>, AssetError> {
+        match self.layers.iter().rev().find(|layer| layer.contains(filename)) {
+            Some(layer) => layer.read(filename),
+            None => Err(not_found(self, filename)),
+        }
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+fn not_found(source: &dyn AssetSource, filename: &str) -> AssetError {
+    AssetError::Io {
+        file: filename.to_string(),
+        reason: format!("File not found in: {}", source.describe()),
+    }
+}
@@ -0,0 +1,316 @@
+//! Wolf3d/SOD asset writer - the reverse of the asset loader.
+//! Encodes the game data back into the original file formats,
+//! so the files can be used by the original games (and by the other tools).
+
+use std::path::Path;
+
+use crate::assets::*;
+use crate::codecs::*;
+use crate::{AssetError, VgaGraph, TILE8_SIZE};
+
+/// The RLEW tag used by the maps of all the original games.
+pub const DEFAULT_RLEW_TAG: u16 = 0xABCD;
+
+/// How many maps fit into MAPHEAD.
+pub const MAX_MAPS: usize = 100;
+
+/// Encode the maps into the contents of the MAPHEAD and GAMEMAPS files, in this order.
+/// The planes are compressed exactly like the original tools did it,
+/// so the original maps are encoded into the same bytes as the shipped files.
+pub fn encode_maps(maps: &[MapData], rlew_tag: u16) -> Result<(Vec<u8>, Vec<u8>), String> {
+    if maps.len() > MAX_MAPS {
+        return Err(format!("Too many maps: {} (max {MAX_MAPS})", maps.len()));
+    }
+
+    let mut maphead = Vec::with_capacity(2 + 4 * MAX_MAPS);
+    maphead.extend_from_slice(&rlew_tag.to_le_bytes());
+    let mut gamemaps = GAMEMAPS_PREFIX.to_vec();
+    for map in maps {
+        let hdridx = encode_one_map(map, rlew_tag, &mut gamemaps)?;
+        maphead.extend_from_slice(&(hdridx as u32).to_le_bytes());
+    }
+    // the unused map slots have no header
+    maphead.resize(2 + 4 * MAX_MAPS, 0);
+
+    Ok((maphead, gamemaps))
+}
+
+/// Encode the maps, and write them to the MAPHEAD.ext and GAMEMAPS.ext files in a directory.
+pub fn write_maps(dir: &Path, ext: &str, maps: &[MapData], rlew_tag: u16) -> Result<(), AssetError> {
+    let (maphead, gamemaps) = encode_maps(maps, rlew_tag)
+        .map_err(|reason| AssetError::bad_data(&format!("GAMEMAPS.{ext}"), None, 0, reason))?;
+    write_file(dir, &format!("MAPHEAD.{ext}"), &maphead)?;
+    write_file(dir, &format!("GAMEMAPS.{ext}"), &gamemaps)
+}
+
+/// Encode the walls, sprites and digitized sounds into the contents of a VSWAP file.
+/// Walls and sprites must be 64x64, or empty (= missing from the file, like in the shareware version).
+/// The digitized sounds are split into pages, followed by the sound info table.
+pub fn encode_vswap(walls: &GfxPages, sprites: &GfxPages, digi_sounds: &[DigiSound]) -> Result<Vec<u8>, String> {
+    // collect all chunks: None = missing chunk
+    let mut chunks: Vec<Option<Vec<u8>>> = Vec::with_capacity(walls.len() + sprites.len() + digi_sounds.len() + 1);
+    for (idx, wall) in walls.iter().enumerate() {
+        chunks.push(match wall.size() {
+            (0, 0) => None,
+            (64, 64) => Some(wall.texels().to_vec()),
+            (w, h) => return Err(format!("Wall #{idx} is {w}x{h} instead of 64x64")),
+        });
+    }
+    for (idx, sprite) in sprites.iter().enumerate() {
+        chunks.push(match sprite.size() {
+            (0, 0) => None,
+            (64, 64) => Some(encode_sprite(sprite.texels()).map_err(|e| format!("Sprite #{idx}: {e}"))?),
+            (w, h) => return Err(format!("Sprite #{idx} is {w}x{h} instead of 64x64")),
+        });
+    }
+
+    // the sound pages, and the sound info table: start page (relative to the first sound page) + length
+    let idx_sprite_start = walls.len();
+    let idx_sound_start = walls.len() + sprites.len();
+    let mut sound_info = Vec::with_capacity(4 * digi_sounds.len());
+    for digi in digi_sounds {
+        let start_page = chunks.len() - idx_sound_start;
+        sound_info.extend_from_slice(&(start_page as u16).to_le_bytes());
+        sound_info.extend_from_slice(&(digi.len() as u16).to_le_bytes());
+        if digi.is_empty() {
+            // missing sounds still need one (missing) page, so the next sound knows where this one ends
+            chunks.push(None);
+        } else {
+            chunks.extend(digi.samples.chunks(DIGI_PAGE_SIZE).map(|page| Some(page.to_vec())));
+        }
+    }
+    if chunks.len() - idx_sound_start > 0xFFFF {
+        return Err("Too many sound pages".to_string());
+    }
+    chunks.push(Some(sound_info));
+    if chunks.len() > 0xFFFF {
+        return Err(format!("Too many chunks: {}", chunks.len()));
+    }
+
+    // the header: chunk count, sprite start, sound start, then the offsets and the lengths
+    let header_len = 6 + 6 * chunks.len();
+    let mut vswap = Vec::with_capacity(header_len + chunks.len() * DIGI_PAGE_SIZE);
+    for w in [chunks.len(), idx_sprite_start, idx_sound_start] {
+        vswap.extend_from_slice(&(w as u16).to_le_bytes());
+    }
+    let mut ofs = align_to_sector(header_len);
+    for chunk in chunks.iter() {
+        match chunk {
+            Some(bytes) => {
+                vswap.extend_from_slice(&(ofs as u32).to_le_bytes());
+                ofs = align_to_sector(ofs + bytes.len());
+            }
+            None => vswap.extend_from_slice(&[0; 4]),
+        }
+    }
+    for chunk in chunks.iter() {
+        let len = chunk.as_ref().map_or(0, |bytes| bytes.len());
+        vswap.extend_from_slice(&(len as u16).to_le_bytes());
+    }
+    // the chunks themselves
+    for bytes in chunks.iter().flatten() {
+        vswap.resize(align_to_sector(vswap.len()), 0);
+        vswap.extend_from_slice(bytes);
+    }
+
+    Ok(vswap)
+}
+
+/// Encode the walls, sprites and digitized sounds, and write them to the VSWAP.ext file in a directory.
+pub fn write_vswap(
+    dir: &Path,
+    ext: &str,
+    walls: &GfxPages,
+    sprites: &GfxPages,
+    digi_sounds: &[DigiSound],
+) -> Result<(), AssetError> {
+    let filename = format!("VSWAP.{ext}");
+    let vswap =
+        encode_vswap(walls, sprites, digi_sounds).map_err(|reason| AssetError::bad_data(&filename, None, 0, reason))?;
+    write_file(dir, &filename, &vswap)
+}
+
+/// The contents of the VGADICT, VGAHEAD and VGAGRAPH files, in this order.
+pub type EncodedVgaGraph = (Vec<u8>, Vec<u8>, Vec<u8>);
+
+/// Encode the fonts, pics and other chunks into the contents of the VGADICT, VGAHEAD and VGAGRAPH files.
+/// The Huffman dictionary is built from the contents of all chunks.
+pub fn encode_vgagraph(vga: &VgaGraph) -> Result<EncodedVgaGraph, String> {
+    // the raw chunks: STRUCTPIC (the pic sizes), the 2 fonts, the pics and then the other chunks
+    let mut pic_sizes = Vec::with_capacity(4 * vga.pics.len());
+    let mut chunks = vec![];
+    for (idx, pic) in vga.pics.iter().enumerate() {
+        let (width, height) = pic.size();
+        pic_sizes.extend_from_slice(&width.to_le_bytes());
+        pic_sizes.extend_from_slice(&height.to_le_bytes());
+        chunks.push(encode_pic(pic).map_err(|e| format!("Pic #{idx}: {e}"))?);
+    }
+    chunks.insert(0, pic_sizes);
+    chunks.insert(1, encode_font(&vga.font1));
+    chunks.insert(2, encode_font(&vga.font2));
+    let idx_tile8 = chunks.len();
+    chunks.extend(vga.other_chunks.iter().cloned());
+
+    // the dictionary
+    let chunk_refs: Vec<&[u8]> = chunks.iter().map(|c| c.as_slice()).collect();
+    let huff_dict = huff_build_dict(&chunk_refs);
+    let vgadict: Vec<u8> = huff_dict.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+    // the encoded chunks, and their offsets (3 bytes each, + the end of the file)
+    let mut vgahead = Vec::with_capacity(3 * chunks.len() + 3);
+    let mut vgagraph = vec![];
+    for (idx, chunk) in chunks.iter().enumerate() {
+        vgahead.extend_from_slice(&(vgagraph.len() as u32).to_le_bytes()[0..3]);
+        if chunk.is_empty() && idx > idx_tile8 {
+            // empty chunks take no space
+            continue;
+        }
+        if idx == idx_tile8 && chunk.len() % TILE8_SIZE != 0 {
+            return Err(format!(
+                "The 8x8 tiles chunk has {} bytes, which is not whole tiles",
+                chunk.len()
+            ));
+        }
+        let encoded = if idx == idx_tile8 {
+            huff_encode(chunk, &huff_dict)?
+        } else {
+            huff_encode_chunk(chunk, &huff_dict)?
+        };
+        vgagraph.extend_from_slice(&encoded);
+    }
+    if vgagraph.len() > 0xFFFFFF {
+        return Err(format!("VGAGRAPH is too large: {} bytes", vgagraph.len()));
+    }
+    vgahead.extend_from_slice(&(vgagraph.len() as u32).to_le_bytes()[0..3]);
+
+    Ok((vgadict, vgahead, vgagraph))
+}
+
+/// Encode the fonts, pics and other chunks, and write them to the VGADICT.ext, VGAHEAD.ext
+/// and VGAGRAPH.ext files in a directory.
+pub fn write_vgagraph(dir: &Path, ext: &str, vga: &VgaGraph) -> Result<(), AssetError> {
+    let (vgadict, vgahead, vgagraph) =
+        encode_vgagraph(vga).map_err(|reason| AssetError::bad_data(&format!("VGAGRAPH.{ext}"), None, 0, reason))?;
+    write_file(dir, &format!("VGADICT.{ext}"), &vgadict)?;
+    write_file(dir, &format!("VGAHEAD.{ext}"), &vgahead)?;
+    write_file(dir, &format!("VGAGRAPH.{ext}"), &vgagraph)
+}
+
+/// Encode a font, as a VGAGRAPH font chunk:
+/// `fontstruct { int height; int location[256]; char width[256]; }`, followed by the characters.
+/// -> see fontstruct in ID_VH.H
+pub fn encode_font(font: &FontData) -> Vec<u8> {
+    const FONT_HEADER_LEN: usize = 2 + 512 + 256;
+    let height = font.font_height() as usize;
+    let mut header = vec![0; FONT_HEADER_LEN];
+    header[0..2].copy_from_slice(&font.font_height().to_le_bytes());
+    header[2 + 512 + 32] = font.space_width() as u8;
+    let mut pixels = vec![];
+    for ch in 33..128 {
+        let Some((width, glyph)) = font.glyph(ch) else {
+            break;
+        };
+        let loc = FONT_HEADER_LEN + pixels.len();
+        header[2 + 2 * ch as usize..4 + 2 * ch as usize].copy_from_slice(&(loc as u16).to_le_bytes());
+        header[2 + 512 + ch as usize] = width as u8;
+        // the glyphs are stored column by column, but the font wants them row by row
+        let width = width as usize;
+        for y in 0..height {
+            pixels.extend((0..width).map(|x| glyph[x * height + y]));
+        }
+    }
+    header.extend_from_slice(&pixels);
+    header
+}
+
+/// Encode a pic, as a VGAGRAPH pic chunk: separated into the 4 VGA planes.
+/// This is the reverse of the munging done when loading the pics.
+/// -> see [VL_MungePic](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_VH.C#L163)
+pub fn encode_pic(pic: &GfxData) -> Result<Vec<u8>, String> {
+    let (width, height) = pic.size();
+    if width == 0 || height == 0 || width % 4 != 0 {
+        return Err(format!(
+            "Invalid pic size: {width}x{height} (the width must be a multiple of 4)"
+        ));
+    }
+    let (width, height) = (width as usize, height as usize);
+    let texels = pic.texels();
+    let mut planes = Vec::with_capacity(texels.len());
+    for plane in 0..4 {
+        for y in 0..height {
+            planes.extend((plane..width).step_by(4).map(|x| texels[x * height + y]));
+        }
+    }
+    Ok(planes)
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The digitized sounds are split into pages of this size.
+const DIGI_PAGE_SIZE: usize = 4096;
+
+/// Like in the original files, the VSWAP chunks start at multiples of 512 bytes.
+const VSWAP_SECTOR_SIZE: usize = 512;
+
+#[inline]
+fn align_to_sector(ofs: usize) -> usize {
+    ofs.next_multiple_of(VSWAP_SECTOR_SIZE)
+}
+
+/// GAMEMAPS starts with the signature of TED5 (the map editor), followed by 3 bytes which are never read.
+/// The 3 bytes are the ones found in GAMEMAPS.WL1, so the encoded file matches it exactly.
+const GAMEMAPS_PREFIX: &[u8] = b"TED5v1.0\xA0\xFF\xCC";
+
+/// Each map header is followed by this signature.
+const MAP_SIGNATURE: &[u8] = b"!ID!";
+
+/// Max length of a map name (it does not need a terminating zero).
+const MAX_NAME_LEN: usize = 16;
+
+/// Append one map to GAMEMAPS: the 3 planes, then the map header, then the signature.
+/// Returns the offset of the map header.
+fn encode_one_map(map: &MapData, rlew_tag: u16, gamemaps: &mut Vec<u8>) -> Result<usize, String> {
+    let name = map.name.as_bytes();
+    if name.len() > MAX_NAME_LEN || !map.name.is_ascii() {
+        return Err(format!("Invalid map name: {}", map.name));
+    }
+
+    // the planes: tiles, things and info (the latter is all ZERO in the original games)
+    let mut offsets = [0; 3];
+    let mut lengths = [0; 3];
+    for (i, plane) in [map.tiles(), map.things(), map.infos()].into_iter().enumerate() {
+        let compressed = compress_map_plane(plane, rlew_tag).map_err(|e| format!("Map {}: {e}", map.name))?;
+        if compressed.len() > 0xFFFF {
+            return Err(format!("Map {} has a plane which is too large", map.name));
+        }
+        offsets[i] = gamemaps.len() as u32;
+        lengths[i] = compressed.len() as u16;
+        gamemaps.extend_from_slice(&compressed);
+    }
+
+    // the header
+    let hdridx = gamemaps.len();
+    offsets
+        .iter()
+        .for_each(|ofs| gamemaps.extend_from_slice(&ofs.to_le_bytes()));
+    lengths
+        .iter()
+        .for_each(|len| gamemaps.extend_from_slice(&len.to_le_bytes()));
+    gamemaps.extend_from_slice(&map.width.to_le_bytes());
+    gamemaps.extend_from_slice(&map.height.to_le_bytes());
+    gamemaps.extend_from_slice(name);
+    gamemaps.resize(gamemaps.len() + MAX_NAME_LEN - name.len(), 0);
+    gamemaps.extend_from_slice(MAP_SIGNATURE);
+
+    Ok(hdridx)
+}
+
+fn write_file(dir: &Path, filename: &str, bytes: &[u8]) -> Result<(), AssetError> {
+    let path = dir.join(filename);
+    std::fs::write(&path, bytes).map_err(|e| AssetError::Io {
+        file: path.display().to_string(),
+        reason: e.to_string(),
+    })
+}
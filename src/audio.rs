@@ -0,0 +1,402 @@
+//! Audio output - sinks (where the samples go) + a mixer for sounds and music.
+//!
+//! All audio is mono, signed 16-bit PCM. The mixer generates samples as time passes,
+//! and pushes them into an `AudioSink`: the SDL2 audio device (see `sdl_wrapper`),
+//! a WAV file (useful for checking the audio without a sound device) or nowhere at all.
+
+use crate::*;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The default sample rate for the audio output.
+pub const DEFAULT_SAMPLE_RATE: u32 = 22050;
+
+/// Maximum number of sounds played at once - when exceeded, the oldest sound is stopped.
+pub const MAX_PLAYING_SOUNDS: usize = 8;
+
+/// Where the generated audio samples go.
+pub trait AudioSink {
+    /// The sample rate expected by the sink.
+    fn sample_rate(&self) -> u32;
+
+    /// Queue some (mono, signed 16-bit) samples, to be played after the previously queued ones.
+    fn queue(&mut self, samples: &[i16]) -> Result<(), String>;
+}
+
+//-----------------
+
+/// Audio sink which just throws away the samples (e.g. when no audio device is available).
+pub struct NullAudioSink {
+    sample_rate: u32,
+    cnt_samples: usize,
+}
+
+impl NullAudioSink {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            cnt_samples: 0,
+        }
+    }
+
+    /// How many samples were "played" so far.
+    #[inline]
+    pub fn cnt_samples(&self) -> usize {
+        self.cnt_samples
+    }
+}
+
+impl AudioSink for NullAudioSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn queue(&mut self, samples: &[i16]) -> Result<(), String> {
+        self.cnt_samples += samples.len();
+        Ok(())
+    }
+}
+
+//-----------------
+
+/// Audio sink which writes the samples into a WAV file (mono, 16-bit PCM).
+/// The WAV header is updated with the correct sizes by `finish`, or when the sink is dropped.
+pub struct WavFileSink {
+    writer: Option<BufWriter<File>>,
+    sample_rate: u32,
+    data_len: u32,
+}
+
+impl WavFileSink {
+    pub fn create(path: &Path, sample_rate: u32) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create {}: {e}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        // the sizes are not known yet => they will be updated at the end
+        writer
+            .write_all(&wav_header(sample_rate, 0))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            writer: Some(writer),
+            sample_rate,
+            data_len: 0,
+        })
+    }
+
+    /// Write the final sizes in the WAV header, and close the file.
+    pub fn finish(&mut self) -> Result<(), String> {
+        if let Some(mut writer) = self.writer.take() {
+            writer
+                .seek(SeekFrom::Start(0))
+                .and_then(|_| writer.write_all(&wav_header(self.sample_rate, self.data_len)))
+                .and_then(|_| writer.flush())
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+impl AudioSink for WavFileSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn queue(&mut self, samples: &[i16]) -> Result<(), String> {
+        let writer = self.writer.as_mut().ok_or("WAV file is already finished")?;
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        writer.write_all(&bytes).map_err(|e| e.to_string())?;
+        self.data_len += bytes.len() as u32;
+        Ok(())
+    }
+}
+
+impl Drop for WavFileSink {
+    fn drop(&mut self) {
+        if let Err(msg) = self.finish() {
+            println!("[ROLF3D] Failed to finish the WAV file: {msg}");
+        }
+    }
+}
+
+/// Build the 44-byte header of a mono, 16-bit PCM WAV file.
+pub fn wav_header(sample_rate: u32, data_len: u32) -> [u8; 44] {
+    let mut hdr = [0; 44];
+    hdr[0..4].copy_from_slice(b"RIFF");
+    hdr[4..8].copy_from_slice(&(36 + data_len).to_le_bytes());
+    hdr[8..16].copy_from_slice(b"WAVEfmt ");
+    hdr[16..20].copy_from_slice(&16_u32.to_le_bytes()); // size of the "fmt " chunk
+    hdr[20..22].copy_from_slice(&1_u16.to_le_bytes()); // PCM
+    hdr[22..24].copy_from_slice(&1_u16.to_le_bytes()); // mono
+    hdr[24..28].copy_from_slice(&sample_rate.to_le_bytes());
+    hdr[28..32].copy_from_slice(&(sample_rate * 2).to_le_bytes()); // bytes per second
+    hdr[32..34].copy_from_slice(&2_u16.to_le_bytes()); // bytes per sample
+    hdr[34..36].copy_from_slice(&16_u16.to_le_bytes()); // bits per sample
+    hdr[36..40].copy_from_slice(b"data");
+    hdr[40..44].copy_from_slice(&data_len.to_le_bytes());
+    hdr
+}
+
+//-----------------
+
+/// Mixes several sounds at once, plus one music stream.
+pub struct AudioMixer {
+    sample_rate: u32,
+    sounds: Vec<PlayingSound>,
+    music: Option<ImfPlayer>,
+    music_buf: Vec<i16>,
+    sound_volume: f64,
+    music_volume: f64,
+}
+
+impl AudioMixer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            sounds: Vec::with_capacity(MAX_PLAYING_SOUNDS),
+            music: None,
+            music_buf: vec![],
+            sound_volume: 1.0,
+            music_volume: 1.0,
+        }
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Set the volumes (between 0 and 1) for sounds and music.
+    pub fn set_volumes(&mut self, sound_volume: f64, music_volume: f64) {
+        self.sound_volume = sound_volume.clamp(0.0, 1.0);
+        self.music_volume = music_volume.clamp(0.0, 1.0);
+    }
+
+    /// Start playing a sound (already rendered at the mixer's sample rate).
+    pub fn play_sound(&mut self, samples: Rc<[i16]>) {
+        if self.sounds.len() >= MAX_PLAYING_SOUNDS {
+            self.sounds.remove(0);
+        }
+        self.sounds.push(PlayingSound { samples, pos: 0 });
+    }
+
+    /// Start playing some music, replacing the current one.
+    pub fn play_music(&mut self, music: ImfMusic, looping: bool) {
+        self.music = Some(ImfPlayer::new(music, self.sample_rate, looping));
+    }
+
+    pub fn stop_music(&mut self) {
+        self.music = None;
+    }
+
+    pub fn stop_all(&mut self) {
+        self.sounds.clear();
+        self.music = None;
+    }
+
+    /// Check if anything is playing.
+    pub fn is_playing(&self) -> bool {
+        !self.sounds.is_empty() || self.music.is_some()
+    }
+
+    /// Mix the next samples of all playing sounds and music into the output buffer.
+    pub fn mix(&mut self, out: &mut [i16]) {
+        // start with the music ...
+        out.fill(0);
+        if let Some(music) = &mut self.music {
+            self.music_buf.resize(out.len(), 0);
+            music.generate(&mut self.music_buf);
+            for (o, m) in out.iter_mut().zip(self.music_buf.iter()) {
+                *o = ((*m as f64) * self.music_volume) as i16;
+            }
+            if music.is_finished() {
+                self.music = None;
+            }
+        }
+        // ... then add the sounds
+        for snd in self.sounds.iter_mut() {
+            let remaining = &snd.samples[snd.pos..];
+            let cnt = remaining.len().min(out.len());
+            for (o, s) in out.iter_mut().zip(remaining[..cnt].iter()) {
+                let mixed = (*o as f64) + (*s as f64) * self.sound_volume;
+                *o = mixed.clamp(-32768.0, 32767.0) as i16;
+            }
+            snd.pos += cnt;
+        }
+        self.sounds.retain(|snd| snd.pos < snd.samples.len());
+    }
+}
+
+//-----------------
+
+/// Which kind of sound effects to play.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundMode {
+    Off,
+    PcSpeaker,
+    AdLib,
+    /// Digitized sounds, where available - otherwise AdLib
+    Digitized,
+}
+
+/// Plays sound effects and music from the game assets, through a mixer.
+/// The sounds are rendered on first use, and then cached.
+/// The mixer keeps its sample rate - if the sink wants another one, the mixed samples are resampled.
+pub struct SoundPlayer {
+    assets: Rc<GameAssets>,
+    mixer: AudioMixer,
+    mode: SoundMode,
+    cache: Vec<Option<Rc<[i16]>>>,
+    /// fractional samples, so that the generated audio stays in sync with the elapsed time
+    frac_samples: f64,
+    mixbuf: Vec<i16>,
+    resampler: Resampler,
+}
+
+impl SoundPlayer {
+    pub fn new(assets: Rc<GameAssets>, sample_rate: u32) -> Self {
+        Self {
+            assets,
+            mixer: AudioMixer::new(sample_rate),
+            mode: SoundMode::Digitized,
+            cache: vec![None; NUM_SOUND_EFFECTS],
+            frac_samples: 0.0,
+            mixbuf: vec![],
+            resampler: Resampler::new(),
+        }
+    }
+
+    #[inline]
+    pub fn mixer(&mut self) -> &mut AudioMixer {
+        &mut self.mixer
+    }
+
+    pub fn set_mode(&mut self, mode: SoundMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.cache.iter_mut().for_each(|c| *c = None);
+        }
+    }
+
+    pub fn play_sound(&mut self, sound: SoundEffect) {
+        if let Some(samples) = self.rendered_sound(sound) {
+            self.mixer.play_sound(samples);
+        }
+    }
+
+    /// Play one of the music tracks (empty tracks are ignored).
+    pub fn play_music(&mut self, track: usize, looping: bool) {
+        if let Some(music) = self.assets.music.get(track).filter(|m| !m.is_empty()) {
+            self.mixer.play_music(music.clone(), looping);
+        }
+    }
+
+    /// Generate the audio for the elapsed time, and push it into the sink.
+    pub fn update(&mut self, sink: &mut dyn AudioSink, elapsed_time: f64) -> Result<(), String> {
+        let sample_rate = self.mixer.sample_rate();
+        self.frac_samples += elapsed_time * (sample_rate as f64);
+        let cnt = self.frac_samples as usize;
+        self.frac_samples -= cnt as f64;
+        if cnt > 0 {
+            self.mixbuf.resize(cnt, 0);
+            self.mixer.mix(&mut self.mixbuf);
+            if sink.sample_rate() == sample_rate {
+                sink.queue(&self.mixbuf)?;
+            } else {
+                let resampled = self.resampler.resample(&self.mixbuf, sample_rate, sink.sample_rate());
+                sink.queue(&resampled)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rendered_sound(&mut self, sound: SoundEffect) -> Option<Rc<[i16]>> {
+        let idx = sound as usize;
+        if self.cache[idx].is_none() {
+            self.cache[idx] = self.render_sound(sound).map(Rc::from);
+        }
+        self.cache[idx].clone()
+    }
+
+    fn render_sound(&self, sound: SoundEffect) -> Option<Vec<i16>> {
+        let sample_rate = self.mixer.sample_rate();
+        let idx = sound as usize;
+        let adlib = self.assets.adlib_sounds.get(idx).filter(|s| !s.is_empty());
+        let pc = self.assets.pc_sounds.get(idx).filter(|s| !s.is_empty());
+        match self.mode {
+            SoundMode::Off => None,
+            SoundMode::PcSpeaker => pc.map(|s| render_pc_sound(s, sample_rate)),
+            SoundMode::AdLib => adlib.map(|s| render_adlib_sound(s, sample_rate)),
+            SoundMode::Digitized => match self.assets.digi_sound(sound) {
+                Some(digi) => Some(resample_digi_sound(digi, sample_rate)),
+                None => adlib.map(|s| render_adlib_sound(s, sample_rate)),
+            },
+        }
+    }
+}
+
+/// Convert a digitized sound (unsigned 8-bit) to signed 16-bit, at the given sample rate.
+/// Uses linear interpolation.
+pub fn resample_digi_sound(digi: &DigiSound, sample_rate: u32) -> Vec<i16> {
+    let src = &digi.samples;
+    if src.is_empty() {
+        return vec![];
+    }
+    let to_i16 = |s: u8| ((s as i16) - 128) << 8;
+    let cnt = ((src.len() as u64) * (sample_rate as u64) / (DIGI_SAMPLE_RATE as u64)) as usize;
+    let step = (DIGI_SAMPLE_RATE as f64) / (sample_rate as f64);
+    (0..cnt)
+        .map(|i| {
+            let pos = (i as f64) * step;
+            let idx = pos as usize;
+            let frac = pos - (idx as f64);
+            let s1 = to_i16(src[idx]) as f64;
+            let s2 = to_i16(src[(idx + 1).min(src.len() - 1)]) as f64;
+            (s1 + (s2 - s1) * frac) as i16
+        })
+        .collect()
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+struct PlayingSound {
+    samples: Rc<[i16]>,
+    pos: usize,
+}
+
+/// Converts a stream of samples to another sample rate, using linear interpolation.
+/// The stream comes in chunks, so the position and the last sample are kept between them.
+struct Resampler {
+    /// The position of the next output sample, in input samples (from the start of the next chunk)
+    pos: f64,
+    /// The last sample of the previous chunk (at position -1)
+    prev: i16,
+}
+
+impl Resampler {
+    fn new() -> Self {
+        Self { pos: 0.0, prev: 0 }
+    }
+
+    fn resample(&mut self, input: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+        let step = (from_rate as f64) / (to_rate as f64);
+        let last = (input.len() as f64) - 1.0;
+        let sample = |idx: isize| if idx < 0 { self.prev } else { input[idx as usize] };
+        let mut out = Vec::with_capacity(((input.len() as f64) / step) as usize + 1);
+        while self.pos <= last {
+            let idx = self.pos.floor();
+            let frac = self.pos - idx;
+            let s1 = sample(idx as isize) as f64;
+            let s2 = sample((idx as isize + 1).min(last as isize)) as f64;
+            out.push((s1 + (s2 - s1) * frac) as i16);
+            self.pos += step;
+        }
+        self.pos -= input.len() as f64;
+        if let Some(&s) = input.last() {
+            self.prev = s;
+        }
+        out
+    }
+}
@@ -15,7 +15,8 @@ const MAX_SCALE: f64 = 40.5;
 const MOVE_SPEED: f64 = 12.0;
 const SCALE_SPEED: f64 = 8.0;
 const MIN_POS: f64 = -14.0;
-const MAX_POS: f64 = 60.0;
+// how far the map can be scrolled, from its right/bottom edge
+const MAX_POS_FROM_EDGE: f64 = 4.0;
 const DIV_MOUSE: f64 = 12.0;
 
 const TEXIDX_ELEVATOR_SWITCH: usize = 41;
@@ -37,17 +38,20 @@ impl AutomapRenderer {
         }
     }
 
-    pub fn handle_inputs(&mut self, inputs: &mut InputManager, elapsed_time: f64) {
+    pub fn handle_inputs(&mut self, inputs: &mut InputManager, map: &LiveMap, elapsed_time: f64) {
+        // maps can have any size => so can the scrolling limits
+        let max_x = (map.width() as f64) - MAX_POS_FROM_EDGE;
+        let max_y = (map.height() as f64) - MAX_POS_FROM_EDGE;
         if inputs.key(Keycode::W) || inputs.key(Keycode::Up) {
-            self.ypos = (self.ypos - MOVE_SPEED * elapsed_time).clamp(MIN_POS, MAX_POS);
+            self.ypos = (self.ypos - MOVE_SPEED * elapsed_time).clamp(MIN_POS, max_y);
         } else if inputs.key(Keycode::S) || inputs.key(Keycode::Down) {
-            self.ypos = (self.ypos + MOVE_SPEED * elapsed_time).clamp(MIN_POS, MAX_POS);
+            self.ypos = (self.ypos + MOVE_SPEED * elapsed_time).clamp(MIN_POS, max_y);
         }
 
         if inputs.key(Keycode::A) || inputs.key(Keycode::Left) {
-            self.xpos = (self.xpos - MOVE_SPEED * elapsed_time).clamp(MIN_POS, MAX_POS);
+            self.xpos = (self.xpos - MOVE_SPEED * elapsed_time).clamp(MIN_POS, max_x);
         } else if inputs.key(Keycode::D) || inputs.key(Keycode::Right) {
-            self.xpos = (self.xpos + MOVE_SPEED * elapsed_time).clamp(MIN_POS, MAX_POS);
+            self.xpos = (self.xpos + MOVE_SPEED * elapsed_time).clamp(MIN_POS, max_x);
         }
 
         if inputs.key(Keycode::KpMinus) {
@@ -58,8 +62,8 @@ impl AutomapRenderer {
 
         if inputs.mouse_btn(sdl2::mouse::MouseButton::Left) {
             let (dx, dy) = inputs.mouse_motion();
-            self.xpos = (self.xpos - (dx as f64) / DIV_MOUSE).clamp(MIN_POS, MAX_POS);
-            self.ypos = (self.ypos - (dy as f64) / DIV_MOUSE).clamp(MIN_POS, MAX_POS);
+            self.xpos = (self.xpos - (dx as f64) / DIV_MOUSE).clamp(MIN_POS, max_x);
+            self.ypos = (self.ypos - (dy as f64) / DIV_MOUSE).clamp(MIN_POS, max_y);
         }
     }
 
@@ -0,0 +1,23 @@
+//! Asset extractor - exports all graphics of a game to PNG files.
+//!
+//! Usage: `rolf3d-extract [GAME_DIR] [OUTPUT_DIR]`
+//! (by default, the game files are searched in the current directory, and exported to `./extracted`)
+
+use rolf3d::*;
+use std::path::Path;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 3 || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("Usage: rolf3d-extract [GAME_DIR] [OUTPUT_DIR]");
+        return;
+    }
+    let game_dir = args.get(1).map(|s| s.as_str()).unwrap_or(".");
+    let out_dir = args.get(2).map(|s| s.as_str()).unwrap_or("extracted");
+
+    let result = GameAssets::load_from(game_dir).and_then(|assets| export_graphics(&assets, Path::new(out_dir)));
+    if let Err(err) = result {
+        println!("ERROR in ROLF3D: {err}");
+        std::process::exit(1);
+    }
+}
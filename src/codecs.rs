@@ -0,0 +1,560 @@
+//! Decoders for the compression schemes used by the WOLF3D asset files:
+//! Carmack + RLEW (for maps), Huffman (for pics) and the sprite "post" format (for VSWAP sprites).
+//! Also the matching encoders, for writing the asset files back.
+//!
+//! All decoders are meant to survive garbage input - every offset, back-reference
+//! and command is checked against the buffers, and malformed data produces a `DecodeError`.
+
+use crate::utils::*;
+use std::fmt;
+
+/// An error found while decoding a compressed chunk.
+/// The offset is relative to the start of the (compressed) input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub offset: usize,
+    pub reason: String,
+}
+
+impl DecodeError {
+    pub fn new(offset: usize, reason: impl Into<String>) -> Self {
+        Self {
+            offset,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} @ 0x{:X}", self.reason, self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+//----------------------
+// Maps: Carmack + RLEW
+//----------------------
+
+/// The high byte of the Carmack near pointers.
+const CARMACK_NEAR_TAG: u8 = 0xA7;
+/// The high byte of the Carmack far pointers.
+const CARMACK_FAR_TAG: u8 = 0xA8;
+
+/// The most words the map compressors accept: the length headers are in bytes, and must fit in 16 bits.
+pub const MAX_COMPRESSED_WORDS: usize = 0x7FFF;
+
+/// Use Carmack and RLEW decompression, to extract a map plane.
+pub fn decompress_map_plane(chunk: &[u8], rlew_tag: u16) -> Result<Vec<u16>, DecodeError> {
+    // first de-Carmack, and then decompress RLEW
+    let intermediate = carmack_decompress(chunk)?;
+    // offsets of RLEW errors are not very useful inside the Carmack-ed chunk
+    // => just report the start of the chunk
+    rlew_decompress(&intermediate, rlew_tag).map_err(|e| DecodeError::new(0, format!("RLEW: {}", e.reason)))
+}
+
+/// Carmack decompression.
+/// The first word of the Carmack-ed chunk is the decompressed length, in bytes.
+/// -> see [CAL_CarmackExpand](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L609)
+pub fn carmack_decompress(chunk: &[u8]) -> Result<Vec<u16>, DecodeError> {
+    let word_at = |idx: usize| {
+        chunk
+            .get(idx..idx + 2)
+            .map(buf_to_u16)
+            .ok_or_else(|| DecodeError::new(idx, "Carmack data ends abruptly"))
+    };
+    let byte_at = |idx: usize| {
+        chunk
+            .get(idx)
+            .cloned()
+            .ok_or_else(|| DecodeError::new(idx, "Carmack data ends abruptly"))
+    };
+
+    let word_cnt = (word_at(0)? / 2) as usize;
+    let mut decoded = Vec::with_capacity(word_cnt);
+    let mut idx = 2;
+    while decoded.len() < word_cnt {
+        let w = word_at(idx)?;
+        let count = (w & 0xFF) as usize;
+        let tag = (w >> 8) as u8;
+        if (tag == CARMACK_NEAR_TAG || tag == CARMACK_FAR_TAG) && count == 0 {
+            // Carmack-style escape sequence
+            let b = byte_at(idx + 2)?;
+            decoded.push((b as u16) | ((tag as u16) << 8));
+            idx += 3;
+        } else if tag == CARMACK_NEAR_TAG || tag == CARMACK_FAR_TAG {
+            // Carmack-style near / far pointer
+            let offs = if tag == CARMACK_NEAR_TAG {
+                let dist = byte_at(idx + 2)? as usize;
+                if dist == 0 || dist > decoded.len() {
+                    return Err(DecodeError::new(
+                        idx,
+                        format!("Invalid near pointer: {dist} words back"),
+                    ));
+                }
+                idx += 3;
+                decoded.len() - dist
+            } else {
+                let offs = word_at(idx + 2)? as usize;
+                if offs >= decoded.len() {
+                    return Err(DecodeError::new(idx, format!("Invalid far pointer: to word {offs}")));
+                }
+                idx += 4;
+                offs
+            };
+            // copy the words one by one - the source may overlap the destination
+            // (never copy more than the announced length)
+            let count = count.min(word_cnt - decoded.len());
+            for i in 0..count {
+                decoded.push(decoded[offs + i]);
+            }
+        } else {
+            // normal word
+            decoded.push(w);
+            idx += 2;
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// RLEW decompression.
+/// The first word of the RLEW-ed data is the decompressed length, in bytes.
+/// The offsets in the errors are word indexes.
+/// -> see [CA_RLEWexpand](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C)
+pub fn rlew_decompress(words: &[u16], rlew_tag: u16) -> Result<Vec<u16>, DecodeError> {
+    let word_at = |idx: usize| {
+        words
+            .get(idx)
+            .cloned()
+            .ok_or_else(|| DecodeError::new(idx, "RLEW data ends abruptly"))
+    };
+
+    let word_cnt = (word_at(0)? / 2) as usize;
+    let mut decoded = Vec::with_capacity(word_cnt);
+    let mut idx = 1;
+    while decoded.len() < word_cnt {
+        let next = word_at(idx)?;
+        if next == rlew_tag {
+            // RLEW sequence (never expand more than the announced length)
+            let cnt = word_at(idx + 1)? as usize;
+            let val = word_at(idx + 2)?;
+            idx += 3;
+            let cnt = cnt.min(word_cnt - decoded.len());
+            decoded.resize(decoded.len() + cnt, val);
+        } else {
+            // normal word
+            idx += 1;
+            decoded.push(next);
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// Use RLEW and Carmack compression, to encode a map plane.
+/// This is the reverse of `decompress_map_plane`, and produces the same bytes as the original tools.
+/// Fails if the plane (or its RLEW-compressed version) is too large for the 16-bit length headers.
+pub fn compress_map_plane(plane: &[u16], rlew_tag: u16) -> Result<Vec<u8>, String> {
+    // first RLEW, and then Carmack-ize
+    let intermediate = rlew_compress(plane, rlew_tag)?;
+    carmack_compress(&intermediate)
+}
+
+/// Carmack compression.
+/// The first word of the output is the length of the input (= the decompressed length), in bytes.
+/// The length must fit in 16 bits => at most `MAX_COMPRESSED_WORDS` words can be compressed.
+/// -> see CAL_CarmackCompress in the TED5 sources
+pub fn carmack_compress(words: &[u16]) -> Result<Vec<u8>, String> {
+    if words.len() > MAX_COMPRESSED_WORDS {
+        return Err(format!("Too many words for Carmack compression: {}", words.len()));
+    }
+    let mut compressed = Vec::with_capacity(2 * words.len() + 2);
+    compressed.extend_from_slice(&((2 * words.len()) as u16).to_le_bytes());
+    let mut idx = 0;
+    while idx < words.len() {
+        let w = words[idx];
+        let remaining = words.len() - idx;
+        // find the longest string (which does not overlap the current position)
+        // => on equal lengths, the latest one wins, since it may be reachable via a near pointer
+        let mut best_len = 0;
+        let mut best_scan = 0;
+        for scan in 0..idx {
+            if words[scan] != w {
+                continue;
+            }
+            let max_len = (idx - scan).min(remaining).min(255);
+            let mut len = 1;
+            while len < max_len && words[scan + len] == words[idx + len] {
+                len += 1;
+            }
+            if len >= best_len {
+                best_len = len;
+                best_scan = scan;
+            }
+        }
+
+        if best_len > 1 && idx - best_scan <= 255 {
+            // near pointer
+            compressed.extend_from_slice(&[best_len as u8, CARMACK_NEAR_TAG, (idx - best_scan) as u8]);
+            idx += best_len;
+        } else if best_len > 2 {
+            // far pointer
+            compressed.extend_from_slice(&[best_len as u8, CARMACK_FAR_TAG]);
+            compressed.extend_from_slice(&(best_scan as u16).to_le_bytes());
+            idx += best_len;
+        } else {
+            // normal word - words which look like a tag must be escaped
+            let tag = (w >> 8) as u8;
+            if tag == CARMACK_NEAR_TAG || tag == CARMACK_FAR_TAG {
+                compressed.extend_from_slice(&[0, tag, (w & 0xFF) as u8]);
+            } else {
+                compressed.extend_from_slice(&w.to_le_bytes());
+            }
+            idx += 1;
+        }
+    }
+    Ok(compressed)
+}
+
+/// RLEW compression.
+/// The first word of the output is the length of the input (= the decompressed length), in bytes.
+/// Like for `carmack_compress`, at most `MAX_COMPRESSED_WORDS` words can be compressed.
+/// -> see CA_RLEWCompress in ID_CA.C
+pub fn rlew_compress(words: &[u16], rlew_tag: u16) -> Result<Vec<u16>, String> {
+    if words.len() > MAX_COMPRESSED_WORDS {
+        return Err(format!("Too many words for RLEW compression: {}", words.len()));
+    }
+    let mut compressed = Vec::with_capacity(words.len() + 1);
+    compressed.push((2 * words.len()) as u16);
+    let mut idx = 0;
+    while idx < words.len() {
+        let value = words[idx];
+        let mut count = 1;
+        while idx + count < words.len() && words[idx + count] == value && count < 0xFFFF {
+            count += 1;
+        }
+        if count > 3 || value == rlew_tag {
+            // RLEW sequence (the tag itself must always be encoded like this)
+            compressed.extend_from_slice(&[rlew_tag, count as u16, value]);
+        } else {
+            // normal words
+            compressed.resize(compressed.len() + count, value);
+        }
+        idx += count;
+    }
+    Ok(compressed)
+}
+
+//------------------
+// Pics: Huffman
+//------------------
+
+/// Huffman decoding for pictures.
+/// The chunk starts with its decoded size (4 bytes), followed by the Huffman-encoded data.
+/// The dictionary must contain 256 nodes (2 words each => 512 words, as in VGADICT)
+/// -> see [CAL_SetupGrFile](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L872)
+/// and [CAL_HuffExpand](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_CA.C#L409)
+pub fn huff_decode_chunk(bytes: &[u8], huff_dict: &[u16]) -> Result<Vec<u8>, DecodeError> {
+    // read the decoded size (4 bytes)
+    let decoded_size = bytes
+        .get(0..4)
+        .map(buf_to_u32)
+        .ok_or_else(|| DecodeError::new(0, "Missing Huffman chunk size"))? as usize;
+    huff_decode(&bytes[4..], decoded_size, huff_dict).map_err(|e| DecodeError::new(e.offset + 4, e.reason))
+}
+
+/// Huffman decoding of all the bits, for chunks which have an implicit size (e.g. the 8x8 tiles).
+/// **Note:** the padding bits at the end may also be decoded into a few (up to 7) garbage bytes.
+pub fn huff_decode_all(bytes: &[u8], huff_dict: &[u16]) -> Result<Vec<u8>, DecodeError> {
+    huff_decode_bits(bytes, None, huff_dict)
+}
+
+/// Huffman decoding, when the decoded size is known.
+/// The offsets of the errors are relative to the start of the encoded data.
+pub fn huff_decode(bytes: &[u8], decoded_size: usize, huff_dict: &[u16]) -> Result<Vec<u8>, DecodeError> {
+    huff_decode_bits(bytes, Some(decoded_size), huff_dict)
+}
+
+/// Build an optimal Huffman dictionary (512 words, as in VGADICT), for the given chunks.
+/// Every byte value gets a code, even if it is not used - the tree always has 255 nodes,
+/// and the last one (index 254) is the root, as the decoder expects.
+pub fn huff_build_dict(chunks: &[&[u8]]) -> Vec<u16> {
+    let mut counts = [0_u64; 256];
+    for chunk in chunks {
+        chunk.iter().for_each(|b| counts[*b as usize] += 1);
+    }
+
+    // the trees which are not merged yet: (count, dictionary word)
+    // => the byte values are leaves, and the nodes are 256 + node index
+    let mut trees: Vec<(u64, u16)> = (0..256).map(|b| (counts[b], b as u16)).collect();
+    let mut huff_dict = Vec::with_capacity(512);
+    while trees.len() > 1 {
+        // merge the 2 least frequent trees (on equal counts, prefer the leaves, for a stable result)
+        trees.sort_by(|a, b| b.cmp(a));
+        let bit0 = trees.pop().unwrap();
+        let bit1 = trees.pop().unwrap();
+        let node_idx = huff_dict.len() / 2;
+        huff_dict.push(bit0.1);
+        huff_dict.push(bit1.1);
+        trees.push((bit0.0 + bit1.0, 256 + node_idx as u16));
+    }
+    // the 256-th node is never used
+    huff_dict.extend_from_slice(&[0, 0]);
+    huff_dict
+}
+
+/// Huffman encoding for pictures - the reverse of `huff_decode_chunk`.
+/// The dictionary must contain all the byte values in the data (`huff_build_dict` does that).
+pub fn huff_encode_chunk(data: &[u8], huff_dict: &[u16]) -> Result<Vec<u8>, String> {
+    let mut encoded = (data.len() as u32).to_le_bytes().to_vec();
+    encoded.extend(huff_encode(data, huff_dict)?);
+    Ok(encoded)
+}
+
+/// Huffman encoding, for chunks which have an implicit size - the reverse of `huff_decode`.
+pub fn huff_encode(data: &[u8], huff_dict: &[u16]) -> Result<Vec<u8>, String> {
+    let codes = huff_codes(huff_dict)?;
+    let mut encoded = Vec::with_capacity(data.len());
+    let mut current_byte = 0_u8;
+    let mut bit_mask = 1_u8;
+    for &b in data {
+        let code = codes[b as usize]
+            .as_ref()
+            .ok_or_else(|| format!("Byte value {b} is not in the Huffman dictionary"))?;
+        for &bit in code {
+            if bit {
+                current_byte |= bit_mask;
+            }
+            bit_mask = bit_mask.rotate_left(1);
+            if bit_mask == 1 {
+                encoded.push(current_byte);
+                current_byte = 0;
+            }
+        }
+    }
+    if bit_mask != 1 {
+        encoded.push(current_byte);
+    }
+    Ok(encoded)
+}
+
+//---------------------
+// Sprites: posts
+//---------------------
+
+/// Decode a VSWAP sprite into a 64x64 column-major texture.
+/// Transparent pixels are 0xFF.
+/// -> see https://devinsmith.net/backups/bruce/wolf3d.html
+pub fn decode_sprite(compressed: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let word_at = |idx: usize| {
+        compressed
+            .get(idx..idx + 2)
+            .map(buf_to_u16)
+            .ok_or_else(|| DecodeError::new(idx, "Sprite data ends abruptly"))
+    };
+
+    let mut pixels = vec![0xFF; 64 * 64];
+    // the first 2 words = the left and right extents of the sprite
+    let left_extent = word_at(0)? as usize;
+    let right_extent = word_at(2)? as usize;
+    if left_extent > right_extent || right_extent >= 64 {
+        let reason = format!("Invalid sprite extents: {left_extent}..{right_extent}");
+        return Err(DecodeError::new(0, reason));
+    }
+
+    // the next N words are the offsets for each column,
+    // then come the textels packed together (one byte each),
+    // and then come the "commands" for each column
+    // (one word each, zero-terminated for each sub-column)
+
+    // moving index into the offsets to the command area for each column
+    let mut ofsidx = 4;
+    // moving index into the texel area
+    let mut texidx = 4 + 2 * (right_extent - left_extent + 1);
+
+    // compute texels for each column
+    for x in left_extent..=right_extent {
+        // offset to the column start, into the destination vector
+        let destidx = x * 64;
+        // read the offset into the command area for this column
+        let mut column_ofs = word_at(ofsidx)? as usize;
+        ofsidx += 2;
+        // keep reading commands for the column
+        // each command is 3 words: end_y * 2, ignored, start_y * 2
+        loop {
+            let end_y = (word_at(column_ofs)? / 2) as usize;
+            if end_y == 0 {
+                break;
+            }
+            let start_y = (word_at(column_ofs + 4)? / 2) as usize;
+            if start_y > end_y || end_y > 64 {
+                let reason = format!("Invalid post in column {x}: {start_y}..{end_y}");
+                return Err(DecodeError::new(column_ofs, reason));
+            }
+            let texels = compressed
+                .get(texidx..texidx + end_y - start_y)
+                .ok_or_else(|| DecodeError::new(texidx, "Sprite texels end abruptly"))?;
+            pixels[destidx + start_y..destidx + end_y].copy_from_slice(texels);
+            texidx += end_y - start_y;
+            column_ofs += 6;
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Encode a 64x64 column-major texture (transparent pixels are 0xFF) into a VSWAP sprite.
+/// This is the reverse of `decode_sprite`. The layout is the same as in the original files:
+/// the extents, the column offsets, the texels and then the posts of each column.
+pub fn encode_sprite(pixels: &[u8]) -> Result<Vec<u8>, String> {
+    if pixels.len() != 64 * 64 {
+        return Err(format!("Sprite has {} pixels instead of 64x64", pixels.len()));
+    }
+    let is_column_empty = |x: usize| pixels[x * 64..x * 64 + 64].iter().all(|p| *p == 0xFF);
+    // a fully transparent sprite still needs one (empty) column
+    let left_extent = (0..64).find(|x| !is_column_empty(*x)).unwrap_or(0);
+    let right_extent = (0..64).rev().find(|x| !is_column_empty(*x)).unwrap_or(0);
+    let cnt_columns = right_extent - left_extent + 1;
+
+    // collect the posts of each column: (start_y, end_y)
+    let mut column_posts = Vec::with_capacity(cnt_columns);
+    let mut cnt_texels = 0;
+    for x in left_extent..=right_extent {
+        let column = &pixels[x * 64..x * 64 + 64];
+        let mut posts = vec![];
+        let mut y = 0;
+        while y < 64 {
+            if column[y] == 0xFF {
+                y += 1;
+                continue;
+            }
+            let start_y = y;
+            while y < 64 && column[y] != 0xFF {
+                y += 1;
+            }
+            posts.push((start_y, y));
+            cnt_texels += y - start_y;
+        }
+        column_posts.push(posts);
+    }
+
+    let texels_start = 4 + 2 * cnt_columns;
+    let mut commands_ofs = texels_start + cnt_texels;
+    let mut encoded = Vec::with_capacity(commands_ofs + 6 * cnt_texels + 2 * cnt_columns);
+    encoded.extend_from_slice(&(left_extent as u16).to_le_bytes());
+    encoded.extend_from_slice(&(right_extent as u16).to_le_bytes());
+    // the column offsets
+    for posts in column_posts.iter() {
+        encoded.extend_from_slice(&(commands_ofs as u16).to_le_bytes());
+        commands_ofs += 6 * posts.len() + 2;
+    }
+    // the texels, and then the commands: end_y * 2, texel offset - start_y, start_y * 2
+    let mut commands = vec![];
+    let mut texidx = texels_start;
+    for (x, posts) in (left_extent..).zip(column_posts.iter()) {
+        for &(start_y, end_y) in posts {
+            encoded.extend_from_slice(&pixels[x * 64 + start_y..x * 64 + end_y]);
+            let corrected_top = (texidx as u16).wrapping_sub(start_y as u16);
+            for w in [2 * end_y as u16, corrected_top, 2 * start_y as u16] {
+                commands.extend_from_slice(&w.to_le_bytes());
+            }
+            texidx += end_y - start_y;
+        }
+        commands.extend_from_slice(&[0, 0]);
+    }
+    encoded.extend_from_slice(&commands);
+    Ok(encoded)
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The root node of the Huffman trees.
+const HUFF_ROOT_NODE: usize = 254;
+
+/// Huffman decoding, of the given size (or of all the bits, if there is no size).
+fn huff_decode_bits(bytes: &[u8], decoded_size: Option<usize>, huff_dict: &[u16]) -> Result<Vec<u8>, DecodeError> {
+    if huff_dict.len() < 512 {
+        return Err(DecodeError::new(
+            0,
+            format!("Huffman dictionary too small: {} words", huff_dict.len()),
+        ));
+    }
+    // each bit of input produces at most one byte of output
+    // => this way, we don't allocate huge buffers, based on garbage sizes
+    let max_size = bytes.len() * 8;
+    let size = decoded_size.unwrap_or(max_size);
+    if size > max_size {
+        return Err(DecodeError::new(0, format!("Huffman chunk size too large: {size}")));
+    }
+    let mut decoded = Vec::with_capacity(size);
+
+    // ok to decode data
+    // the original CAL_HuffExpand also has a "screen hack" mode, which writes straight into the
+    // planar EGA/VGA memory; we always decode into a linear buffer, so it is not needed here
+    // root node of Huffman tree is always at index 254 (and 255 is unused)
+    let mut huff_idx = HUFF_ROOT_NODE;
+    'outer: for &current_byte in bytes {
+        let mut bit_mask = 1;
+        while bit_mask != 0 {
+            if decoded.len() >= size {
+                break 'outer;
+            }
+            // check current bit
+            let is_zero_bit = (current_byte & bit_mask) == 0;
+            let huff_target = if is_zero_bit {
+                huff_dict[2 * huff_idx]
+            } else {
+                huff_dict[2 * huff_idx + 1]
+            };
+            // act based on huff data
+            if huff_target < 256 {
+                // it's a byte value
+                decoded.push((huff_target & 0xFF) as u8);
+                huff_idx = HUFF_ROOT_NODE;
+            } else {
+                // it's an index to another node
+                huff_idx = (huff_target & 0xFF) as usize;
+            }
+            // advance to next bit
+            bit_mask <<= 1;
+        }
+    }
+
+    if decoded_size.is_some() && decoded.len() < size {
+        let reason = format!("Huffman data ends abruptly: {} of {size} bytes", decoded.len());
+        return Err(DecodeError::new(bytes.len(), reason));
+    }
+    Ok(decoded)
+}
+
+/// Get the code (the bits, from the root down) for each byte value in a Huffman dictionary.
+fn huff_codes(huff_dict: &[u16]) -> Result<Vec<Option<Vec<bool>>>, String> {
+    if huff_dict.len() < 512 {
+        return Err(format!("Huffman dictionary too small: {} words", huff_dict.len()));
+    }
+    let mut codes = vec![None; 256];
+    // walk the tree - each node must be reached only once, otherwise it's not a tree
+    let mut visited = [false; 256];
+    let mut pending = vec![(HUFF_ROOT_NODE, vec![])];
+    while let Some((node, code)) = pending.pop() {
+        if visited[node] {
+            return Err("Huffman dictionary is not a tree".to_string());
+        }
+        visited[node] = true;
+        for bit in [false, true] {
+            let target = huff_dict[2 * node + (bit as usize)];
+            let mut target_code = code.clone();
+            target_code.push(bit);
+            if target < 256 {
+                codes[target as usize].get_or_insert(target_code);
+            } else {
+                pending.push(((target & 0xFF) as usize, target_code));
+            }
+        }
+    }
+    Ok(codes)
+}
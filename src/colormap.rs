@@ -0,0 +1,92 @@
+//! Color maps - shade the 3D view with the distance (i.e. fog), while staying in 8-bit colors.
+//! Like in Doom: for each brightness level, a table maps each palette color to the nearest shaded color.
+
+use crate::{Palette, RGB};
+
+/// How many brightness levels the color maps have.
+pub const COLORMAP_LEVELS: usize = 32;
+
+/// The fog of a level: everything fades into the fog color (a palette index) with the distance,
+/// and reaches it completely at the falloff distance (in map cells).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    pub color: u8,
+    pub falloff: f64,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            color: 0,
+            falloff: DEFAULT_FOG_FALLOFF,
+        }
+    }
+}
+
+/// The color maps for a palette and a fog: `COLORMAP_LEVELS` tables of 256 colors,
+/// from full brightness (level 0) to the fog color (the last level).
+pub struct ColorMaps {
+    maps: Vec<[u8; 256]>,
+    fog: Fog,
+}
+
+impl ColorMaps {
+    pub fn new(palette: &Palette, fog: Fog) -> Self {
+        let fog_rgb = palette.color(fog.color);
+        let last_level = (COLORMAP_LEVELS - 1) as f64;
+        let maps = (0..COLORMAP_LEVELS)
+            .map(|level| {
+                let t = (level as f64) / last_level;
+                let mut map = [0; 256];
+                for (c, shaded) in map.iter_mut().enumerate() {
+                    *shaded = if c == TRANSPARENT_COLOR as usize {
+                        TRANSPARENT_COLOR
+                    } else {
+                        let rgb = blend(palette.color(c as u8), fog_rgb, t);
+                        palette.nearest_color(rgb, Some(TRANSPARENT_COLOR))
+                    };
+                }
+                map
+            })
+            .collect();
+        Self { maps, fog }
+    }
+
+    #[inline]
+    pub fn fog(&self) -> Fog {
+        self.fog
+    }
+
+    /// The brightness level at a distance (0 = full brightness).
+    #[inline]
+    pub fn level(&self, dist: f64) -> usize {
+        let level = (dist / self.fog.falloff * ((COLORMAP_LEVELS - 1) as f64)) as usize;
+        level.min(COLORMAP_LEVELS - 1)
+    }
+
+    /// The color map of a brightness level.
+    #[inline]
+    pub fn map(&self, level: usize) -> &[u8; 256] {
+        &self.maps[level]
+    }
+
+    /// Shade a color, as seen from a distance.
+    #[inline]
+    pub fn shade(&self, color: u8, dist: f64) -> u8 {
+        self.maps[self.level(dist)][color as usize]
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+const DEFAULT_FOG_FALLOFF: f64 = 12.0;
+/// Transparent pixels must stay transparent.
+const TRANSPARENT_COLOR: u8 = 0xFF;
+
+#[inline]
+fn blend(color: RGB, fog: RGB, t: f64) -> RGB {
+    let mix = |c: u8, f: u8| ((c as f64) + ((f as f64) - (c as f64)) * t).round() as u8;
+    RGB::from(mix(color.r, fog.r), mix(color.g, fog.g), mix(color.b, fog.b))
+}
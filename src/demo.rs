@@ -0,0 +1,196 @@
+//! Demos - the recorded games from VGAGRAPH (DEMO0 .. DEMO3), which the original plays in its attract loop.
+//! A demo is just the map number + the state of the controls, sampled every 4 tics (see WL_PLAY.C / WL_GAME.C).
+
+use crate::*;
+use std::rc::Rc;
+
+/// The original samples the controls every 4 tics (of 1/70 seconds) => each demo step lasts this long.
+pub const DEMO_STEP_TIME: f64 = DEMO_TICS as f64 / TICS_PER_SECOND;
+
+/// The button bits in a demo step (`buttonstate` in the original).
+pub const DEMO_BTN_ATTACK: u8 = 0x01;
+pub const DEMO_BTN_STRAFE: u8 = 0x02;
+pub const DEMO_BTN_RUN: u8 = 0x04;
+pub const DEMO_BTN_USE: u8 = 0x08;
+/// The 4 "ready weapon" buttons (knife, pistol, machine gun, chain gun) start from this bit.
+pub const DEMO_BTN_FIRST_WEAPON: u8 = 0x10;
+
+/// One demo step: the pressed buttons + the movement of the controls, per tic.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DemoStep {
+    pub buttons: u8,
+    /// Turn (or strafe, when the strafe button is held) - positive is to the right
+    pub dx: i8,
+    /// Move - negative is forward
+    pub dy: i8,
+}
+
+/// A recorded demo.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Demo {
+    /// The map index (like in `GameAssets::maps`)
+    pub map: usize,
+    pub steps: Vec<DemoStep>,
+}
+
+impl Demo {
+    /// Check if a VGAGRAPH chunk looks like a demo:
+    /// the map number, then the length of the demo (which must match the chunk), then 3 bytes for each step.
+    pub fn is_demo(bytes: &[u8]) -> bool {
+        bytes.len() >= DEMO_HEADER_SIZE
+            && (bytes.len() - DEMO_HEADER_SIZE).is_multiple_of(3)
+            && u16::from_le_bytes([bytes[1], bytes[2]]) as usize == bytes.len()
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if !Self::is_demo(bytes) {
+            return Err("Invalid demo header".to_string());
+        }
+        let steps = bytes[DEMO_HEADER_SIZE..]
+            .chunks_exact(3)
+            .map(|step| DemoStep {
+                buttons: step[0],
+                dx: step[1] as i8,
+                dy: step[2] as i8,
+            })
+            .collect();
+        Ok(Self {
+            map: bytes[0] as usize,
+            steps,
+        })
+    }
+
+    /// Encode the demo, in the original format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = DEMO_HEADER_SIZE + self.steps.len() * 3;
+        let mut bytes = Vec::with_capacity(len);
+        bytes.push(self.map as u8);
+        bytes.extend_from_slice(&(len as u16).to_le_bytes());
+        bytes.push(0);
+        for step in &self.steps {
+            bytes.extend_from_slice(&[step.buttons, step.dx as u8, step.dy as u8]);
+        }
+        bytes
+    }
+
+    #[inline]
+    pub fn duration(&self) -> f64 {
+        (self.steps.len() as f64) * DEMO_STEP_TIME
+    }
+}
+
+impl DemoStep {
+    /// Convert the step to the controls of the player. The buttons act only when pressed (like `buttonheld`
+    /// in the original), so the previous step is also needed.
+    /// Like in the original, the player turns by whole degrees, and the fraction is carried to the next step
+    /// (in `angle_frac`, which starts from 0) - this way, the player follows the recorded path.
+    pub fn controls(&self, prev: &DemoStep, angle_frac: &mut i32) -> PlayerControls {
+        let pressed = self.buttons & !prev.buttons;
+        // the controls are per tic => for the whole step, they are multiplied by the tics (like in PollControls)
+        let dx = (self.dx as i32) * DEMO_TICS;
+        let dy = (self.dy as f64) / BASE_MOVE;
+        let (turn, strafe) = if self.buttons & DEMO_BTN_STRAFE != 0 {
+            (0.0, (self.dx as f64) / BASE_MOVE)
+        } else {
+            // -> see ControlMovement in WL_AGENT.C
+            *angle_frac += dx;
+            let degrees = *angle_frac / (ANGLE_SCALE as i32);
+            *angle_frac -= degrees * (ANGLE_SCALE as i32);
+            // `PlayerControls` turns by BASE_MOVE / ANGLE_SCALE degrees per tic, for 1.0
+            let degrees_per_step = BASE_MOVE / ANGLE_SCALE * (DEMO_TICS as f64);
+            ((degrees as f64) / degrees_per_step, 0.0)
+        };
+        let weapon = (0..4).find(|w| pressed & (DEMO_BTN_FIRST_WEAPON << w) != 0);
+        PlayerControls {
+            forward: -dy,
+            strafe,
+            turn,
+            use_pressed: pressed & DEMO_BTN_USE != 0,
+            attack_pressed: pressed & DEMO_BTN_ATTACK != 0,
+            weapon,
+        }
+    }
+}
+
+//----------------------
+
+/// Plays a demo on its own `LiveMap` - for the attract mode, or to check that the simulation behaves.
+pub struct DemoPlayer {
+    livemap: LiveMap,
+    demo: Demo,
+    step_idx: usize,
+    /// The fraction of a degree, not turned yet
+    angle_frac: i32,
+    /// The time not played yet (less than a demo step)
+    pending_time: f64,
+}
+
+impl DemoPlayer {
+    /// Start a demo - like the original, on a new game. Fails if the demo map does not exist.
+    pub fn new(assets: Rc<GameAssets>, demo: Demo) -> Result<Self, String> {
+        let cnt_maps = assets.maps.len();
+        if demo.map >= cnt_maps {
+            return Err(format!("Demo on map {}, but there are only {cnt_maps} maps", demo.map));
+        }
+        let episode = u8::try_from(demo.map / 10).map_err(|_| format!("Invalid demo map: {}", demo.map))?;
+        let floor = (demo.map % 10) as u8;
+        Ok(Self {
+            livemap: LiveMap::new_on_floor(assets, episode, floor),
+            demo,
+            step_idx: 0,
+            angle_frac: 0,
+            pending_time: 0.0,
+        })
+    }
+
+    #[inline]
+    pub fn livemap(&self) -> &LiveMap {
+        &self.livemap
+    }
+
+    #[inline]
+    pub fn livemap_mut(&mut self) -> &mut LiveMap {
+        &mut self.livemap
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.step_idx >= self.demo.steps.len()
+    }
+
+    /// Play the next demo step (which lasts `DEMO_STEP_TIME`). Returns false if the demo is finished.
+    pub fn step(&mut self) -> bool {
+        let Some(step) = self.demo.steps.get(self.step_idx) else {
+            return false;
+        };
+        let prev = match self.step_idx {
+            0 => DemoStep::default(),
+            idx => self.demo.steps[idx - 1],
+        };
+        let controls = step.controls(&prev, &mut self.angle_frac);
+        self.livemap.update(&controls, DEMO_STEP_TIME);
+        self.step_idx += 1;
+        true
+    }
+
+    /// Play the demo in real time: play all the steps which fit in the elapsed time.
+    pub fn update(&mut self, elapsed_time: f64) {
+        self.pending_time += elapsed_time;
+        while self.pending_time >= DEMO_STEP_TIME && self.step() {
+            self.pending_time -= DEMO_STEP_TIME;
+        }
+    }
+
+    /// Play the whole demo, at once.
+    pub fn play_to_end(&mut self) {
+        while self.step() {}
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+const DEMO_HEADER_SIZE: usize = 4;
+/// How many tics each demo step lasts (DEMOTICS in the original)
+const DEMO_TICS: i32 = 4;
@@ -0,0 +1,164 @@
+//! Graphics exporter - writes the walls, sprites, pics and fonts to indexed PNG files.
+//! The PNG palette is the game palette (or the pic's own palette), so the exported files keep the original color indexes.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::{AssetError, FontData, GameAssets, GfxData, Palette, PicDict, PicType};
+
+/// The color index used for transparent pixels (in sprites and in font glyph sheets).
+pub const TRANSPARENT_COLOR: u8 = 0xFF;
+
+/// Export all graphics into a directory, as PNG files:
+/// `walls/`, `sprites/` and `pics/` (one file per item, named by index) + `font1.png` and `font2.png`.
+/// Empty items (e.g. missing in the shareware version) are skipped.
+/// Returns the number of written files.
+pub fn export_graphics(assets: &GameAssets, outdir: &Path) -> Result<usize, AssetError> {
+    let palette = &assets.palette;
+    let mut cnt = 0;
+    for (idx, wall) in assets.walls.iter().enumerate() {
+        cnt += export_one(
+            wall,
+            palette,
+            false,
+            &outdir.join("walls"),
+            &format!("wall_{idx:03}.png"),
+        )?;
+    }
+    for (idx, sprite) in assets.sprites.iter().enumerate() {
+        cnt += export_one(
+            sprite,
+            palette,
+            true,
+            &outdir.join("sprites"),
+            &format!("sprite_{idx:03}.png"),
+        )?;
+    }
+    for idx in 0..PicDict::pic_count() {
+        let pic = assets.pics.pic_by_index(idx);
+        let pic_palette = PicType::from_index(idx).and_then(|typ| assets.pics.pic_palette(typ));
+        cnt += export_one(
+            pic,
+            pic_palette.unwrap_or(palette),
+            false,
+            &outdir.join("pics"),
+            &format!("pic_{idx:03}.png"),
+        )?;
+    }
+    for (font, name) in [(&assets.font1, "font1.png"), (&assets.font2, "font2.png")] {
+        let sheet = font_glyph_sheet(font);
+        cnt += export_one(&sheet, palette, true, outdir, name)?;
+    }
+    println!("[ROLF3D] Exported {cnt} PNG files to: {}", outdir.display());
+    Ok(cnt)
+}
+
+/// Write a graphic to a PNG file.
+/// If `transparent` is set, pixels with `TRANSPARENT_COLOR` become transparent.
+pub fn write_png(path: &Path, gfx: &GfxData, palette: &Palette, transparent: bool) -> Result<(), AssetError> {
+    let io_error = |reason: String| AssetError::Io {
+        file: path.display().to_string(),
+        reason,
+    };
+    let file = File::create(path).map_err(|e| io_error(e.to_string()))?;
+    let mut writer = BufWriter::new(file);
+    encode_png(&mut writer, gfx, palette, transparent).map_err(io_error)?;
+    writer.flush().map_err(|e| io_error(e.to_string()))
+}
+
+/// Encode a graphic as an 8-bit indexed PNG, using the given palette.
+/// If `transparent` is set, pixels with `TRANSPARENT_COLOR` become transparent.
+pub fn encode_png<W: Write>(out: W, gfx: &GfxData, palette: &Palette, transparent: bool) -> Result<(), String> {
+    let (width, height) = gfx.size();
+    if width == 0 || height == 0 {
+        return Err("Cannot encode an empty graphic".to_string());
+    }
+    let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.to_rgb_bytes());
+    if transparent {
+        // all colors are opaque, except for the last one (= TRANSPARENT_COLOR)
+        let mut trns = vec![0xFF; 256];
+        trns[TRANSPARENT_COLOR as usize] = 0;
+        encoder.set_trns(trns);
+    }
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&rows_from_columns(gfx))
+        .map_err(|e| e.to_string())
+}
+
+/// Put all the characters of a font into one graphic: 16 characters per row, starting with the space.
+/// The cells have the size of the widest character, and the background is transparent.
+pub fn font_glyph_sheet(font: &FontData) -> GfxData {
+    const CHARS_PER_ROW: usize = 16;
+    const FIRST_CHAR: u8 = 32;
+    const LAST_CHAR: u8 = 127;
+    let height = font.font_height() as usize;
+    let cell_width = (FIRST_CHAR..=LAST_CHAR)
+        .filter_map(|ch| font.glyph(ch))
+        .map(|(w, _)| w as usize)
+        .max()
+        .unwrap_or(1);
+    let cnt_rows = ((LAST_CHAR - FIRST_CHAR + 1) as usize).div_ceil(CHARS_PER_ROW);
+    let sheet_width = cell_width * CHARS_PER_ROW;
+    let sheet_height = height * cnt_rows;
+
+    // the sheet is also stored column by column
+    let mut texels = vec![TRANSPARENT_COLOR; sheet_width * sheet_height];
+    for ch in FIRST_CHAR..=LAST_CHAR {
+        let Some((width, pixels)) = font.glyph(ch) else {
+            continue;
+        };
+        let cell = (ch - FIRST_CHAR) as usize;
+        let x0 = (cell % CHARS_PER_ROW) * cell_width;
+        let y0 = (cell / CHARS_PER_ROW) * height;
+        for x in 0..width as usize {
+            for y in 0..height {
+                let c = pixels[x * height + y];
+                if c != 0 {
+                    texels[(x0 + x) * sheet_height + y0 + y] = c;
+                }
+            }
+        }
+    }
+    GfxData::new_pic(sheet_width as u16, sheet_height as u16, texels)
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// Export one graphic, if not empty. Returns the number of written files (0 or 1).
+fn export_one(
+    gfx: &GfxData,
+    palette: &Palette,
+    transparent: bool,
+    dir: &Path,
+    filename: &str,
+) -> Result<usize, AssetError> {
+    let (width, height) = gfx.size();
+    if width == 0 || height == 0 {
+        return Ok(0);
+    }
+    std::fs::create_dir_all(dir).map_err(|e| AssetError::Io {
+        file: dir.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    write_png(&dir.join(filename), gfx, palette, transparent)?;
+    Ok(1)
+}
+
+/// The graphics are stored column by column, but PNG wants them row by row.
+fn rows_from_columns(gfx: &GfxData) -> Vec<u8> {
+    let (width, height) = gfx.size();
+    let (width, height) = (width as usize, height as usize);
+    let texels = gfx.texels();
+    let mut rows = Vec::with_capacity(width * height);
+    for y in 0..height {
+        rows.extend((0..width).map(|x| texels[x * height + y]));
+    }
+    rows
+}
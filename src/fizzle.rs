@@ -0,0 +1,134 @@
+//! FizzleFade - the screen dissolves into another one, pixel by pixel, in a pseudo-random order.
+//! Like in the original (see `FizzleFade` in ID_VH.C), the order comes from a linear-feedback shift register,
+//! so every pixel is visited exactly once, without having to shuffle (or remember) anything.
+
+/// The pixel indexes `0..len`, each exactly once, in the LFSR order.
+#[derive(Clone, Debug)]
+pub struct FizzleOrder {
+    state: u32,
+    mask: u32,
+    len: usize,
+    remaining: usize,
+}
+
+impl FizzleOrder {
+    pub fn new(len: usize) -> Self {
+        // use the smallest register which has enough states (the state is never 0 => 2^bits - 1 states)
+        let bits = (2..=32).find(|&bits| ((1u64 << bits) - 1) >= len as u64).unwrap_or(32);
+        Self {
+            state: 1,
+            mask: LFSR_MASKS[bits - 2],
+            len,
+            remaining: len,
+        }
+    }
+}
+
+impl Iterator for FizzleOrder {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.remaining > 0 {
+            let idx = (self.state - 1) as usize;
+            // the original: shift right, and XOR the taps if a 1 was shifted out
+            let lsb = self.state & 1;
+            self.state >>= 1;
+            if lsb != 0 {
+                self.state ^= self.mask;
+            }
+            // the register may have more states than pixels => skip those
+            if idx < self.len {
+                self.remaining -= 1;
+                return Some(idx);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// A FizzleFade from a source frame to a target frame (e.g. the screen buffer bytes), over some time.
+pub struct FizzleFade {
+    frame: Vec<u8>,
+    target: Vec<u8>,
+    order: FizzleOrder,
+    revealed: usize,
+    duration: f64,
+    elapsed: f64,
+}
+
+impl FizzleFade {
+    /// Start fading from `source` to `target`, which must have the same size. The duration is in seconds.
+    pub fn new(source: Vec<u8>, target: Vec<u8>, duration: f64) -> Self {
+        assert_eq!(source.len(), target.len(), "FizzleFade frames must have the same size");
+        let order = FizzleOrder::new(target.len());
+        Self {
+            frame: source,
+            target,
+            order,
+            revealed: 0,
+            duration,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Reveal more pixels - as many as needed for the elapsed time.
+    pub fn update(&mut self, elapsed_time: f64) {
+        self.elapsed += elapsed_time;
+        let len = self.target.len();
+        let should_reveal = if self.duration <= 0.0 || self.elapsed >= self.duration {
+            len
+        } else {
+            ((len as f64) * self.elapsed / self.duration) as usize
+        };
+        while self.revealed < should_reveal {
+            let Some(idx) = self.order.next() else {
+                break;
+            };
+            self.frame[idx] = self.target[idx];
+            self.revealed += 1;
+        }
+    }
+
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.revealed >= self.target.len()
+    }
+
+    /// How much of the target was revealed (0.0 ... 1.0).
+    #[inline]
+    pub fn progress(&self) -> f64 {
+        if self.target.is_empty() {
+            1.0
+        } else {
+            (self.revealed as f64) / (self.target.len() as f64)
+        }
+    }
+
+    /// What should be on the screen now.
+    #[inline]
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// The frame which is faded into.
+    #[inline]
+    pub fn target(&self) -> &[u8] {
+        &self.target
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The taps of maximal-length Galois LFSRs, for 2 ... 32 bits.
+/// The 17-bit one (0x12000) is the one used by the original, for its 320x200 screen.
+const LFSR_MASKS: &[u32] = &[
+    0x3, 0x6, 0xC, 0x14, 0x30, 0x60, 0xB8, 0x110, 0x240, 0x500, 0x829, 0x100D, 0x2015, 0x6000, 0xD008, 0x12000,
+    0x20400, 0x40023, 0x90000, 0x140000, 0x300000, 0x420000, 0xE10000, 0x1200000, 0x2000023, 0x4000013, 0x9000000,
+    0x14000000, 0x20000029, 0x48000000, 0x80200003,
+];
@@ -9,12 +9,21 @@ use std::rc::Rc;
 
 pub struct GameLoop {
     scrbuf: ScreenBuffer,
-    _assets: Rc<GameAssets>,
+    assets: Rc<GameAssets>,
     mode: GameMode,
     livemap: LiveMap,
+    /// The demo played in the attract mode (on its own map, so the game is not affected)
+    demo: Option<DemoPlayer>,
+    demo_idx: usize,
     automap: AutomapRenderer,
+    articles: ArticleViewer,
     inputs: InputManager,
+    sound_player: SoundPlayer,
     status_bar_enabled: bool, // TODO move to some GameConfig struct
+    /// Go to the next floor once the screen has faded out
+    next_floor_pending: bool,
+    /// How long since the player died (in the `Death` mode)
+    death_time: f64,
 }
 
 impl GameLoop {
@@ -24,13 +33,19 @@ impl GameLoop {
         let livemap = LiveMap::new(Rc::clone(&ga), 0);
 
         let mut zelf = Self {
-            scrbuf: ScreenBuffer::new(width, height, ga.is_sod),
-            _assets: Rc::clone(&ga),
+            scrbuf: ScreenBuffer::new(width, height, ga.palette.clone()),
+            assets: Rc::clone(&ga),
             mode: GameMode::Live,
             livemap,
+            demo: None,
+            demo_idx: 0,
             automap: AutomapRenderer::new(Rc::clone(&ga)),
+            articles: ArticleViewer::new(Rc::clone(&ga)),
             inputs: InputManager::new(pixel_size),
+            sound_player: SoundPlayer::new(Rc::clone(&ga), DEFAULT_SAMPLE_RATE),
             status_bar_enabled: false,
+            next_floor_pending: false,
+            death_time: 0.0,
         };
 
         zelf.enable_status_bar(true);
@@ -41,6 +56,94 @@ impl GameLoop {
         self.status_bar_enabled = enabled;
         self.scrbuf.enable_status_bar(enabled);
     }
+
+    /// Play a demo, in the attract mode. The demos are played in a loop.
+    fn start_demo(&mut self, demo_idx: usize) {
+        let demos = &self.assets.demos;
+        if demos.is_empty() {
+            return;
+        }
+        self.demo_idx = demo_idx % demos.len();
+        let demo = demos[self.demo_idx].clone();
+        match DemoPlayer::new(Rc::clone(&self.assets), demo) {
+            Ok(player) => self.demo = Some(player),
+            Err(msg) => {
+                println!("[ROLF3D] Cannot play demo #{}: {msg}", self.demo_idx);
+                return;
+            }
+        }
+        self.mode = GameMode::Demo;
+        let effects = self.scrbuf.effects_mut();
+        effects.clear_flashes();
+        effects.fade_in();
+    }
+
+    /// The player died => the 3D view fizzles to red (see `Died` in WL_GAME.C), then the floor restarts.
+    fn start_death(&mut self) {
+        self.mode = GameMode::Death;
+        self.death_time = 0.0;
+        self.sound_player.play_sound(SoundEffect::PlayerDeath);
+        self.scrbuf.effects_mut().clear_flashes();
+
+        let source = self.scrbuf.frame();
+        let (width, view_height) = (self.scrbuf.scr_width(), self.scrbuf.view_height());
+        self.scrbuf.fill_rect(0, 0, width, view_height, DEATH_COLOR);
+        let target = self.scrbuf.frame();
+        self.scrbuf.start_transition(source, target, DEATH_FIZZLE_TIME);
+    }
+
+    /// A boss was killed => like the death cam of the original (see `DeathCam` in WL_AGENT.C),
+    /// the view is cleared with "Let's see that again!", then it fizzles into the 3D view.
+    // TODO turn the view towards the boss and replay its death, once the actors exist
+    fn start_death_cam(&mut self) {
+        self.mode = GameMode::DeathCam;
+        self.death_time = 0.0;
+        self.scrbuf.effects_mut().clear_flashes();
+
+        let (width, view_height) = (self.scrbuf.scr_width(), self.scrbuf.view_height());
+        self.scrbuf.fill_rect(0, 0, width, view_height, DEATH_CAM_COLOR);
+        let font = &self.assets.font1;
+        let x = (width - font.text_width(DEATH_CAM_TEXT)) / 2;
+        font.draw_text(
+            x,
+            view_height / 2,
+            DEATH_CAM_TEXT,
+            DEATH_CAM_TEXT_COLOR,
+            &mut self.scrbuf,
+        );
+        let source = self.scrbuf.frame();
+        self.livemap.paint_3d(&mut self.scrbuf);
+        let target = self.scrbuf.frame();
+        self.scrbuf.start_transition(source, target, DEATH_FIZZLE_TIME);
+    }
+
+    /// Fade out, go to the next floor, then fade in (the floor changes in `update_palette_effects`).
+    fn start_next_floor(&mut self) {
+        if !self.next_floor_pending {
+            self.next_floor_pending = true;
+            self.scrbuf.effects_mut().fade_out();
+        }
+    }
+
+    /// Update the palette flashes and fades, and start the new ones.
+    fn update_palette_effects(&mut self, elapsed_time: f64) {
+        let mut flashes = self.livemap.take_palette_flashes();
+        if let Some(demo) = &mut self.demo {
+            flashes.extend(demo.livemap_mut().take_palette_flashes());
+        }
+        let effects = self.scrbuf.effects_mut();
+        effects.update(elapsed_time);
+        for flash in flashes {
+            effects.start_flash(flash);
+        }
+
+        if self.next_floor_pending && effects.is_black() {
+            self.next_floor_pending = false;
+            effects.clear_flashes();
+            effects.fade_in();
+            self.livemap.go_to_next_floor();
+        }
+    }
 }
 
 impl GraphicsLoop for GameLoop {
@@ -59,7 +162,7 @@ impl GraphicsLoop for GameLoop {
 
         // TODO temp hack, to scroll between maps
         if self.inputs.consume_key(Keycode::Insert) {
-            self.livemap.go_to_next_floor();
+            self.start_next_floor();
         }
         true
     }
@@ -72,13 +175,34 @@ impl GraphicsLoop for GameLoop {
             self.enable_status_bar(false);
         }
 
+        // toggle the lighting (distance shading + fog)
+        if self.inputs.consume_key(Keycode::L) {
+            self.scrbuf.enable_lighting(!self.scrbuf.lighting_enabled());
+        }
+
         if self.inputs.consume_key(Keycode::Tab) {
             match self.mode {
                 GameMode::Live => self.mode = GameMode::Automap,
                 GameMode::Automap => self.mode = GameMode::Live,
+                GameMode::Article | GameMode::Demo | GameMode::Death | GameMode::DeathCam => {}
             }
         }
 
+        // TODO temp hack, to start the attract mode (until there is a title screen / main menu)
+        if self.mode == GameMode::Live && self.inputs.consume_key(Keycode::Home) {
+            self.start_demo(0);
+        }
+
+        // F1 = help, like in the original - if the game has no help (e.g. SOD), F1 is left to the live map
+        let can_show_article = matches!(self.mode, GameMode::Live | GameMode::Automap);
+        if can_show_article
+            && self.articles.has_help()
+            && self.inputs.consume_key(Keycode::F1)
+            && self.articles.show_help()
+        {
+            self.mode = GameMode::Article;
+        }
+
         // TODO temporary: manual loop through pics
         if self.inputs.key(Keycode::F8) {
             _temp_advance_fwd(0.1);
@@ -90,7 +214,10 @@ impl GraphicsLoop for GameLoop {
 
         // paint status bar
         if self.status_bar_enabled {
-            self.livemap.paint_status_bar(&mut self.scrbuf);
+            match &self.demo {
+                Some(demo) if self.mode == GameMode::Demo => demo.livemap().paint_status_bar(&mut self.scrbuf),
+                _ => self.livemap.paint_status_bar(&mut self.scrbuf),
+            }
         }
 
         // update depending on game state
@@ -98,17 +225,98 @@ impl GraphicsLoop for GameLoop {
             GameMode::Live => {
                 self.livemap.handle_inputs(&mut self.inputs, elapsed_time);
                 self.livemap.paint_3d(&mut self.scrbuf);
+                if self.livemap.is_player_dead() {
+                    self.start_death();
+                } else if self.livemap.take_boss_kill() {
+                    self.start_death_cam();
+                }
             }
             GameMode::Automap => {
-                self.automap.handle_inputs(&mut self.inputs, elapsed_time);
+                self.automap
+                    .handle_inputs(&mut self.inputs, &self.livemap, elapsed_time);
                 self.automap.paint(&self.livemap, &mut self.scrbuf);
             }
+            GameMode::Article => {
+                self.articles.handle_inputs(&mut self.inputs, elapsed_time);
+                self.articles.paint(&mut self.scrbuf);
+                if !self.articles.is_open() {
+                    self.mode = GameMode::Live;
+                }
+            }
+            GameMode::Demo => {
+                // Escape ends the attract mode
+                if self.inputs.consume_key(Keycode::Escape) {
+                    self.demo = None;
+                    self.mode = GameMode::Live;
+                } else if let Some(demo) = &mut self.demo {
+                    demo.update(elapsed_time);
+                    demo.livemap().paint_3d(&mut self.scrbuf);
+                    for snd in demo.livemap_mut().take_sounds() {
+                        self.sound_player.play_sound(snd);
+                    }
+                    if demo.is_finished() {
+                        self.start_demo(self.demo_idx + 1);
+                    }
+                }
+            }
+            GameMode::Death => {
+                self.death_time += elapsed_time;
+                if !self.scrbuf.is_transition_running() && self.death_time >= DEATH_TIME {
+                    if !self.livemap.restart_after_death() {
+                        // TODO game over screen + high scores
+                        println!("[ROLF3D] Game over");
+                        self.livemap = LiveMap::new(Rc::clone(&self.assets), self.livemap.episode());
+                    }
+                    self.mode = GameMode::Live;
+                }
+            }
+            GameMode::DeathCam => {
+                self.death_time += elapsed_time;
+                if !self.scrbuf.is_transition_running() && self.death_time >= DEATH_TIME {
+                    // the episode is won => the end-of-episode text, if the game has one
+                    // TODO the victory screen (with the episode stats) comes before it, in the original
+                    self.mode = if self.articles.show_episode_end(self.livemap.episode()) {
+                        GameMode::Article
+                    } else {
+                        GameMode::Live
+                    };
+                }
+            }
+        }
+
+        // play the sounds triggered by the game
+        for snd in self.livemap.take_sounds() {
+            self.sound_player.play_sound(snd);
         }
+        self.update_palette_effects(elapsed_time);
+        self.scrbuf.update_transition(elapsed_time);
 
         true
     }
 
+    fn play_audio(&mut self, sink: &mut dyn AudioSink, elapsed_time: f64) {
+        if let Err(msg) = self.sound_player.update(sink, elapsed_time) {
+            // TODO should audio errors disable the sound ?
+            println!("[ROLF3D] Audio error: {msg}");
+        }
+    }
+
     fn paint(&self, painter: &mut dyn Painter) {
         self.scrbuf.paint(painter);
     }
 }
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The original fizzles the view in 70 tics
+const DEATH_FIZZLE_TIME: f64 = 1.0;
+/// How long the death sequence lasts, before the floor restarts
+const DEATH_TIME: f64 = 2.5;
+/// The view turns red when the player dies
+const DEATH_COLOR: u8 = 4;
+/// The death cam starts from a cleared view, with a message (like `VW_Bar` + `US_CPrint` in the original)
+const DEATH_CAM_COLOR: u8 = 127;
+const DEATH_CAM_TEXT_COLOR: u8 = 15;
+const DEATH_CAM_TEXT: &str = "Let's see that again!";
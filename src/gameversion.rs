@@ -0,0 +1,170 @@
+//! Game version identification - tells the exact release of the game files, not just the game type.
+//!
+//! The file extension only says which game it is (WL1, WL6, SOD ...), but different releases of the same game
+//! (Apogee 1.0/1.1/1.4, Activision, GT ...) may have a different layout of the chunks.
+//! So the releases are identified by a fingerprint: the CRC-32 of VGAHEAD, MAPHEAD and of the VSWAP header.
+//! Level packs replace the maps (and MAPHEAD) of a release => if only MAPHEAD is different,
+//! the release is still identified, but its maps are reported as modified.
+
+use std::fmt;
+
+use crate::{buf_to_u16, crc32};
+
+/// The known game releases (the extension tells if it is the shareware or the registered version).
+/// Releases with an unknown fingerprint are `Unknown`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameRelease {
+    /// Wolfenstein 3D v1.0 (Apogee)
+    Wolf3dApogee10,
+    /// Wolfenstein 3D v1.1 (Apogee)
+    Wolf3dApogee11,
+    /// Wolfenstein 3D v1.4 (Apogee)
+    Wolf3dApogee14,
+    /// Wolfenstein 3D v1.4 (Activision)
+    Wolf3dActivision,
+    /// Wolfenstein 3D v1.4 (GT Interactive)
+    Wolf3dGT,
+    Unknown,
+}
+
+impl GameRelease {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameRelease::Wolf3dApogee10 => "Wolfenstein 3D v1.0 (Apogee)",
+            GameRelease::Wolf3dApogee11 => "Wolfenstein 3D v1.1 (Apogee)",
+            GameRelease::Wolf3dApogee14 => "Wolfenstein 3D v1.4 (Apogee)",
+            GameRelease::Wolf3dActivision => "Wolfenstein 3D v1.4 (Activision)",
+            GameRelease::Wolf3dGT => "Wolfenstein 3D v1.4 (GT Interactive)",
+            GameRelease::Unknown => "unknown release",
+        }
+    }
+
+    /// The releases built with GOODTIMES (GT and Activision) have no "Read This!" help:
+    /// F1 is the boss key there (see `US_ControlPanel` in WL_MENU.C).
+    #[inline]
+    pub fn has_help(self) -> bool {
+        !matches!(self, GameRelease::Wolf3dActivision | GameRelease::Wolf3dGT)
+    }
+}
+
+/// The fingerprint of the game files: the CRC-32 of VGAHEAD, MAPHEAD
+/// and of the VSWAP header (the chunk counters, offsets and lengths).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub vgahead: u32,
+    pub maphead: u32,
+    pub vswap_header: u32,
+}
+
+impl Fingerprint {
+    /// Compute the fingerprint from the file contents.
+    /// `vswap` may be the whole VSWAP file, or just its header. Returns `None` if the VSWAP header is truncated.
+    pub fn new(vgahead: &[u8], maphead: &[u8], vswap: &[u8]) -> Option<Self> {
+        let header_len = vswap_header_len(vswap)?;
+        Some(Self {
+            vgahead: crc32(vgahead),
+            maphead: crc32(maphead),
+            vswap_header: crc32(&vswap[0..header_len]),
+        })
+    }
+
+    /// Same graphics and sounds as the other fingerprint (the maps may be different).
+    #[inline]
+    pub fn same_graphics(&self, other: &Fingerprint) -> bool {
+        self.vgahead == other.vgahead && self.vswap_header == other.vswap_header
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "VGAHEAD={:08X} MAPHEAD={:08X} VSWAP={:08X}",
+            self.vgahead, self.maphead, self.vswap_header
+        )
+    }
+}
+
+/// The identified version of the game files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameVersion {
+    /// The extension of the game files (WL1, WL6, SOD ...)
+    pub ext: &'static str,
+    pub release: GameRelease,
+    pub fingerprint: Fingerprint,
+    /// The maps are not the ones of the release (e.g. a level pack).
+    pub modified_maps: bool,
+}
+
+impl GameVersion {
+    /// Look up the fingerprint in the list of known releases (for the given extension).
+    /// If only MAPHEAD does not match, it is still the same release, but with modified maps.
+    pub fn identify(ext: &'static str, fingerprint: Fingerprint) -> Self {
+        let (release, modified_maps) = KNOWN_RELEASES
+            .iter()
+            .find(|(rel_ext, _, fp)| rel_ext.eq_ignore_ascii_case(ext) && fp.same_graphics(&fingerprint))
+            .map(|(_, release, fp)| (*release, fp.maphead != fingerprint.maphead))
+            .unwrap_or((GameRelease::Unknown, false));
+        Self {
+            ext,
+            release,
+            fingerprint,
+            modified_maps,
+        }
+    }
+
+    #[inline]
+    pub fn is_known(&self) -> bool {
+        self.release != GameRelease::Unknown
+    }
+
+    /// Spear of Destiny (or its demo), which has its own rules (e.g. other solid decorations, no help).
+    #[inline]
+    pub fn is_sod(&self) -> bool {
+        self.ext.starts_with(['S', 's'])
+    }
+
+    /// True if the game has "Read This!" help pages (F1).
+    #[inline]
+    pub fn has_help(&self) -> bool {
+        !self.is_sod() && self.release.has_help()
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_known() {
+            write!(f, "{} ({})", self.release.name(), self.ext)?;
+            if self.modified_maps {
+                write!(f, " with modified maps")?;
+            }
+            Ok(())
+        } else {
+            write!(f, "{} {} [{}]", self.ext, self.release.name(), self.fingerprint)
+        }
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The fingerprints of the known releases.
+// TODO add the fingerprints of the other releases (Apogee 1.0 and 1.1, the registered 1.4, Activision, GT)
+// - just run the game with their files, and copy the printed fingerprint :)
+const KNOWN_RELEASES: &[(&str, GameRelease, Fingerprint)] = &[(
+    "WL1",
+    GameRelease::Wolf3dApogee14,
+    Fingerprint {
+        vgahead: 0xA60A98F0,
+        maphead: 0x088F7551,
+        vswap_header: 0x81BF8FD0,
+    },
+)];
+
+/// The VSWAP header: 3 counters + an offset (u32) and a length (u16) for each chunk.
+fn vswap_header_len(vswap: &[u8]) -> Option<usize> {
+    let cnt_chunks = vswap.get(0..2).map(buf_to_u16)? as usize;
+    let len = 6 + cnt_chunks * 6;
+    (len <= vswap.len()).then_some(len)
+}
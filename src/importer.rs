@@ -0,0 +1,235 @@
+//! Graphics importer - the reverse of the exporter.
+//! Reads true-color PNG/BMP images, quantizes them to the game palette (WL or SOD)
+//! and turns them into `GfxData`, so they can replace the walls and sprites from VSWAP.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::utils::*;
+use crate::{AssetError, AssetSource, GfxData, GfxPages, Palette, RGB, TRANSPARENT_COLOR};
+
+/// The directory (next to the game files) which holds the replacement graphics.
+/// It has the same layout as the exported graphics: `walls/wall_NNN.png` and `sprites/sprite_NNN.png`
+/// (BMP files are also accepted). Mods (see `LayeredSource`) can also contain replacement graphics,
+/// using the same layout.
+pub const REPLACEMENTS_DIR: &str = "mods";
+
+/// Import a PNG or BMP image, as a graphic which uses the given palette.
+/// If `transparent` is set, (mostly) transparent pixels become `TRANSPARENT_COLOR`,
+/// otherwise the alpha channel is ignored. Opaque pixels never use `TRANSPARENT_COLOR`.
+pub fn import_image(path: &Path, palette: &Palette, transparent: bool) -> Result<GfxData, AssetError> {
+    let bytes = std::fs::read(path).map_err(|e| AssetError::Io {
+        file: path.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    import_image_bytes(&bytes, &path.display().to_string(), palette, transparent)
+}
+
+/// Import a PNG or BMP image from memory - same as `import_image`.
+/// The file name is used for detecting BMP images (by extension) and for errors.
+pub fn import_image_bytes(
+    bytes: &[u8],
+    filename: &str,
+    palette: &Palette,
+    transparent: bool,
+) -> Result<GfxData, AssetError> {
+    let is_bmp = Path::new(filename)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("bmp"));
+    let image = if is_bmp {
+        decode_bmp(bytes).map_err(|(offset, reason)| AssetError::bad_data(filename, None, offset, reason))?
+    } else {
+        decode_png(bytes).map_err(|reason| AssetError::bad_data(filename, None, 0, reason))?
+    };
+    if image.width == 0 || image.height == 0 || image.width > 0xFFFF || image.height > 0xFFFF {
+        let reason = format!("Invalid image size: {}x{}", image.width, image.height);
+        return Err(AssetError::bad_data(filename, None, 0, reason));
+    }
+    Ok(quantize_image(&image, palette, transparent))
+}
+
+/// Find the palette color closest to an RGB color (never returns `TRANSPARENT_COLOR`).
+#[inline]
+pub fn nearest_palette_color(r: u8, g: u8, b: u8, palette: &Palette) -> u8 {
+    palette.nearest_color(RGB::from(r, g, b), Some(TRANSPARENT_COLOR))
+}
+
+/// Replace walls and sprites with the images found in an asset source (see `REPLACEMENTS_DIR`).
+/// Replacements must be 64x64, like the VSWAP pages. Returns the number of replaced graphics.
+pub fn load_gfx_replacements(
+    source: &dyn AssetSource,
+    walls: &mut GfxPages,
+    sprites: &mut GfxPages,
+    palette: &Palette,
+) -> Result<usize, AssetError> {
+    let mut cnt = 0;
+    for (subdir, prefix, gfx_list, transparent) in
+        [("walls", "wall", walls, false), ("sprites", "sprite", sprites, true)]
+    {
+        for idx in 0..gfx_list.len() {
+            let found = ["png", "bmp"]
+                .iter()
+                .map(|ext| format!("{subdir}/{prefix}_{idx:03}.{ext}"))
+                .find_map(|filename| source.locate(&filename).map(|path| (filename, path)));
+            let Some((filename, path)) = found else {
+                continue;
+            };
+            let bytes = source.read(&filename)?;
+            let replacement = import_image_bytes(&bytes, &path, palette, transparent)?;
+            if replacement.size() != (64, 64) {
+                let (w, h) = replacement.size();
+                let reason = format!("Replacement must be 64x64, not {w}x{h}");
+                return Err(AssetError::bad_data(&path, None, 0, reason));
+            }
+            gfx_list.set(idx, replacement);
+            cnt += 1;
+        }
+    }
+    Ok(cnt)
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// A decoded image: RGBA pixels, row by row.
+struct RgbaImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+/// Alpha values below this are considered transparent.
+const ALPHA_THRESHOLD: u8 = 0x80;
+
+fn quantize_image(image: &RgbaImage, palette: &Palette, transparent: bool) -> GfxData {
+    // images usually have few colors => remember the ones already quantized
+    let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+    // GfxData wants the pixels column by column
+    let mut texels = Vec::with_capacity(image.width * image.height);
+    for x in 0..image.width {
+        for y in 0..image.height {
+            let [r, g, b, a] = image.pixels[y * image.width + x];
+            let c = if transparent && a < ALPHA_THRESHOLD {
+                TRANSPARENT_COLOR
+            } else {
+                *cache
+                    .entry([r, g, b])
+                    .or_insert_with(|| nearest_palette_color(r, g, b, palette))
+            };
+            texels.push(c);
+        }
+    }
+    GfxData::new_pic(image.width as u16, image.height as u16, texels)
+}
+
+/// Decode a PNG image, of any type (indexed, gray, RGB, with or without alpha).
+fn decode_png<R: std::io::Read>(input: R) -> Result<RgbaImage, String> {
+    let mut decoder = png::Decoder::new(input);
+    // expand everything to 8 bits per channel, and the palettes to RGB(A)
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| e.to_string())?;
+    let bytes = &buf[..info.buffer_size()];
+    let pixels: Vec<[u8; 4]> = match info.color_type {
+        png::ColorType::Grayscale => bytes.iter().map(|&v| [v, v, v, 0xFF]).collect(),
+        png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Rgb => bytes.chunks_exact(3).map(|p| [p[0], p[1], p[2], 0xFF]).collect(),
+        png::ColorType::Rgba => bytes.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect(),
+        png::ColorType::Indexed => return Err("Indexed PNG was not expanded".to_string()),
+    };
+    Ok(RgbaImage {
+        width: info.width as usize,
+        height: info.height as usize,
+        pixels,
+    })
+}
+
+/// Decode an uncompressed BMP image: 8 bits (with palette), 24 bits or 32 bits per pixel.
+/// Errors contain the offset in the file.
+fn decode_bmp(bytes: &[u8]) -> Result<RgbaImage, (usize, String)> {
+    let u16_at = |ofs: usize| {
+        bytes
+            .get(ofs..ofs + 2)
+            .map(buf_to_u16)
+            .ok_or((ofs, "BMP ends abruptly".to_string()))
+    };
+    let u32_at = |ofs: usize| {
+        bytes
+            .get(ofs..ofs + 4)
+            .map(buf_to_u32)
+            .ok_or((ofs, "BMP ends abruptly".to_string()))
+    };
+
+    if bytes.get(0..2) != Some(b"BM") {
+        return Err((0, "Not a BMP file".to_string()));
+    }
+    let data_ofs = u32_at(10)? as usize;
+    let header_len = u32_at(14)? as usize;
+    if header_len < 40 {
+        return Err((14, format!("Unsupported BMP header ({header_len} bytes)")));
+    }
+    let width = u32_at(18)? as i32;
+    let height = u32_at(22)? as i32;
+    let bpp = u16_at(28)?;
+    let compression = u32_at(30)?;
+    // 32-bit BMPs may use BITFIELDS, but in practice they are always BGRA
+    if compression != 0 && !(compression == 3 && bpp == 32) {
+        return Err((30, format!("Unsupported BMP compression: {compression}")));
+    }
+    if width <= 0 || height == 0 || width > 0xFFFF || height.unsigned_abs() > 0xFFFF {
+        return Err((18, format!("Invalid BMP size: {width}x{height}")));
+    }
+    // positive height => the rows are stored bottom-up
+    let bottom_up = height > 0;
+    let (width, height) = (width as usize, height.unsigned_abs() as usize);
+
+    // the palette (for 8 bits/pixel) follows the header: BGR0 entries
+    let palette = if bpp == 8 {
+        let cnt_colors = match u32_at(46)? {
+            0 => 256,
+            n => (n as usize).min(256),
+        };
+        let ofs = 14 + header_len;
+        let entries = bytes
+            .get(ofs..ofs + 4 * cnt_colors)
+            .ok_or((ofs, "BMP palette ends abruptly".to_string()))?;
+        entries.chunks_exact(4).map(|e| [e[2], e[1], e[0], 0xFF]).collect()
+    } else {
+        vec![]
+    };
+    let bytes_per_pixel = match bpp {
+        8 => 1,
+        24 => 3,
+        32 => 4,
+        _ => return Err((28, format!("Unsupported BMP bits per pixel: {bpp}"))),
+    };
+
+    // each row is padded to 4 bytes
+    let row_len = (width * bytes_per_pixel + 3) & !3;
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row_idx = if bottom_up { height - 1 - y } else { y };
+        let ofs = data_ofs + row_idx * row_len;
+        let row = bytes
+            .get(ofs..ofs + width * bytes_per_pixel)
+            .ok_or((ofs, "BMP pixels end abruptly".to_string()))?;
+        for p in row.chunks_exact(bytes_per_pixel) {
+            let rgba = match bpp {
+                8 => *palette
+                    .get(p[0] as usize)
+                    .ok_or((ofs, format!("BMP color {} is not in the palette", p[0])))?,
+                24 => [p[2], p[1], p[0], 0xFF],
+                _ => [p[2], p[1], p[0], p[3]],
+            };
+            pixels.push(rgba);
+        }
+    }
+    // many 32-bit BMPs have no alpha at all (all zero) => they are opaque
+    if bpp == 32 && pixels.iter().all(|p| p[3] == 0) {
+        pixels.iter_mut().for_each(|p| p[3] = 0xFF);
+    }
+
+    Ok(RgbaImage { width, height, pixels })
+}
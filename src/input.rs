@@ -1,5 +1,6 @@
 //! InputManager - handles keyboard & mouse, knows if key/mousebtn is pressed, set key timings.
 
+use crate::TICS_PER_SECOND;
 use sdl2::event::Event;
 use sdl2::keyboard::*;
 use sdl2::mouse::*;
@@ -122,9 +123,76 @@ impl InputManager {
     }
 }
 
+//------------------
+
+/// What the player wants to do, in one update of the game world - from the keyboard, or from a demo.
+/// The speeds are relative to walking (1.0 = walk, 2.0 = run, like BASEMOVE and RUNMOVE in the original).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerControls {
+    /// Move forward (negative = backwards)
+    pub forward: f64,
+    /// Move sideways (positive = to the right)
+    pub strafe: f64,
+    /// Turn (positive = to the right)
+    pub turn: f64,
+    pub use_pressed: bool,
+    pub attack_pressed: bool,
+    /// The weapon to select (0 = knife ... 3 = chain gun)
+    pub weapon: Option<u8>,
+}
+
+/// Walking, in the units of the original controls (BASEMOVE) - `PlayerControls` uses 1.0 for it.
+pub const BASE_MOVE: f64 = 35.0;
+/// Each tic, the original moves the player by (control * MOVE_SCALE) / TILE_GLOBAL tiles - or by
+/// (control * BACK_MOVE_SCALE) / TILE_GLOBAL, when moving backwards - and turns it by (control / ANGLE_SCALE) degrees.
+/// -> see ControlMovement in WL_AGENT.C
+pub const MOVE_SCALE: f64 = 150.0;
+pub const BACK_MOVE_SCALE: f64 = 100.0;
+pub const ANGLE_SCALE: f64 = 20.0;
+/// The size of a tile, in the fixed-point units of the original.
+pub const TILE_GLOBAL: f64 = 65536.0;
+/// The walking speed, in tiles per second (~5.6).
+pub const PLAYER_MOVE_SPEED: f64 = BASE_MOVE * MOVE_SCALE / TILE_GLOBAL * TICS_PER_SECOND;
+/// The turning speed (when walking), in radians per second (122.5 degrees).
+pub const PLAYER_TURN_SPEED: f64 = BASE_MOVE / ANGLE_SCALE * TICS_PER_SECOND * std::f64::consts::PI / 180.0;
+
+impl PlayerControls {
+    /// Read the controls from the keyboard. The pressed keys (use, attack, weapons) are consumed.
+    pub fn from_inputs(inputs: &mut InputManager) -> Self {
+        let weapon_keys = [Keycode::Num1, Keycode::Num2, Keycode::Num3, Keycode::Num4];
+        let weapon = (0..4).find(|&w| inputs.consume_key(weapon_keys[w as usize]));
+        let use_pressed = inputs.consume_key(Keycode::E) || inputs.consume_key(Keycode::Space);
+        // TODO (later) do NOT consume the key - machine gun and chain gun are automatic
+        let attack_pressed = inputs.consume_key(Keycode::LCtrl) || inputs.consume_key(Keycode::RCtrl);
+        Self {
+            forward: axis(
+                inputs.key(Keycode::W) || inputs.key(Keycode::Up),
+                inputs.key(Keycode::S) || inputs.key(Keycode::Down),
+            ),
+            strafe: -axis(inputs.key(Keycode::A), inputs.key(Keycode::D)),
+            turn: -axis(inputs.key(Keycode::Left), inputs.key(Keycode::Right)),
+            use_pressed,
+            attack_pressed,
+            weapon,
+        }
+    }
+}
+
 //------------------
 //  Internal stuff
 
+/// 1.0 / -1.0 / 0.0, depending on which key is pressed (the first one wins).
+#[inline]
+fn axis(positive: bool, negative: bool) -> f64 {
+    if positive {
+        1.0
+    } else if negative {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
 #[inline(always)]
 fn key2code(key: Keycode) -> i32 {
     key as i32
@@ -0,0 +1,167 @@
+//! Level colors - the ceiling and floor color of each map (+ the fog, for the lighting mode).
+//! The original has no textured ceilings and floors, just a color for each (see `vgaCeiling` in WL_DRAW.C).
+
+use crate::Fog;
+
+/// The ceiling and floor colors of a map (palette indexes) + its fog (used only with lighting).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelColors {
+    pub ceiling: u8,
+    pub floor: u8,
+    pub fog: Fog,
+}
+
+impl Default for LevelColors {
+    fn default() -> Self {
+        Self {
+            ceiling: DEFAULT_CEILING_COLOR,
+            floor: DEFAULT_FLOOR_COLOR,
+            fog: Fog::default(),
+        }
+    }
+}
+
+/// The colors of all the maps, by map index (like in `GameAssets::maps`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelColorTable {
+    colors: Vec<LevelColors>,
+}
+
+impl LevelColorTable {
+    /// The built-in table for a game type (by file extension) - the ceilings from the original, the floors are
+    /// all the same. Unknown extensions get the Spear of Destiny table.
+    pub fn builtin(game_ext: &str) -> Self {
+        let ceilings = match game_ext {
+            "WL1" => &WL6_CEILINGS[..10],
+            "WL3" => &WL6_CEILINGS[..30],
+            "WL6" => WL6_CEILINGS,
+            _ => SOD_CEILINGS,
+        };
+        let colors = ceilings
+            .iter()
+            .map(|&ceiling| LevelColors {
+                ceiling,
+                ..Default::default()
+            })
+            .collect();
+        Self { colors }
+    }
+
+    /// Override the colors of some maps, from text. Each line sets the ceiling color (and optionally the floor
+    /// color) of a map, or of a range of maps. Colors can be decimal or hex (with `0x`).
+    /// Maps followed by `.fog` get the fog color and its falloff distance (in map cells):
+    /// ```text
+    /// # comments start with '#'
+    /// 0 = 0x1D
+    /// 1..5 = 0x4E, 0x19
+    /// 1..5.fog = 0x00, 12.5
+    /// ```
+    /// Maps past the end of the table are added (the ones in between get the default colors).
+    /// Errors contain the line number (starting from 1).
+    pub fn apply(&mut self, text: &str) -> Result<(), (usize, String)> {
+        for (line_idx, line) in text.lines().enumerate() {
+            let line_num = line_idx + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((maps, colors)) = line.split_once('=') else {
+                return Err((line_num, format!("Expected `map = ceiling[, floor]`, found: {line}")));
+            };
+            let (maps, is_fog) = match maps.trim().strip_suffix(".fog") {
+                Some(maps) => (maps, true),
+                None => (maps, false),
+            };
+            let parse_map = |s: &str| {
+                s.trim()
+                    .parse::<usize>()
+                    .map_err(|_| (line_num, format!("Invalid map index: {}", s.trim())))
+            };
+            let (first, last) = maps.split_once("..").unwrap_or((maps, maps));
+            let (first, last) = (parse_map(first)?, parse_map(last)?);
+            if first > last || last >= MAX_MAPS {
+                return Err((line_num, format!("Invalid map range: {}", maps.trim())));
+            }
+            let (color, second) = match colors.split_once(',') {
+                Some((color, second)) => (color, Some(second.trim())),
+                None => (colors, None),
+            };
+            let color = parse_color(color).ok_or((line_num, format!("Invalid color: {}", color.trim())))?;
+            let invalid = |what: &str| (line_num, format!("Invalid {what}: {}", second.unwrap_or("")));
+
+            let fog = if is_fog {
+                let falloff = second.and_then(|s| s.parse::<f64>().ok()).filter(|f| *f > 0.0);
+                let falloff = falloff.ok_or_else(|| invalid("fog falloff"))?;
+                Some(Fog { color, falloff })
+            } else {
+                None
+            };
+            let floor = match second {
+                Some(floor) if !is_fog => Some(parse_color(floor).ok_or_else(|| invalid("color"))?),
+                _ => None,
+            };
+
+            if self.colors.len() <= last {
+                self.colors.resize(last + 1, LevelColors::default());
+            }
+            for colors in self.colors[first..=last].iter_mut() {
+                match fog {
+                    Some(fog) => colors.fog = fog,
+                    None => {
+                        colors.ceiling = color;
+                        colors.floor = floor.unwrap_or(colors.floor);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The colors of a map. The maps which are not in the table get the default colors.
+    #[inline]
+    pub fn colors(&self, map_idx: usize) -> LevelColors {
+        self.colors.get(map_idx).copied().unwrap_or_default()
+    }
+
+    /// How many maps are in the table.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+const DEFAULT_CEILING_COLOR: u8 = 0x1D;
+const DEFAULT_FLOOR_COLOR: u8 = 0x19;
+/// MAPHEAD has room for 100 maps
+const MAX_MAPS: usize = 100;
+
+/// The ceiling colors of Wolf3D (all 6 episodes), from the original `vgaCeiling` table.
+const WL6_CEILINGS: &[u8] = &[
+    0x1D, 0x1D, 0x1D, 0x1D, 0x1D, 0x1D, 0x1D, 0x1D, 0x1D, 0xBF, 0x4E, 0x4E, 0x4E, 0x1D, 0x8D, 0x4E, 0x1D, 0x2D, 0x1D,
+    0x8D, 0x1D, 0x1D, 0x1D, 0x1D, 0x1D, 0x2D, 0xDD, 0x1D, 0x1D, 0x98, 0x1D, 0x9D, 0x2D, 0xDD, 0xDD, 0x9D, 0x2D, 0x4D,
+    0x1D, 0xDD, 0x7D, 0x1D, 0x2D, 0x2D, 0xDD, 0xD7, 0x1D, 0x1D, 0x1D, 0x2D, 0x1D, 0x1D, 0x1D, 0x1D, 0xDD, 0xDD, 0x7D,
+    0xDD, 0xDD, 0xDD,
+];
+
+/// The ceiling colors of Spear of Destiny (21 maps), from the original `vgaCeiling` table.
+const SOD_CEILINGS: &[u8] = &[
+    0x6F, 0x4F, 0x1D, 0xDE, 0xDF, 0x2E, 0x7F, 0x9E, 0xAE, 0x7F, 0x1D, 0xDE, 0xDF, 0xDE, 0xDF, 0xDE, 0xE1, 0xDC, 0x2E,
+    0x1D, 0xDC,
+];
+
+fn parse_color(s: &str) -> Option<u8> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
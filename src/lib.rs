@@ -1,33 +1,71 @@
 //! ROLF3D - a Rust implementation of the WOLF3D raycasting engine :)
 //! Main library.
 
+mod article;
+mod articleviewer;
+mod asseterror;
 mod assetloader;
 mod assets;
+mod assetsource;
+mod assetwriter;
+mod audio;
 mod automap;
+mod codecs;
+mod colormap;
+mod demo;
+mod exporter;
+mod fizzle;
 mod gameloop;
+mod gameversion;
+mod importer;
 mod input;
+mod levelcolors;
 mod livemap;
 mod mapcell;
 mod notifier;
+mod opl2;
+mod paleffects;
+mod palette;
+mod pcspeaker;
 mod picdict;
 mod raycaster;
 mod scrbuf;
 mod sdl_wrapper;
+mod sounds;
 mod status;
 mod utils;
 
+pub use article::*;
+pub use articleviewer::*;
+pub use asseterror::*;
 pub use assetloader::*;
 pub use assets::*;
+pub use assetsource::*;
+pub use assetwriter::*;
+pub use audio::*;
 pub use automap::*;
+pub use codecs::*;
+pub use colormap::*;
+pub use demo::*;
+pub use exporter::*;
+pub use fizzle::*;
 pub use gameloop::*;
+pub use gameversion::*;
+pub use importer::*;
 pub use input::*;
+pub use levelcolors::*;
 pub use livemap::*;
 pub use mapcell::*;
 pub use notifier::*;
+pub use opl2::*;
+pub use paleffects::*;
+pub use palette::*;
+pub use pcspeaker::*;
 pub use picdict::*;
 pub use raycaster::*;
 pub use scrbuf::*;
 pub use sdl_wrapper::*;
+pub use sounds::*;
 pub use status::*;
 pub use utils::*;
 
@@ -40,4 +78,10 @@ pub enum GameMode {
     // TODO PauseMenu,
     Live,
     Automap,
+    Article,
+    Demo,
+    /// The player died: the view fizzles to red, then the floor restarts
+    Death,
+    /// A boss was killed: the view fizzles into the death cam
+    DeathCam,
 }
@@ -4,8 +4,6 @@ use crate::{raycaster::RayCaster, *};
 use sdl2::keyboard::Keycode;
 use std::{f64::consts::PI, rc::Rc};
 
-const MOVE_SPEED: f64 = 4.5;
-const ROTATE_SPEED: f64 = 2.0;
 // Minimum distance between the player and a wall
 // (or, it can be considered the "diameter" of the player object in the world)
 const MIN_DISTANCE_TO_WALL: f64 = 0.375;
@@ -28,31 +26,41 @@ pub struct LiveMap {
     actors: Vec<Actor>,
     width: u16,
     height: u16,
+    colors: LevelColors,
     status: GameStatus,
     clipping_enabled: bool,
     secret_floor_return: u8,
     player_map_x: i32,
     player_map_y: i32,
     notifier: Notifier,
+    /// A boss was killed, since the last `take_boss_kill`
+    boss_killed: bool,
 }
 
 impl LiveMap {
     pub fn new(assets: Rc<GameAssets>, episode: u8) -> Self {
+        Self::new_on_floor(assets, episode, 0)
+    }
+
+    /// Start on a given floor of the episode (e.g. for the demos).
+    pub fn new_on_floor(assets: Rc<GameAssets>, episode: u8, floor: u8) -> Self {
         let mut livemap = Self {
             description: String::new(),
             episode,
-            floor: 0,
+            floor,
             assets,
             cells: vec![],
             actors: vec![],
             width: 0,
             height: 0,
+            colors: LevelColors::default(),
             status: GameStatus::new(0),
             clipping_enabled: true,
             secret_floor_return: 0,
             player_map_x: -1,
             player_map_y: -1,
             notifier: Notifier::new(),
+            boss_killed: false,
         };
         livemap.floor_has_changed();
         livemap
@@ -74,6 +82,11 @@ impl LiveMap {
         self.floor_has_changed();
     }
 
+    #[inline]
+    pub fn episode(&self) -> u8 {
+        self.episode
+    }
+
     #[inline]
     pub fn width(&self) -> u16 {
         self.width
@@ -84,6 +97,12 @@ impl LiveMap {
         self.height
     }
 
+    /// The ceiling and floor colors of the current floor.
+    #[inline]
+    pub fn level_colors(&self) -> LevelColors {
+        self.colors
+    }
+
     #[inline]
     pub fn cell(&self, x: i32, y: i32) -> Option<&MapCell> {
         if let Some(idx) = self.cell_index(x, y) {
@@ -93,52 +112,107 @@ impl LiveMap {
         }
     }
 
+    /// Take the sounds triggered since the last call.
+    pub fn take_sounds(&mut self) -> Vec<SoundEffect> {
+        self.notifier.take_sounds()
+    }
+
+    /// Take the palette flashes (damage, pickups) triggered since the last call.
+    pub fn take_palette_flashes(&mut self) -> Vec<PaletteFlash> {
+        self.notifier.take_flashes()
+    }
+
+    /// A boss was killed => the game shows the death cam.
+    // TODO call it when a boss dies, once the actors can be killed (now it is only a temporary key)
+    pub fn kill_boss(&mut self) {
+        self.boss_killed = true;
+    }
+
+    /// True if a boss was killed since the last call.
+    pub fn take_boss_kill(&mut self) -> bool {
+        std::mem::take(&mut self.boss_killed)
+    }
+
+    #[inline]
+    pub fn is_player_dead(&self) -> bool {
+        self.status.is_dead()
+    }
+
+    /// After the player died: lose a life and restart the floor.
+    /// Returns false if there were no lives left (= game over).
+    pub fn restart_after_death(&mut self) -> bool {
+        // TODO also lose the weapons and the ammo, like in the original
+        let has_lives = self.status.try_decrement_lives();
+        if has_lives {
+            self.floor_has_changed();
+        }
+        has_lives
+    }
+
+    /// The player position (in map cells) and angle (in radians).
+    #[inline]
+    pub fn player_pos(&self) -> (f64, f64, f64) {
+        let player = &self.actors[0];
+        (player.x, player.y, player.angle)
+    }
+
     // TODO the return of next game state is kinda hacky => FIX IT !!
     pub fn handle_inputs(&mut self, inputs: &mut InputManager, elapsed_time: f64) {
+        let controls = PlayerControls::from_inputs(inputs);
+        self.update(&controls, elapsed_time);
+
+        // TODO: temporary keys
+        if inputs.consume_key(Keycode::F1) {
+            self.clipping_enabled = !self.clipping_enabled;
+        }
+        if inputs.consume_key(Keycode::F2) {
+            self.status._tmp_give_stuff();
+        }
+        if inputs.consume_key(Keycode::F3) {
+            self.status.damage_health(10);
+            self.notifier.notify_damage(10);
+        }
+        if inputs.consume_key(Keycode::F4) {
+            self.kill_boss();
+        }
+    }
+
+    /// Update the game world: the player acts on the controls (from the keyboard, or from a demo),
+    /// then the doors, push walls etc are moved.
+    pub fn update(&mut self, controls: &PlayerControls, elapsed_time: f64) {
         // TODO: update doors, secret walls, actors - only if NOT paused
         self.notifier.update_time(elapsed_time);
 
         // weapons
-        if inputs.consume_key(Keycode::Num1) {
-            self.status.try_select_weapon(0);
-        } else if inputs.consume_key(Keycode::Num2) {
-            self.status.try_select_weapon(1);
-        } else if inputs.consume_key(Keycode::Num3) {
-            self.status.try_select_weapon(2);
-        } else if inputs.consume_key(Keycode::Num4) {
-            self.status.try_select_weapon(3);
+        if let Some(weapon) = controls.weapon {
+            self.status.try_select_weapon(weapon as i32);
         }
 
-        if inputs.consume_key(Keycode::E) || inputs.consume_key(Keycode::Space) {
+        if controls.use_pressed {
             self.perform_use();
         }
 
         // TODO temporary - "fake" shooting
-        // (later, do NOT consume key - machine gun and chain gun are automatic)
-        if inputs.consume_key(Keycode::LCtrl) || inputs.consume_key(Keycode::RCtrl) {
-            if self.status.get_selected_weapon() != 0 {
-                self.status.consume_ammo();
-            }
+        if controls.attack_pressed && self.status.get_selected_weapon() != 0 {
+            self.status.consume_ammo();
         }
 
         // update player
         let player_angle = self.actors[0].angle;
-        if inputs.key(Keycode::W) || inputs.key(Keycode::Up) {
-            self.translate_actor(0, elapsed_time, player_angle);
-        } else if inputs.key(Keycode::S) || inputs.key(Keycode::Down) {
-            self.translate_actor(0, -elapsed_time, player_angle);
+        if controls.forward != 0.0 {
+            // like in the original, moving backwards is slower
+            let back_scale = if controls.forward < 0.0 {
+                BACK_MOVE_SCALE / MOVE_SCALE
+            } else {
+                1.0
+            };
+            self.translate_actor(0, elapsed_time * controls.forward * back_scale, player_angle);
         }
-
-        if inputs.key(Keycode::A) {
-            self.translate_actor(0, elapsed_time, player_angle - HALF_PI);
-        } else if inputs.key(Keycode::D) {
-            self.translate_actor(0, elapsed_time, player_angle + HALF_PI);
+        if controls.strafe != 0.0 {
+            self.translate_actor(0, elapsed_time * controls.strafe, player_angle + HALF_PI);
         }
-
-        if inputs.key(Keycode::Left) {
-            self.rotate_actor(0, -elapsed_time);
-        } else if inputs.key(Keycode::Right) {
-            self.rotate_actor(0, elapsed_time);
+        if controls.turn != 0.0 {
+            self.rotate_actor(0, elapsed_time * controls.turn);
         }
 
         // update doors and push walls
@@ -151,17 +225,6 @@ impl LiveMap {
         self.update_player();
 
         // TODO update actors ...
-
-        // TODO: temporary keys
-        if inputs.consume_key(Keycode::F1) {
-            self.clipping_enabled = !self.clipping_enabled;
-        }
-        if inputs.consume_key(Keycode::F2) {
-            self.status._tmp_give_stuff();
-        }
-        if inputs.consume_key(Keycode::F3) {
-            self.status.damage_health(10);
-        }
     }
 
     #[inline]
@@ -175,10 +238,8 @@ impl LiveMap {
     }
 
     pub fn paint_3d(&self, scrbuf: &mut ScreenBuffer) {
-        // TODO (later) use correct sky color per game and level
-        // (0x1D, 0xBF, 0x4E and 0x8D)
-        const SKY_COLOR: u8 = 0x1D;
-        scrbuf.clear_3d_view(SKY_COLOR);
+        scrbuf.set_fog(self.colors.fog);
+        scrbuf.clear_3d_view(self.colors.ceiling, self.colors.floor);
 
         // cast rays to draw the walls
         let width = scrbuf.scr_width();
@@ -243,6 +304,7 @@ impl LiveMap {
         // load map
         self.width = mapsrc.width;
         self.height = mapsrc.height;
+        self.colors = self.assets.level_colors.colors(idx);
         (self.cells, self.actors) = mapcell::load_map_to_cells(mapsrc, self.assets.is_sod);
         // update status
         self.status.set_floor(self.floor as i32, (self.actors.len() - 1) as i32);
@@ -327,7 +389,7 @@ impl LiveMap {
 
     #[inline]
     fn translate_actor(&mut self, actor_idx: usize, ellapsed_time: f64, angle: f64) {
-        let distance = ellapsed_time * MOVE_SPEED;
+        let distance = ellapsed_time * PLAYER_MOVE_SPEED;
 
         // transform the polar (distance, angle) into movements along the 2 axis
         let (s, c) = angle.sin_cos();
@@ -382,7 +444,7 @@ impl LiveMap {
     #[inline]
     fn rotate_actor(&mut self, actor_idx: usize, ellapsed_time: f64) {
         let actor = self.actors.get_mut(actor_idx).unwrap();
-        actor.angle += ellapsed_time * ROTATE_SPEED;
+        actor.angle += ellapsed_time * PLAYER_TURN_SPEED;
         if actor.angle >= PI2 {
             actor.angle -= PI2;
         } else if actor.angle < 0.0 {
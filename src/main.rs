@@ -13,7 +13,23 @@ const SLEEP_KIND: SleepKind = SleepKind::SLEEP(1);
 
 fn main() {
     // load and prepare game assets
-    let assets = GameAssets::load().expect("ERROR in ROLF3D: failed to load game assets");
+    // (optional command line arguments: the directory of the asset files, then the mods to load over them,
+    //  as directories or ZIP/PK3 archives - e.g. `rolf3d data levelpack.pk3`)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let assets = match args.split_first() {
+        Some((dir, mods)) => {
+            let mods: Vec<&str> = mods.iter().map(|m| m.as_str()).collect();
+            GameAssets::load_with_mods(dir, &mods)
+        }
+        None => GameAssets::load(),
+    };
+    let assets = match assets {
+        Ok(assets) => assets,
+        Err(err) => {
+            println!("ERROR in ROLF3D: failed to load game assets: {err}");
+            std::process::exit(1);
+        }
+    };
 
     // main game loop
     let sdl_config = SdlConfiguration::new("ROLF3D", SCR_WIDTH, SCR_HEIGHT, PIXEL_SIZE, SLEEP_KIND);
@@ -131,6 +131,7 @@ pub enum CellState {
 pub struct MapCell {
     pub tile: u16,  // TODO temp pub ...
     pub thing: u16, // TODO temp pub ...
+    pub info: u16,  // plane #3 - extra info from extended map editors (unused by the original games)
     tex_sprt: u16,  // texture or sprite index
     flags: u8,      // state + various flags
     coll: Collectible,
@@ -376,6 +377,7 @@ pub fn load_map_to_cells(mapsrc: &MapData, is_sod: bool) -> (Vec<MapCell>, Vec<A
             let c = MapCell {
                 tile: mapsrc.tile(x, y),
                 thing: mapsrc.thing(x, y),
+                info: mapsrc.info(x, y),
                 tex_sprt: NO_TEXTURE,
                 flags: 0,
                 coll: Collectible::None,
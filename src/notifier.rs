@@ -2,7 +2,7 @@
 
 use std::collections::VecDeque;
 
-use crate::{Collectible, GameAssets, ScreenBuffer};
+use crate::{Collectible, GameAssets, PaletteFlash, ScreenBuffer, SoundEffect};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum Notification {
@@ -16,33 +16,67 @@ pub enum Notification {
     // TODO add notifications for ALL possible sounds (+ temporary messages)
 }
 
-pub struct Notifier(VecDeque<TimedMessage>);
+pub struct Notifier {
+    messages: VecDeque<TimedMessage>,
+    sounds: Vec<SoundEffect>,
+    flashes: Vec<PaletteFlash>,
+}
 
 impl Notifier {
     pub fn new() -> Self {
-        Self(VecDeque::with_capacity(MAX_MESSAGES))
+        Self {
+            messages: VecDeque::with_capacity(MAX_MESSAGES),
+            sounds: vec![],
+            flashes: vec![],
+        }
     }
 
     pub fn notify(&mut self, note: Notification) {
-        // TODO sounds !!
         let msg = from_notification(note).to_string();
         self.add_message(msg);
+        if let Some(snd) = sound_for_notification(note) {
+            self.sounds.push(snd);
+        }
     }
 
     pub fn notify_collectible(&mut self, coll: Collectible) {
-        // TODO sounds !!
         let msg = from_collectible(coll).to_string();
         self.add_message(msg);
+        if let Some(snd) = sound_for_collectible(coll) {
+            self.sounds.push(snd);
+        }
+        // like in the original, every pickup flashes the screen
+        self.flashes.push(PaletteFlash::Bonus);
+    }
+
+    /// The player was hurt => the screen flashes red.
+    pub fn notify_damage(&mut self, damage: i32) {
+        self.flashes.push(PaletteFlash::Damage(damage));
+    }
+
+    /// Play a sound, without any message.
+    pub fn play_sound(&mut self, snd: SoundEffect) {
+        self.sounds.push(snd);
+    }
+
+    /// Take the sounds which should be played, since the last call.
+    pub fn take_sounds(&mut self) -> Vec<SoundEffect> {
+        std::mem::take(&mut self.sounds)
+    }
+
+    /// Take the palette flashes which should be shown, since the last call.
+    pub fn take_flashes(&mut self) -> Vec<PaletteFlash> {
+        std::mem::take(&mut self.flashes)
     }
 
     pub fn update_time(&mut self, elapsed: f64) {
-        self.0.iter_mut().for_each(|notif| {
+        self.messages.iter_mut().for_each(|notif| {
             notif.timeout -= elapsed;
         });
         // remove old, finished notifications
-        while let Some(notif) = self.0.back() {
+        while let Some(notif) = self.messages.back() {
             if notif.timeout <= 0.0 {
-                self.0.pop_back();
+                self.messages.pop_back();
             } else {
                 break;
             }
@@ -51,7 +85,7 @@ impl Notifier {
 
     pub fn paint(&self, scrbuf: &mut ScreenBuffer, assets: &GameAssets) {
         let mut y = 5;
-        for tm in self.0.iter() {
+        for tm in self.messages.iter() {
             assets.font1.draw_text(6, y + 1, &tm.msg, 0, scrbuf);
             assets.font1.draw_text(5, y, &tm.msg, 15, scrbuf);
             y += 15;
@@ -65,15 +99,15 @@ impl Notifier {
             return;
         }
         // remove old notifications
-        while self.0.len() >= MAX_MESSAGES {
-            self.0.pop_back();
+        while self.messages.len() >= MAX_MESSAGES {
+            self.messages.pop_back();
         }
         // add new notification
         let tm = TimedMessage {
             msg,
             timeout: MSG_TIMEOUT,
         };
-        self.0.push_front(tm);
+        self.messages.push_front(tm);
     }
 }
 
@@ -101,6 +135,37 @@ fn from_notification(note: Notification) -> &'static str {
     }
 }
 
+// -> see OperateDoor and PushWall in WL_ACT1.C
+fn sound_for_notification(note: Notification) -> Option<SoundEffect> {
+    match note {
+        Notification::LockNeedsGoldKey | Notification::LockNeedsSilverKey | Notification::LockNoKeyAvailable => {
+            Some(SoundEffect::NoWay)
+        }
+        Notification::FoundSecret => Some(SoundEffect::PushWall),
+        _ => None,
+    }
+}
+
+// -> see GetBonus in WL_AGENT.C
+fn sound_for_collectible(coll: Collectible) -> Option<SoundEffect> {
+    match coll {
+        Collectible::DogFood | Collectible::GoodFood => Some(SoundEffect::Health1),
+        Collectible::FirstAid => Some(SoundEffect::Health2),
+        Collectible::Gibs1 | Collectible::Gibs2 => Some(SoundEffect::Slurpie),
+        Collectible::AmmoClipSmall | Collectible::AmmoClipNormal | Collectible::AmmoBox => Some(SoundEffect::GetAmmo),
+        Collectible::MachineGun => Some(SoundEffect::GetMachineGun),
+        Collectible::ChainGun => Some(SoundEffect::GetGatling),
+        Collectible::GoldKey | Collectible::SilverKey => Some(SoundEffect::GetKey),
+        Collectible::TreasureCross => Some(SoundEffect::Bonus1),
+        Collectible::TreasureCup => Some(SoundEffect::Bonus2),
+        Collectible::TreasureChest => Some(SoundEffect::Bonus3),
+        Collectible::TreasureCrown => Some(SoundEffect::Bonus4),
+        Collectible::TreasureOneUp => Some(SoundEffect::Bonus1Up),
+        // TODO SOD sounds (GETAMMOBOXSND, GETSPEARSND)
+        _ => None,
+    }
+}
+
 fn from_collectible(coll: Collectible) -> &'static str {
     match coll {
         Collectible::DogFood => "You ate some dog food :(",
@@ -0,0 +1,569 @@
+//! OPL2 (YM3812) emulator - renders AdLib sound effects and IMF music to PCM.
+//!
+//! This is a register-level emulator, working with floats - it is NOT cycle exact,
+//! but it is close enough to recognize the original sounds and music :)
+//! Rhythm mode and CSM mode are not emulated (WOLF3D does not use them).
+//!
+//! ## Some useful links:
+//! * [OPL chip @ ModdingWiki](https://moddingwiki.shikadi.net/wiki/OPL_chip)
+//! * [IMF Format @ ModdingWiki](https://moddingwiki.shikadi.net/wiki/IMF_Format)
+//! * [WOLF3D sound manager](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.C)
+
+use crate::{AdLibSound, ImfMusic, IMF_TICK_RATE, SOUND_TICK_RATE};
+use std::f64::consts::PI;
+
+/// The OPL2 is an FM synthesizer with 9 channels, each one having 2 operators (modulator + carrier).
+pub struct Opl2 {
+    sample_rate: u32,
+    wave_select_enabled: bool,
+    deep_tremolo: bool,
+    deep_vibrato: bool,
+    lfo_time: f64,
+    channels: [Channel; NUM_CHANNELS],
+    operators: [Operator; NUM_OPERATORS],
+}
+
+impl Opl2 {
+    pub fn new(sample_rate: u32) -> Self {
+        assert!(sample_rate > 0);
+        Self {
+            sample_rate,
+            wave_select_enabled: false,
+            deep_tremolo: false,
+            deep_vibrato: false,
+            lfo_time: 0.0,
+            channels: [Channel::default(); NUM_CHANNELS],
+            operators: [Operator::default(); NUM_OPERATORS],
+        }
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Write a value into one of the OPL2 registers.
+    pub fn write_reg(&mut self, reg: u8, value: u8) {
+        match reg {
+            0x01 => self.wave_select_enabled = (value & 0x20) != 0,
+            0x20..=0x35 => self.with_operator(reg - 0x20, |op| {
+                op.tremolo = (value & 0x80) != 0;
+                op.vibrato = (value & 0x40) != 0;
+                op.sustained = (value & 0x20) != 0;
+                op.ksr = (value & 0x10) != 0;
+                op.mult = value & 0x0F;
+            }),
+            0x40..=0x55 => self.with_operator(reg - 0x40, |op| {
+                op.ksl = value >> 6;
+                op.total_level = value & 0x3F;
+            }),
+            0x60..=0x75 => self.with_operator(reg - 0x60, |op| {
+                op.attack_rate = value >> 4;
+                op.decay_rate = value & 0x0F;
+            }),
+            0x80..=0x95 => self.with_operator(reg - 0x80, |op| {
+                op.sustain_level = value >> 4;
+                op.release_rate = value & 0x0F;
+            }),
+            0xE0..=0xF5 => self.with_operator(reg - 0xE0, |op| op.wave = value & 0x03),
+            0xA0..=0xA8 => {
+                let ch = &mut self.channels[(reg - 0xA0) as usize];
+                ch.fnum = (ch.fnum & 0x300) | (value as u16);
+            }
+            0xB0..=0xB8 => {
+                let chidx = (reg - 0xB0) as usize;
+                let ch = &mut self.channels[chidx];
+                ch.fnum = (ch.fnum & 0xFF) | (((value & 0x03) as u16) << 8);
+                ch.block = (value >> 2) & 0x07;
+                let key_on = (value & 0x20) != 0;
+                if key_on != ch.key_on {
+                    ch.key_on = key_on;
+                    let (m, c) = channel_operators(chidx);
+                    self.operators[m].key(key_on);
+                    self.operators[c].key(key_on);
+                }
+            }
+            0xBD => {
+                self.deep_tremolo = (value & 0x80) != 0;
+                self.deep_vibrato = (value & 0x40) != 0;
+                // TODO rhythm mode (bit 5) + percussion keys (bits 0..4) - not used by WOLF3D
+            }
+            0xC0..=0xC8 => {
+                let ch = &mut self.channels[(reg - 0xC0) as usize];
+                ch.feedback = (value >> 1) & 0x07;
+                ch.additive = (value & 0x01) != 0;
+            }
+            _ => {} // test register, timers, CSM etc => ignored
+        }
+    }
+
+    /// Generate mono, signed 16-bit samples into the output buffer.
+    pub fn generate(&mut self, out: &mut [i16]) {
+        let dt = 1.0 / (self.sample_rate as f64);
+        for sample in out.iter_mut() {
+            // LFOs - shared by all operators
+            let tremolo_depth = if self.deep_tremolo { 4.8 } else { 1.0 };
+            let tremolo_db = tremolo_depth * 0.5 * (1.0 - (2.0 * PI * TREMOLO_FREQ * self.lfo_time).cos());
+            let vibrato_cents = if self.deep_vibrato { 14.0 } else { 7.0 };
+            let vibrato_mul = (vibrato_cents / 1200.0 * (2.0 * PI * VIBRATO_FREQ * self.lfo_time).sin()).exp2();
+            self.lfo_time += dt;
+
+            let mut mix = 0.0;
+            for chidx in 0..NUM_CHANNELS {
+                let ch = self.channels[chidx];
+                let (m, c) = channel_operators(chidx);
+                if self.operators[m].stage == EnvStage::Off && self.operators[c].stage == EnvStage::Off {
+                    continue;
+                }
+                let lfo = (tremolo_db, vibrato_mul);
+                let wse = self.wave_select_enabled;
+                // modulator, with self-feedback
+                let fb_phase = if ch.feedback > 0 {
+                    let op = &self.operators[m];
+                    (op.prev_out[0] + op.prev_out[1]) * 0.5 * ((1 << ch.feedback) as f64) / 64.0
+                } else {
+                    0.0
+                };
+                let mod_out = self.operators[m].output(&ch, fb_phase, lfo, wse, dt);
+                // carrier - modulated by the modulator, or added to it
+                if ch.additive {
+                    let car_out = self.operators[c].output(&ch, 0.0, lfo, wse, dt);
+                    mix += mod_out + car_out;
+                } else {
+                    let car_out = self.operators[c].output(&ch, mod_out * MOD_DEPTH, lfo, wse, dt);
+                    mix += car_out;
+                }
+            }
+
+            // each channel can reach ~1/8 of the full 16-bit scale (like on the real chip)
+            *sample = (mix * 4095.0).round().clamp(-32768.0, 32767.0) as i16;
+        }
+    }
+
+    /// Generate the samples for a number of ticks, at the given tick rate.
+    /// The fractional samples are kept in `frac`, so that consecutive calls stay in sync.
+    fn generate_ticks(&mut self, ticks: u32, tick_rate: u32, frac: &mut u64, out: &mut Vec<i16>) {
+        *frac += (ticks as u64) * (self.sample_rate as u64);
+        let cnt = (*frac / (tick_rate as u64)) as usize;
+        *frac %= tick_rate as u64;
+        let start = out.len();
+        out.resize(start + cnt, 0);
+        self.generate(&mut out[start..]);
+    }
+
+    fn with_operator(&mut self, offset: u8, func: impl FnOnce(&mut Operator)) {
+        if let Some(opidx) = OPERATOR_FOR_OFFSET[offset as usize] {
+            func(&mut self.operators[opidx]);
+        }
+    }
+}
+
+/// Render an AdLib sound effect, the way WOLF3D plays it: on channel 0, one note per tick, at 140 Hz.
+/// A short tail is added at the end, so that the release of the last note is not cut.
+/// -> see SDL_ALPlaySound and SDL_ALSoundService in ID_SD.C
+pub fn render_adlib_sound(sound: &AdLibSound, sample_rate: u32) -> Vec<i16> {
+    let mut opl = Opl2::new(sample_rate);
+    let mut out = Vec::with_capacity((sound.len() + RELEASE_TAIL_TICKS) * (sample_rate / SOUND_TICK_RATE) as usize);
+    let mut frac = 0;
+
+    // enable wave select + set the instrument (SDL_AlSetFXInst)
+    opl.write_reg(0x01, 0x20);
+    let inst = &sound.instrument;
+    let (m, c) = (0x00, 0x03);
+    opl.write_reg(0x20 + m, inst.m_char);
+    opl.write_reg(0x40 + m, inst.m_scale);
+    opl.write_reg(0x60 + m, inst.m_attack);
+    opl.write_reg(0x80 + m, inst.m_sus);
+    opl.write_reg(0xE0 + m, inst.m_wave);
+    opl.write_reg(0x20 + c, inst.c_char);
+    opl.write_reg(0x40 + c, inst.c_scale);
+    opl.write_reg(0x60 + c, inst.c_attack);
+    opl.write_reg(0x80 + c, inst.c_sus);
+    opl.write_reg(0xE0 + c, inst.c_wave);
+    // the original code also ignores `n_conn` here, and always uses FM with no feedback
+    opl.write_reg(0xC0, 0);
+
+    // play the notes
+    let block = ((sound.block & 7) << 2) | 0x20;
+    for &note in sound.notes.iter() {
+        if note == 0 {
+            opl.write_reg(0xB0, 0);
+        } else {
+            opl.write_reg(0xA0, note);
+            opl.write_reg(0xB0, block);
+        }
+        opl.generate_ticks(1, SOUND_TICK_RATE, &mut frac, &mut out);
+    }
+
+    // key off + release tail
+    opl.write_reg(0xB0, 0);
+    opl.generate_ticks(RELEASE_TAIL_TICKS as u32, SOUND_TICK_RATE, &mut frac, &mut out);
+    out
+}
+
+/// Render IMF music: each command writes an OPL2 register, then waits for some ticks, at 700 Hz.
+pub fn render_imf_music(music: &ImfMusic, sample_rate: u32) -> Vec<i16> {
+    let total_ticks = music.duration_ticks() as u64;
+    let total = total_ticks * (sample_rate as u64) / (IMF_TICK_RATE as u64);
+    let mut out = vec![0; total as usize];
+    ImfState::new(sample_rate).generate(music, false, &mut out);
+    out
+}
+
+/// Streaming IMF music player - generates the music as it is needed, instead of rendering all of it upfront.
+pub struct ImfPlayer {
+    state: ImfState,
+    music: ImfMusic,
+    looping: bool,
+}
+
+impl ImfPlayer {
+    pub fn new(music: ImfMusic, sample_rate: u32, looping: bool) -> Self {
+        Self {
+            state: ImfState::new(sample_rate),
+            music,
+            looping,
+        }
+    }
+
+    /// Check if the music has finished (looping music never finishes).
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.state.is_finished(&self.music)
+    }
+
+    /// Generate the next samples of the music. After the music finishes, the output is silence.
+    #[inline]
+    pub fn generate(&mut self, out: &mut [i16]) {
+        self.state.generate(&self.music, self.looping, out);
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+const NUM_CHANNELS: usize = 9;
+const NUM_OPERATORS: usize = 18;
+
+/// The OPL2 runs at 3.579545 MHz / 72
+const NATIVE_RATE: f64 = 49716.0;
+
+/// How much the modulator can shift the phase of the carrier, in periods.
+const MOD_DEPTH: f64 = 4.0;
+
+const TREMOLO_FREQ: f64 = 3.7;
+const VIBRATO_FREQ: f64 = 6.1;
+
+/// Times for a full attack (0 to 96 dB) and a full decay (96 dB to 0), at the slowest rate (4), in seconds.
+const ATTACK_TIME: f64 = 2.82624;
+const DECAY_TIME: f64 = 39.28064;
+
+/// Silence, in dB.
+const MAX_ATTENUATION: f64 = 96.0;
+
+/// Ticks to render after an AdLib sound effect ends (~0.1 seconds).
+const RELEASE_TAIL_TICKS: usize = 14;
+
+/// Frequency multipliers, for each MULT value.
+const MULTIPLIERS: [f64; 16] = [
+    0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 10.0, 12.0, 12.0, 15.0, 15.0,
+];
+
+/// Key scale levels (in dB, for block 7), for the upper 4 bits of FNUM.
+const KSL_TABLE: [f64; 16] = [
+    0.0, 9.0, 12.0, 13.875, 15.0, 16.125, 16.875, 17.625, 18.0, 18.75, 19.125, 19.5, 19.875, 20.25, 20.625, 21.0,
+];
+
+/// Key scale level factors - 0, 3, 1.5 or 6 dB/octave.
+const KSL_FACTORS: [f64; 4] = [0.0, 0.5, 0.25, 1.0];
+
+/// Operator index, for each of the register offsets (the registers are not contiguous).
+const OPERATOR_FOR_OFFSET: [Option<usize>; 0x16] = [
+    Some(0),
+    Some(1),
+    Some(2),
+    Some(3),
+    Some(4),
+    Some(5),
+    None,
+    None,
+    Some(6),
+    Some(7),
+    Some(8),
+    Some(9),
+    Some(10),
+    Some(11),
+    None,
+    None,
+    Some(12),
+    Some(13),
+    Some(14),
+    Some(15),
+    Some(16),
+    Some(17),
+];
+
+/// The indexes of the modulator and carrier operators, for a channel.
+#[inline]
+fn channel_operators(chidx: usize) -> (usize, usize) {
+    let m = (chidx / 3) * 6 + (chidx % 3);
+    (m, m + 3)
+}
+
+#[derive(Clone, Copy, Default)]
+struct Channel {
+    fnum: u16,
+    block: u8,
+    key_on: bool,
+    feedback: u8,
+    additive: bool,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+enum EnvStage {
+    #[default]
+    Off,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+#[derive(Clone, Copy)]
+struct Operator {
+    // registers
+    tremolo: bool,
+    vibrato: bool,
+    sustained: bool,
+    ksr: bool,
+    mult: u8,
+    ksl: u8,
+    total_level: u8,
+    attack_rate: u8,
+    decay_rate: u8,
+    sustain_level: u8,
+    release_rate: u8,
+    wave: u8,
+    // state
+    stage: EnvStage,
+    env_db: f64,
+    phase: f64,
+    prev_out: [f64; 2],
+}
+
+impl Default for Operator {
+    fn default() -> Self {
+        Self {
+            tremolo: false,
+            vibrato: false,
+            sustained: false,
+            ksr: false,
+            mult: 0,
+            ksl: 0,
+            total_level: 0,
+            attack_rate: 0,
+            decay_rate: 0,
+            sustain_level: 0,
+            release_rate: 0,
+            wave: 0,
+            stage: EnvStage::Off,
+            env_db: MAX_ATTENUATION,
+            phase: 0.0,
+            prev_out: [0.0; 2],
+        }
+    }
+}
+
+impl Operator {
+    fn key(&mut self, key_on: bool) {
+        if key_on {
+            self.stage = EnvStage::Attack;
+            self.phase = 0.0;
+        } else if self.stage != EnvStage::Off {
+            self.stage = EnvStage::Release;
+        }
+    }
+
+    /// Compute the next output of the operator (between -1 and 1), and advance its phase and envelope.
+    /// The phase offset (modulation or feedback) is in periods.
+    fn output(&mut self, ch: &Channel, phase_offset: f64, lfo: (f64, f64), wse: bool, dt: f64) -> f64 {
+        self.advance_envelope(ch, dt);
+
+        // attenuation = envelope + total level + key scaling + tremolo
+        let mut atten = self.env_db + (self.total_level as f64) * 0.75;
+        if self.ksl > 0 {
+            let ksl_db = (KSL_TABLE[(ch.fnum >> 6) as usize] - 6.0 * (7 - ch.block) as f64).max(0.0);
+            atten += ksl_db * KSL_FACTORS[self.ksl as usize];
+        }
+        if self.tremolo {
+            atten += lfo.0;
+        }
+
+        let wave = if wse { self.wave } else { 0 };
+        let out = if atten < MAX_ATTENUATION {
+            waveform(wave, self.phase + phase_offset) * (10.0_f64).powf(-atten / 20.0)
+        } else {
+            0.0
+        };
+
+        // advance phase
+        let mut freq = (ch.fnum as f64) * ((1 << ch.block) as f64) * NATIVE_RATE / ((1 << 20) as f64);
+        freq *= MULTIPLIERS[self.mult as usize];
+        if self.vibrato {
+            freq *= lfo.1;
+        }
+        self.phase = (self.phase + freq * dt).fract();
+
+        self.prev_out = [self.prev_out[1], out];
+        out
+    }
+
+    fn advance_envelope(&mut self, ch: &Channel, dt: f64) {
+        // key scale rate => faster envelopes for higher notes
+        let ksr_offset = (((ch.block as u16) << 1) | ((ch.fnum >> 9) & 1)) >> (if self.ksr { 0 } else { 2 });
+        let ksr_offset = ksr_offset as u8;
+        let sustain_db = if self.sustain_level == 15 {
+            93.0
+        } else {
+            (self.sustain_level as f64) * 3.0
+        };
+
+        match self.stage {
+            EnvStage::Off => self.env_db = MAX_ATTENUATION,
+            EnvStage::Attack => {
+                let rate = effective_rate(self.attack_rate, ksr_offset);
+                if rate >= 60 {
+                    self.env_db = 0.0;
+                } else if rate > 0 {
+                    self.env_db -= MAX_ATTENUATION * dt * rate_factor(rate) / ATTACK_TIME;
+                }
+                if self.env_db <= 0.0 {
+                    self.env_db = 0.0;
+                    self.stage = EnvStage::Decay;
+                }
+            }
+            EnvStage::Decay => {
+                let rate = effective_rate(self.decay_rate, ksr_offset);
+                self.env_db += MAX_ATTENUATION * dt * rate_factor(rate) / DECAY_TIME;
+                if self.env_db >= sustain_db {
+                    self.env_db = sustain_db;
+                    self.stage = EnvStage::Sustain;
+                }
+            }
+            EnvStage::Sustain => {
+                // non-sustained sounds keep decaying, with the release rate
+                if !self.sustained {
+                    self.release(ksr_offset, dt);
+                }
+            }
+            EnvStage::Release => self.release(ksr_offset, dt),
+        }
+    }
+
+    fn release(&mut self, ksr_offset: u8, dt: f64) {
+        let rate = effective_rate(self.release_rate, ksr_offset);
+        self.env_db += MAX_ATTENUATION * dt * rate_factor(rate) / DECAY_TIME;
+        if self.env_db >= MAX_ATTENUATION {
+            self.env_db = MAX_ATTENUATION;
+            self.stage = EnvStage::Off;
+        }
+    }
+}
+
+/// The actual envelope rate (0..=63), based on the register value (0..=15) and the key scale.
+#[inline]
+fn effective_rate(rate: u8, ksr_offset: u8) -> u8 {
+    if rate == 0 {
+        0
+    } else {
+        (4 * rate + ksr_offset).min(63)
+    }
+}
+
+/// How much faster an envelope rate is, compared to the slowest one (4).
+/// Each 4 steps double the speed.
+#[inline]
+fn rate_factor(rate: u8) -> f64 {
+    if rate < 4 {
+        0.0
+    } else {
+        ((rate / 4 - 1) as f64).exp2() * (1.0 + ((rate & 3) as f64) * 0.25)
+    }
+}
+
+/// The 4 OPL2 waveforms: sine, half sine, absolute sine and quarter ("pulse") sine.
+#[inline]
+fn waveform(wave: u8, phase: f64) -> f64 {
+    let phase = phase.rem_euclid(1.0);
+    let sin = (2.0 * PI * phase).sin();
+    match wave {
+        0 => sin,
+        1 => sin.max(0.0),
+        2 => sin.abs(),
+        _ => {
+            if (phase % 0.5) < 0.25 {
+                sin.abs()
+            } else {
+                0.0
+            }
+        }
+    }
+}
+
+/// Where the playback of some IMF music is - without the music itself, so that it can also play borrowed music.
+struct ImfState {
+    opl: Opl2,
+    cmd_idx: usize,
+    /// samples left until the next command
+    samples_left: usize,
+    /// fractional samples, so that the commands stay in sync with the tick rate
+    frac: u64,
+}
+
+impl ImfState {
+    fn new(sample_rate: u32) -> Self {
+        let mut opl = Opl2::new(sample_rate);
+        // the music expects wave select to be enabled (done at startup, in the original code)
+        opl.write_reg(0x01, 0x20);
+        Self {
+            opl,
+            cmd_idx: 0,
+            samples_left: 0,
+            frac: 0,
+        }
+    }
+
+    #[inline]
+    fn is_finished(&self, music: &ImfMusic) -> bool {
+        self.cmd_idx >= music.len() && self.samples_left == 0
+    }
+
+    fn generate(&mut self, music: &ImfMusic, looping: bool, out: &mut [i16]) {
+        let mut start = 0;
+        while start < out.len() {
+            // execute commands, until there is something to wait for
+            while self.samples_left == 0 {
+                if self.cmd_idx >= music.len() {
+                    if looping && music.duration_ticks() > 0 {
+                        self.cmd_idx = 0;
+                    } else {
+                        out[start..].fill(0);
+                        return;
+                    }
+                }
+                let cmd = music.commands[self.cmd_idx];
+                self.cmd_idx += 1;
+                self.opl.write_reg(cmd.reg, cmd.value);
+                self.frac += (cmd.delay as u64) * (self.opl.sample_rate() as u64);
+                self.samples_left = (self.frac / (IMF_TICK_RATE as u64)) as usize;
+                self.frac %= IMF_TICK_RATE as u64;
+            }
+            // generate samples until the next command
+            let cnt = self.samples_left.min(out.len() - start);
+            self.opl.generate(&mut out[start..start + cnt]);
+            self.samples_left -= cnt;
+            start += cnt;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Palette effects - the whole screen flashes red on damage and gold on bonus pickups, and fades to/from black
+//! between levels. Like in the original, these are done by shifting the palette, not the pixels
+//! (see `UpdatePaletteShifts` in WL_PLAY.C and `VL_FadeOut` / `VL_FadeIn` in ID_VL.C).
+
+use crate::{Palette, RGB};
+
+/// The original counts the time in tics, of 1/70 seconds.
+pub const TICS_PER_SECOND: f64 = 70.0;
+
+/// Something which makes the palette flash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteFlash {
+    /// The player was hurt, by this many health points
+    Damage(i32),
+    /// The player picked up something
+    Bonus,
+}
+
+/// The state of the palette effects: the flashes, which fade out over time, and the fade to/from black.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PaletteEffects {
+    /// How long the red flash still lasts, in tics (= the damage taken, like `damagecount` in the original)
+    damage_tics: f64,
+    /// How long the gold flash still lasts, in tics
+    bonus_tics: f64,
+    fade: Fade,
+}
+
+impl PaletteEffects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn start_flash(&mut self, flash: PaletteFlash) {
+        match flash {
+            // more damage => a stronger and longer flash
+            PaletteFlash::Damage(damage) => self.damage_tics += damage.max(0) as f64,
+            PaletteFlash::Bonus => self.bonus_tics = (NUM_WHITE_SHIFTS * WHITE_TICS) as f64,
+        }
+    }
+
+    /// Stop the flashes (e.g. when the level changes).
+    pub fn clear_flashes(&mut self) {
+        self.damage_tics = 0.0;
+        self.bonus_tics = 0.0;
+    }
+
+    /// Start fading to black - the screen stays black until `fade_in`.
+    pub fn fade_out(&mut self) {
+        self.fade = Fade::Out { tics: 0.0 };
+    }
+
+    /// Start fading in from black.
+    pub fn fade_in(&mut self) {
+        self.fade = Fade::In { tics: 0.0 };
+    }
+
+    /// Check if a fade is in progress.
+    #[inline]
+    pub fn is_fading(&self) -> bool {
+        matches!(self.fade, Fade::Out { .. } | Fade::In { .. })
+    }
+
+    /// Check if the screen is black, after a `fade_out`.
+    #[inline]
+    pub fn is_black(&self) -> bool {
+        self.fade == Fade::Black
+    }
+
+    pub fn update(&mut self, elapsed_time: f64) {
+        let tics = elapsed_time * TICS_PER_SECOND;
+        self.damage_tics = (self.damage_tics - tics).max(0.0);
+        self.bonus_tics = (self.bonus_tics - tics).max(0.0);
+        self.fade = match self.fade {
+            Fade::Out { tics: done } if done + tics >= FADE_STEPS as f64 => Fade::Black,
+            Fade::Out { tics: done } => Fade::Out { tics: done + tics },
+            Fade::In { tics: done } if done + tics >= FADE_STEPS as f64 => Fade::None,
+            Fade::In { tics: done } => Fade::In { tics: done + tics },
+            fade => fade,
+        };
+    }
+
+    /// How strong the red flash is now (0.0 = none).
+    pub fn red_amount(&self) -> f64 {
+        if self.damage_tics <= 0.0 {
+            return 0.0;
+        }
+        // the original: red = damagecount/10 + 1, at most NUMREDSHIFTS, and the shift is red/REDSTEPS
+        let red = ((self.damage_tics as usize) / 10 + 1).min(NUM_RED_SHIFTS);
+        (red as f64) / (RED_STEPS as f64)
+    }
+
+    /// How strong the gold flash is now (0.0 = none).
+    pub fn gold_amount(&self) -> f64 {
+        if self.bonus_tics <= 0.0 {
+            return 0.0;
+        }
+        let white = ((self.bonus_tics as usize) / WHITE_TICS + 1).min(NUM_WHITE_SHIFTS);
+        (white as f64) / (WHITE_STEPS as f64)
+    }
+
+    /// How dark the screen is now, because of the fades (0.0 = not at all, 1.0 = black).
+    pub fn black_amount(&self) -> f64 {
+        // like the original, the fades go in steps (one per tic)
+        let step = |tics: f64| (tics as usize).min(FADE_STEPS) as f64 / (FADE_STEPS as f64);
+        match self.fade {
+            Fade::None => 0.0,
+            Fade::Out { tics } => step(tics),
+            Fade::Black => 1.0,
+            Fade::In { tics } => 1.0 - step(tics),
+        }
+    }
+
+    /// Apply the effects to a palette. Returns `None` if there is nothing to apply.
+    /// Like in the original, the red flash wins over the gold one. The fade is applied over both.
+    pub fn apply(&self, palette: &Palette) -> Option<Palette> {
+        let (red, gold, black) = (self.red_amount(), self.gold_amount(), self.black_amount());
+        let flashed = if red > 0.0 {
+            Some(palette.blended(RED, red))
+        } else if gold > 0.0 {
+            Some(palette.blended(GOLD, gold))
+        } else {
+            None
+        };
+        if black > 0.0 {
+            Some(flashed.as_ref().unwrap_or(palette).blended(BLACK, black))
+        } else {
+            flashed
+        }
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+// the constants from WL_PLAY.C
+const NUM_RED_SHIFTS: usize = 6;
+const RED_STEPS: usize = 8;
+const NUM_WHITE_SHIFTS: usize = 3;
+const WHITE_STEPS: usize = 20;
+const WHITE_TICS: usize = 6;
+// VW_FadeOut / VW_FadeIn use 30 steps, one per VBL
+const FADE_STEPS: usize = 30;
+
+const RED: RGB = RGB { r: 255, g: 0, b: 0 };
+/// The original shifts toward (64, 62, 0), in 6-bit VGA values => "gold"
+const GOLD: RGB = RGB { r: 255, g: 248, b: 0 };
+const BLACK: RGB = RGB { r: 0, g: 0, b: 0 };
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Fade {
+    #[default]
+    None,
+    Out {
+        tics: f64,
+    },
+    /// Faded out completely
+    Black,
+    In {
+        tics: f64,
+    },
+}
@@ -0,0 +1,242 @@
+//! Palettes - the 256 colors used by all the graphics.
+//!
+//! Each game has one palette (Wolf3D and SOD differ in only 2 colors), but some pics have their own palette
+//! (e.g. the SOD title screen). Palettes can be loaded from:
+//! * GAMEPAL.OBJ - the object file linked into the original executable (see the original sources)
+//! * raw palette data: 768 bytes of RGB values, either 6-bit VGA values (like in GAMEPAL.OBJ and VGAGRAPH)
+//!   or 8-bit values (e.g. .pal and .lmp files)
+//! * JASC-PAL text files (e.g. from Paint Shop Pro)
+
+use crate::RGB;
+
+/// The number of bytes in a raw palette: 256 RGB colors.
+pub const PALETTE_SIZE: usize = 256 * 3;
+
+/// A palette of 256 colors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette(Vec<RGB>);
+
+impl Palette {
+    /// The built-in Wolf3D palette (from GAMEPAL.OBJ).
+    pub fn wolf3d() -> Self {
+        Self::from_rgb_bytes(WOLF3D_PALETTE).unwrap()
+    }
+
+    /// The built-in SOD palette - same as the Wolf3D one, except for 2 colors.
+    pub fn sod() -> Self {
+        let mut palette = Self::wolf3d();
+        palette.0[166] = RGB::from(0, 56, 0);
+        palette.0[167] = RGB::from(0, 40, 0);
+        palette
+    }
+
+    /// The built-in palette for Wolf3D or SOD.
+    #[inline]
+    pub fn builtin(sod: bool) -> Self {
+        if sod {
+            Self::sod()
+        } else {
+            Self::wolf3d()
+        }
+    }
+
+    /// Create a palette from 6-bit VGA values (0..=63), like the ones in GAMEPAL.OBJ or in VGAGRAPH.
+    /// Needs at least `PALETTE_SIZE` bytes (the rest is ignored).
+    pub fn from_vga_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let bytes = palette_bytes(bytes)?;
+        if let Some(v) = bytes.iter().find(|v| **v > 63) {
+            return Err(format!("Invalid VGA palette value: {v}"));
+        }
+        // 0..=62 are just multiplied by 4, 63 becomes 255 (so white is really white)
+        let colors = bytes
+            .iter()
+            .map(|&v| if v == 63 { 255 } else { v * 4 })
+            .collect::<Vec<u8>>();
+        Self::from_rgb_bytes(&colors)
+    }
+
+    /// Create a palette from 8-bit RGB values. Needs at least `PALETTE_SIZE` bytes (the rest is ignored).
+    pub fn from_rgb_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let bytes = palette_bytes(bytes)?;
+        Ok(Self(
+            bytes.chunks_exact(3).map(|c| RGB::from(c[0], c[1], c[2])).collect(),
+        ))
+    }
+
+    /// Parse a palette file: JASC-PAL, GAMEPAL.OBJ, or raw palette data.
+    /// Raw data is considered to have 6-bit VGA values if all of its values are at most 63,
+    /// otherwise 8-bit values (an 8-bit palette that dark would be quite useless anyway).
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.starts_with(b"JASC-PAL") {
+            parse_jasc(bytes)
+        } else if bytes.first() == Some(&OMF_THEADR) {
+            Self::from_vga_bytes(&omf_data(bytes)?)
+        } else if palette_bytes(bytes)?.iter().all(|v| *v <= 63) {
+            Self::from_vga_bytes(bytes)
+        } else {
+            Self::from_rgb_bytes(bytes)
+        }
+    }
+
+    #[inline]
+    pub fn color(&self, c: u8) -> RGB {
+        self.0[c as usize]
+    }
+
+    /// Blend all the colors toward a color, by `amount` (0.0 = no change, 1.0 = all colors become `target`).
+    /// Used for the palette effects (flashes and fades).
+    pub fn blended(&self, target: RGB, amount: f64) -> Self {
+        let mix = |c: u8, t: u8| ((c as f64) + ((t as f64) - (c as f64)) * amount).round() as u8;
+        Self(
+            self.0
+                .iter()
+                .map(|c| RGB::from(mix(c.r, target.r), mix(c.g, target.g), mix(c.b, target.b)))
+                .collect(),
+        )
+    }
+
+    /// Find the color closest to an RGB color - skipping `skip` (e.g. the transparent color), if given.
+    pub fn nearest_color(&self, rgb: RGB, skip: Option<u8>) -> u8 {
+        let mut best = 0;
+        let mut best_dist = u32::MAX;
+        for (c, color) in self.0.iter().enumerate() {
+            if Some(c as u8) == skip {
+                continue;
+            }
+            let dr = (color.r as i32) - (rgb.r as i32);
+            let dg = (color.g as i32) - (rgb.g as i32);
+            let db = (color.b as i32) - (rgb.b as i32);
+            let dist = (dr * dr + dg * dg + db * db) as u32;
+            if dist < best_dist {
+                best = c as u8;
+                best_dist = dist;
+                if dist == 0 {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// The palette as 8-bit RGB values (`PALETTE_SIZE` bytes).
+    pub fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|c| [c.r, c.g, c.b]).collect()
+    }
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// OMF record types: the first record of an object file + the data records
+const OMF_THEADR: u8 = 0x80;
+const OMF_LEDATA: u8 = 0xA0;
+
+fn palette_bytes(bytes: &[u8]) -> Result<&[u8], String> {
+    if bytes.len() < PALETTE_SIZE {
+        return Err(format!("Palette has {} bytes instead of {PALETTE_SIZE}", bytes.len()));
+    }
+    Ok(&bytes[0..PALETTE_SIZE])
+}
+
+/// Parse a JASC-PAL file: header, version, number of colors, then one "R G B" line per color.
+fn parse_jasc(bytes: &[u8]) -> Result<Palette, String> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+    lines.next(); // JASC-PAL
+    lines.next(); // version
+    let cnt_colors: usize = lines
+        .next()
+        .and_then(|line| line.parse().ok())
+        .ok_or("JASC-PAL: invalid number of colors")?;
+    let mut colors = Vec::with_capacity(PALETTE_SIZE);
+    for line in lines.take(cnt_colors) {
+        let rgb: Vec<u8> = line.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        if rgb.len() != 3 {
+            return Err(format!("JASC-PAL: invalid color: {line}"));
+        }
+        colors.extend(rgb);
+    }
+    // palettes with fewer colors are padded with black
+    colors.resize(PALETTE_SIZE.max(colors.len()), 0);
+    Palette::from_rgb_bytes(&colors)
+}
+
+/// Extract the data from an OMF object file (like GAMEPAL.OBJ) - the contents of all its LEDATA records.
+/// Each record is: type (u8), length (u16) and the contents (the last byte of which is a checksum).
+/// LEDATA contents: segment index (1 or 2 bytes), offset (u16), then the data.
+fn omf_data(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut data = vec![];
+    let mut idx = 0;
+    while idx + 3 <= bytes.len() {
+        let rec_type = bytes[idx];
+        let rec_len = u16::from_le_bytes([bytes[idx + 1], bytes[idx + 2]]) as usize;
+        let contents = bytes
+            .get(idx + 3..idx + 3 + rec_len)
+            .ok_or(format!("OMF record at 0x{idx:X} ends abruptly"))?;
+        if rec_type == OMF_LEDATA && rec_len > 0 {
+            let contents = &contents[0..rec_len - 1];
+            let seg_len = if contents.first().is_some_and(|b| b & 0x80 != 0) {
+                2
+            } else {
+                1
+            };
+            if contents.len() < seg_len + 2 {
+                return Err(format!("OMF LEDATA record at 0x{idx:X} is too short"));
+            }
+            let offset = u16::from_le_bytes([contents[seg_len], contents[seg_len + 1]]) as usize;
+            let chunk = &contents[seg_len + 2..];
+            if data.len() < offset + chunk.len() {
+                data.resize(offset + chunk.len(), 0);
+            }
+            data[offset..offset + chunk.len()].copy_from_slice(chunk);
+        }
+        idx += 3 + rec_len;
+    }
+    Ok(data)
+}
+
+/// The Wolf3D palette, as 8-bit RGB values.
+const WOLF3D_PALETTE: &[u8] = &[
+    0x00, 0x00, 0x00, 0x00, 0x00, 0xA8, 0x00, 0xA8, 0x00, 0x00, 0xA8, 0xA8, 0xA8, 0x00, 0x00, 0xA8, 0x00, 0xA8, 0xA8,
+    0x54, 0x00, 0xA8, 0xA8, 0xA8, 0x54, 0x54, 0x54, 0x54, 0x54, 0xFF, 0x54, 0xFF, 0x54, 0x54, 0xFF, 0xFF, 0xFF, 0x54,
+    0x54, 0xFF, 0x54, 0xFF, 0xFF, 0xFF, 0x54, 0xFF, 0xFF, 0xFF, 0xEC, 0xEC, 0xEC, 0xDC, 0xDC, 0xDC, 0xD0, 0xD0, 0xD0,
+    0xC0, 0xC0, 0xC0, 0xB4, 0xB4, 0xB4, 0xA8, 0xA8, 0xA8, 0x98, 0x98, 0x98, 0x8C, 0x8C, 0x8C, 0x7C, 0x7C, 0x7C, 0x70,
+    0x70, 0x70, 0x64, 0x64, 0x64, 0x54, 0x54, 0x54, 0x48, 0x48, 0x48, 0x38, 0x38, 0x38, 0x2C, 0x2C, 0x2C, 0x20, 0x20,
+    0x20, 0xFF, 0x00, 0x00, 0xEC, 0x00, 0x00, 0xE0, 0x00, 0x00, 0xD4, 0x00, 0x00, 0xC8, 0x00, 0x00, 0xBC, 0x00, 0x00,
+    0xB0, 0x00, 0x00, 0xA4, 0x00, 0x00, 0x98, 0x00, 0x00, 0x88, 0x00, 0x00, 0x7C, 0x00, 0x00, 0x70, 0x00, 0x00, 0x64,
+    0x00, 0x00, 0x58, 0x00, 0x00, 0x4C, 0x00, 0x00, 0x40, 0x00, 0x00, 0xFF, 0xD8, 0xD8, 0xFF, 0xB8, 0xB8, 0xFF, 0x9C,
+    0x9C, 0xFF, 0x7C, 0x7C, 0xFF, 0x5C, 0x5C, 0xFF, 0x40, 0x40, 0xFF, 0x20, 0x20, 0xFF, 0x00, 0x00, 0xFF, 0xA8, 0x5C,
+    0xFF, 0x98, 0x40, 0xFF, 0x88, 0x20, 0xFF, 0x78, 0x00, 0xE4, 0x6C, 0x00, 0xCC, 0x60, 0x00, 0xB4, 0x54, 0x00, 0x9C,
+    0x4C, 0x00, 0xFF, 0xFF, 0xD8, 0xFF, 0xFF, 0xB8, 0xFF, 0xFF, 0x9C, 0xFF, 0xFF, 0x7C, 0xFF, 0xF8, 0x5C, 0xFF, 0xF4,
+    0x40, 0xFF, 0xF4, 0x20, 0xFF, 0xF4, 0x00, 0xE4, 0xD8, 0x00, 0xCC, 0xC4, 0x00, 0xB4, 0xAC, 0x00, 0x9C, 0x9C, 0x00,
+    0x84, 0x84, 0x00, 0x70, 0x6C, 0x00, 0x58, 0x54, 0x00, 0x40, 0x40, 0x00, 0xD0, 0xFF, 0x5C, 0xC4, 0xFF, 0x40, 0xB4,
+    0xFF, 0x20, 0xA0, 0xFF, 0x00, 0x90, 0xE4, 0x00, 0x80, 0xCC, 0x00, 0x74, 0xB4, 0x00, 0x60, 0x9C, 0x00, 0xD8, 0xFF,
+    0xD8, 0xBC, 0xFF, 0xB8, 0x9C, 0xFF, 0x9C, 0x80, 0xFF, 0x7C, 0x60, 0xFF, 0x5C, 0x40, 0xFF, 0x40, 0x20, 0xFF, 0x20,
+    0x00, 0xFF, 0x00, 0x00, 0xFF, 0x00, 0x00, 0xEC, 0x00, 0x00, 0xE0, 0x00, 0x00, 0xD4, 0x00, 0x04, 0xC8, 0x00, 0x04,
+    0xBC, 0x00, 0x04, 0xB0, 0x00, 0x04, 0xA4, 0x00, 0x04, 0x98, 0x00, 0x04, 0x88, 0x00, 0x04, 0x7C, 0x00, 0x04, 0x70,
+    0x00, 0x04, 0x64, 0x00, 0x04, 0x58, 0x00, 0x04, 0x4C, 0x00, 0x04, 0x40, 0x00, 0xD8, 0xFF, 0xFF, 0xB8, 0xFF, 0xFF,
+    0x9C, 0xFF, 0xFF, 0x7C, 0xFF, 0xF8, 0x5C, 0xFF, 0xFF, 0x40, 0xFF, 0xFF, 0x20, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0x00,
+    0xE4, 0xE4, 0x00, 0xCC, 0xCC, 0x00, 0xB4, 0xB4, 0x00, 0x9C, 0x9C, 0x00, 0x84, 0x84, 0x00, 0x70, 0x70, 0x00, 0x58,
+    0x58, 0x00, 0x40, 0x40, 0x5C, 0xBC, 0xFF, 0x40, 0xB0, 0xFF, 0x20, 0xA8, 0xFF, 0x00, 0x9C, 0xFF, 0x00, 0x8C, 0xE4,
+    0x00, 0x7C, 0xCC, 0x00, 0x6C, 0xB4, 0x00, 0x5C, 0x9C, 0xD8, 0xD8, 0xFF, 0xB8, 0xBC, 0xFF, 0x9C, 0x9C, 0xFF, 0x7C,
+    0x80, 0xFF, 0x5C, 0x60, 0xFF, 0x40, 0x40, 0xFF, 0x20, 0x24, 0xFF, 0x00, 0x04, 0xFF, 0x00, 0x00, 0xFF, 0x00, 0x00,
+    0xEC, 0x00, 0x00, 0xE0, 0x00, 0x00, 0xD4, 0x00, 0x00, 0xC8, 0x00, 0x00, 0xBC, 0x00, 0x00, 0xB0, 0x00, 0x00, 0xA4,
+    0x00, 0x00, 0x98, 0x00, 0x00, 0x88, 0x00, 0x00, 0x7C, 0x00, 0x00, 0x70, 0x00, 0x00, 0x64, 0x00, 0x00, 0x58, 0x00,
+    0x00, 0x4C, 0x00, 0x00, 0x40, 0x28, 0x28, 0x28, 0xFF, 0xE0, 0x34, 0xFF, 0xD4, 0x24, 0xFF, 0xCC, 0x18, 0xFF, 0xC0,
+    0x08, 0xFF, 0xB4, 0x00, 0xB4, 0x20, 0xFF, 0xA8, 0x00, 0xFF, 0x98, 0x00, 0xE4, 0x80, 0x00, 0xCC, 0x74, 0x00, 0xB4,
+    0x60, 0x00, 0x9C, 0x50, 0x00, 0x84, 0x44, 0x00, 0x70, 0x34, 0x00, 0x58, 0x28, 0x00, 0x40, 0xFF, 0xD8, 0xFF, 0xFF,
+    0xB8, 0xFF, 0xFF, 0x9C, 0xFF, 0xFF, 0x7C, 0xFF, 0xFF, 0x5C, 0xFF, 0xFF, 0x40, 0xFF, 0xFF, 0x20, 0xFF, 0xFF, 0x00,
+    0xFF, 0xE0, 0x00, 0xE4, 0xC8, 0x00, 0xCC, 0xB4, 0x00, 0xB4, 0x9C, 0x00, 0x9C, 0x84, 0x00, 0x84, 0x6C, 0x00, 0x70,
+    0x58, 0x00, 0x58, 0x40, 0x00, 0x40, 0xFF, 0xE8, 0xDC, 0xFF, 0xE0, 0xD0, 0xFF, 0xD8, 0xC4, 0xFF, 0xD4, 0xBC, 0xFF,
+    0xCC, 0xB0, 0xFF, 0xC4, 0xA4, 0xFF, 0xBC, 0x9C, 0xFF, 0xB8, 0x90, 0xFF, 0xB0, 0x80, 0xFF, 0xA4, 0x70, 0xFF, 0x9C,
+    0x60, 0xF0, 0x94, 0x5C, 0xE8, 0x8C, 0x58, 0xDC, 0x88, 0x54, 0xD0, 0x80, 0x50, 0xC8, 0x7C, 0x4C, 0xBC, 0x78, 0x48,
+    0xB4, 0x70, 0x44, 0xA8, 0x68, 0x40, 0xA0, 0x64, 0x3C, 0x9C, 0x60, 0x38, 0x90, 0x5C, 0x34, 0x88, 0x58, 0x30, 0x80,
+    0x50, 0x2C, 0x74, 0x4C, 0x28, 0x6C, 0x48, 0x24, 0x5C, 0x40, 0x20, 0x54, 0x3C, 0x1C, 0x48, 0x38, 0x18, 0x40, 0x30,
+    0x18, 0x38, 0x2C, 0x14, 0x28, 0x20, 0x0C, 0x60, 0x00, 0x64, 0x00, 0x64, 0x64, 0x00, 0x60, 0x60, 0x00, 0x00, 0x1C,
+    0x00, 0x00, 0x2C, 0x30, 0x24, 0x10, 0x48, 0x00, 0x48, 0x50, 0x00, 0x50, 0x00, 0x00, 0x34, 0x1C, 0x1C, 0x1C, 0x4C,
+    0x4C, 0x4C, 0x5C, 0x5C, 0x5C, 0x40, 0x40, 0x40, 0x30, 0x30, 0x30, 0x34, 0x34, 0x34, 0xD8, 0xF4, 0xF4, 0xB8, 0xE8,
+    0xE8, 0x9C, 0xDC, 0xDC, 0x74, 0xC8, 0xC8, 0x48, 0xC0, 0xC0, 0x20, 0xB4, 0xB4, 0x20, 0xB0, 0xB0, 0x00, 0xA4, 0xA4,
+    0x00, 0x98, 0x98, 0x00, 0x8C, 0x8C, 0x00, 0x84, 0x84, 0x00, 0x7C, 0x7C, 0x00, 0x78, 0x78, 0x00, 0x74, 0x74, 0x00,
+    0x70, 0x70, 0x00, 0x6C, 0x6C, 0xFF, 0x00, 0xFF,
+];
@@ -0,0 +1,101 @@
+//! PC speaker synthesizer - renders the PC speaker sounds to PCM, as square waves.
+//!
+//! Each byte of a PC speaker sound is played for one tick (at 140 Hz):
+//! the PIT timer divisor is `byte * 60`, and a ZERO byte turns the speaker off (silence).
+//! -> see SDL_PCPlaySound and SDL_PCService in [ID_SD.C](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/ID_SD.C)
+
+use crate::{PcSound, SOUND_TICK_RATE};
+
+/// The frequency of the PIT (Programmable Interval Timer) which drives the PC speaker.
+pub const PIT_FREQUENCY: f64 = 1193181.0;
+
+/// The amplitude of the square waves (square waves are loud, so not full scale).
+pub const PC_SPEAKER_VOLUME: f64 = 6000.0;
+
+/// Get the frequency of a PC speaker tone, or `None` for silence.
+#[inline]
+pub fn pc_tone_frequency(tone: u8) -> Option<f64> {
+    if tone == 0 {
+        None
+    } else {
+        Some(PIT_FREQUENCY / ((tone as f64) * 60.0))
+    }
+}
+
+/// The PC speaker - plays a square wave at a given frequency, or stays silent.
+pub struct PcSpeaker {
+    sample_rate: u32,
+    frequency: Option<f64>,
+    phase: f64,
+}
+
+impl PcSpeaker {
+    pub fn new(sample_rate: u32) -> Self {
+        assert!(sample_rate > 0);
+        Self {
+            sample_rate,
+            frequency: None,
+            phase: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Set the current tone (0 = silence).
+    /// The phase is kept when the tone changes, so there are no clicks between tones.
+    pub fn set_tone(&mut self, tone: u8) {
+        self.frequency = pc_tone_frequency(tone);
+    }
+
+    /// Generate mono, signed 16-bit samples into the output buffer.
+    /// Each sample is the average of the square wave over the sample's duration,
+    /// which takes care of most of the aliasing of a "naive" square wave.
+    pub fn generate(&mut self, out: &mut [i16]) {
+        let Some(freq) = self.frequency else {
+            out.fill(0);
+            return;
+        };
+        let step = freq / (self.sample_rate as f64);
+        for sample in out.iter_mut() {
+            let next = self.phase + step;
+            let avg = (square_integral(next) - square_integral(self.phase)) / step;
+            *sample = (avg * PC_SPEAKER_VOLUME).round() as i16;
+            self.phase = next.fract();
+        }
+    }
+}
+
+/// Render a PC speaker sound: one tone per tick, at 140 Hz.
+pub fn render_pc_sound(sound: &PcSound, sample_rate: u32) -> Vec<i16> {
+    let mut speaker = PcSpeaker::new(sample_rate);
+    let total = (sound.len() as u64) * (sample_rate as u64) / (SOUND_TICK_RATE as u64);
+    let mut out = vec![0; total as usize];
+    // split the output exactly at the tick boundaries
+    let mut start = 0;
+    for (tick, &tone) in sound.tones.iter().enumerate() {
+        let end = ((tick as u64 + 1) * (sample_rate as u64) / (SOUND_TICK_RATE as u64)) as usize;
+        speaker.set_tone(tone);
+        speaker.generate(&mut out[start..end]);
+        start = end;
+    }
+    out
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// The integral of a square wave (+1 in the first half of each period, -1 in the second),
+/// from 0 to `x` (in periods). Since a whole period integrates to zero, only the fraction matters.
+#[inline]
+fn square_integral(x: f64) -> f64 {
+    let f = x.fract();
+    if f < 0.5 {
+        f
+    } else {
+        1.0 - f
+    }
+}
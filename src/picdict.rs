@@ -1,11 +1,27 @@
 //! Picture dictionary - for accessing the PIC-s.
-//! Since their indices are game-dependent, this module knows where each pic is located for each game type.
+//! Since their indices are game-dependent, each game type has a `PicTable`, which tells where each pic is located.
+//! The tables for all known releases are built in, but they can also be loaded from a text file
+//! (see `PicTable::parse`), so a new data version can be supported without recompiling.
 
-use crate::GfxData;
+use std::fmt;
 
-//#[derive(Clone, Copy, PartialEq, Eq)]
-#[derive(Clone, Copy, PartialEq, Eq)] //, strum_macros::Display, strum_macros::FromRepr)]
-pub enum PicType {
+use crate::{GameRelease, GfxData, Palette};
+
+/// Defines the `PicType` enum + the names of its variants (for looking them up by name).
+macro_rules! pic_types {
+    ($($name:ident,)*) => {
+        /// All the pics used by the game, regardless of where they are located in the VGAGRAPH file.
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum PicType {
+            $($name,)*
+        }
+
+        const PIC_TYPES: &[PicType] = &[$(PicType::$name,)*];
+        const PIC_NAMES: &[&str] = &[$(stringify!($name),)*];
+    };
+}
+
+pic_types! {
     BackDropScreen,
     //---
     OptionTitle,
@@ -133,28 +149,213 @@ pub enum PicType {
     //-------
     Paused,
     GetPsyched,
+    //------- help pics, used by the text articles (see `Article`)
+    HelpBJ,
+    HelpCastle,
+    HelpKeyboard,
+    HelpJoystick,
+    HelpHealth,
+    HelpTreasure,
+    HelpGun,
+    HelpKey,
+    HelpBlaze,
+    HelpWeapons,
+    HelpWolfLogo,
+    HelpVisa,
+    HelpMasterCard,
+    HelpIdLogo,
+    HelpTopWindow,
+    HelpLeftWindow,
+    HelpRightWindow,
+    HelpBottomInfo,
+    HelpGame,
+}
+
+impl PicType {
+    /// The name of the pic type (same as the enum variant).
+    #[inline]
+    pub fn name(self) -> &'static str {
+        PIC_NAMES[self as usize]
+    }
+
+    /// Find a pic type by its name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        PIC_NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .map(|idx| PIC_TYPES[idx])
+    }
+
+    /// Get the pic type at the given index (= its position in the enum).
+    pub fn from_index(idx: usize) -> Option<Self> {
+        PIC_TYPES.get(idx).copied()
+    }
+}
+
+impl fmt::Display for PicType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
-pub struct PicDict(Vec<GfxData>);
+/// Tells where each `PicType` is located in the VGAGRAPH file (as a pic index, NOT a chunk index).
+/// Pic types which are missing from a game have no index.
+/// Some pics also have their own palette, stored in another VGAGRAPH chunk,
+/// and some are split in 2 pics (the lower one is stacked under the first one).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PicTable {
+    indexes: Vec<Option<usize>>,
+    palette_chunks: Vec<(PicType, usize)>,
+    lower_pics: Vec<(PicType, usize)>,
+}
+
+impl PicTable {
+    /// The built-in table for a game release, or - if the release is unknown - for the game type
+    /// (by file extension). Unknown extensions get the Spear of Destiny table.
+    pub fn builtin(game_ext: &str, release: GameRelease) -> Self {
+        let text = match (release, game_ext) {
+            (GameRelease::Unknown, "WL1") => WL1_TABLE,
+            (GameRelease::Unknown, "WL3" | "WL6") => WL6_TABLE,
+            (GameRelease::Unknown, "SDM") => SDM_TABLE,
+            (GameRelease::Unknown, _) => SOD_TABLE,
+            // the known releases are all Wolf3D: the 1.4 ones have the layout of the released sources
+            // TODO the 1.0 and 1.1 releases have another layout => until they have their own tables,
+            // they get the 1.4 ones (a `PICDICT.ext` file next to the game files can fix the mapping)
+            (_, "WL1") => WL1_TABLE,
+            (_, _) => WL6_TABLE,
+        };
+        Self::parse(text).expect("invalid built-in pic table")
+    }
+
+    /// Parse a table from text. Each line maps a pic type, or a range of consecutive pic types,
+    /// to the index of the (first) pic in the VGAGRAPH file.
+    /// A pic type followed by `.palette` gets its palette from a VGAGRAPH chunk (by chunk index),
+    /// and one followed by `.lower` gets the pic at the given index stacked under it (same width):
+    /// ```text
+    /// # comments start with '#'
+    /// TitleScreen = 79
+    /// StatusKnife..StatusFaceGod3 = 98
+    /// TitleScreen.lower = 80
+    /// TitleScreen.palette = 153
+    /// ```
+    /// Errors contain the line number (starting from 1).
+    pub fn parse(text: &str) -> Result<Self, (usize, String)> {
+        let mut indexes = vec![None; TOTAL_PICS];
+        let mut palette_chunks = vec![];
+        let mut lower_pics = vec![];
+        for (line_idx, line) in text.lines().enumerate() {
+            let line_num = line_idx + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((types, pic_idx)) = line.split_once('=') else {
+                return Err((line_num, format!("Expected `PicType = index`, found: {line}")));
+            };
+            let pic_idx: usize = pic_idx
+                .trim()
+                .parse()
+                .map_err(|_| (line_num, format!("Invalid pic index: {}", pic_idx.trim())))?;
+            let lookup = |name: &str| {
+                PicType::from_name(name.trim()).ok_or((line_num, format!("Unknown pic type: {}", name.trim())))
+            };
+            if let Some(typ) = types.trim().strip_suffix(".palette") {
+                palette_chunks.push((lookup(typ)?, pic_idx));
+                continue;
+            }
+            if let Some(typ) = types.trim().strip_suffix(".lower") {
+                lower_pics.push((lookup(typ)?, pic_idx));
+                continue;
+            }
+            let (first, last) = types.split_once("..").unwrap_or((types, types));
+            let (first, last) = (lookup(first)? as usize, lookup(last)? as usize);
+            if first > last {
+                return Err((line_num, format!("Invalid pic type range: {}", types.trim())));
+            }
+            for (delta, idx) in indexes[first..=last].iter_mut().enumerate() {
+                *idx = Some(pic_idx + delta);
+            }
+        }
+        Ok(Self {
+            indexes,
+            palette_chunks,
+            lower_pics,
+        })
+    }
+
+    /// The index of a pic type in the VGAGRAPH file, if the game has it.
+    #[inline]
+    pub fn pic_index(&self, typ: PicType) -> Option<usize> {
+        self.indexes[typ as usize]
+    }
+
+    /// The pics which have their own palette + the VGAGRAPH chunk of each palette.
+    #[inline]
+    pub fn palette_chunks(&self) -> &[(PicType, usize)] {
+        &self.palette_chunks
+    }
+
+    /// The pics which are split in 2 + the index of the lower pic in the VGAGRAPH file.
+    #[inline]
+    pub fn lower_pics(&self) -> &[(PicType, usize)] {
+        &self.lower_pics
+    }
+}
+
+pub struct PicDict {
+    pics: Vec<GfxData>,
+    palettes: Vec<Option<Palette>>,
+    /// The pic type of each pic in the VGAGRAPH file (in file order)
+    types_in_file: Vec<Option<PicType>>,
+}
 
 impl PicDict {
+    /// Build the dictionary from the pics of the VGAGRAPH file (in file order), using the given table.
     #[inline]
-    pub fn new(game_ext: &str, input: Vec<GfxData>) -> Self {
-        let mapped_vec = into_ordered_pics_vec(game_ext, input);
-        Self(mapped_vec)
+    pub fn new(table: &PicTable, input: Vec<GfxData>) -> Self {
+        let mut types_in_file = vec![None; input.len()];
+        for (typ, idx) in PIC_TYPES.iter().zip(&table.indexes) {
+            if let Some(slot) = idx.and_then(|idx| types_in_file.get_mut(idx)) {
+                slot.get_or_insert(*typ);
+            }
+        }
+        let pics = into_ordered_pics_vec(table, input);
+        Self {
+            pics,
+            palettes: vec![None; TOTAL_PICS],
+            types_in_file,
+        }
     }
 
     #[inline]
     pub fn pic(&self, typ: PicType, delta_idx: usize) -> &GfxData {
         let idx = (typ as usize) + delta_idx;
-        assert!(idx < self.0.len());
-        &self.0[idx]
+        assert!(idx < self.pics.len());
+        &self.pics[idx]
     }
 
     #[inline]
     pub fn pic_by_index(&self, idx: usize) -> &GfxData {
-        assert!(idx < self.0.len());
-        &self.0[idx]
+        assert!(idx < self.pics.len());
+        &self.pics[idx]
+    }
+
+    /// Get a pic by its index in the VGAGRAPH file (e.g. the pics referenced by the text articles).
+    /// Only the pics which have a `PicType` are kept, so the others are `None`.
+    pub fn pic_by_file_index(&self, file_idx: usize) -> Option<&GfxData> {
+        let typ = (*self.types_in_file.get(file_idx)?)?;
+        Some(self.pic(typ, 0))
+    }
+
+    /// The palette of a pic, if it has its own (otherwise, the game palette is used).
+    #[inline]
+    pub fn pic_palette(&self, typ: PicType) -> Option<&Palette> {
+        self.palettes[typ as usize].as_ref()
+    }
+
+    #[inline]
+    pub fn set_pic_palette(&mut self, typ: PicType, palette: Palette) {
+        self.palettes[typ as usize] = Some(palette);
     }
 
     #[inline]
@@ -166,130 +367,122 @@ impl PicDict {
 //--------------------------------
 //  Internal stuff
 
-const BACK_DROP: usize = PicType::BackDropScreen as usize;
-//-------
-const OPTION_TITLE: usize = PicType::OptionTitle as usize;
-const SAVE_TITLE: usize = PicType::SaveGameTitle as usize;
-const EPISODE_6: usize = PicType::Episode6 as usize;
-//-------
-const FLOOR_END_GUY: usize = PicType::FloorEndGuy as usize;
-const FLOOR_END_WINNER: usize = PicType::FloorEndWinnerBJ as usize;
-//-------
-const STATUS_BAR: usize = PicType::StatusBar as usize;
-const TITLE_SCREEN: usize = PicType::TitleScreen as usize;
-const PG13_PIC: usize = PicType::Pg13Pic as usize;
-const CREDITS_SCREEN: usize = PicType::CreditsScreen as usize;
-const HIGH_SCORES: usize = PicType::HighScoresTitle as usize;
-//-------
-const STATUS_KNIFE: usize = PicType::StatusKnife as usize;
-const STATUS_GOD1: usize = PicType::StatusFaceGod1 as usize;
-const STATUS_GOD2: usize = PicType::StatusFaceGod2 as usize;
-const STATUS_GOD3: usize = PicType::StatusFaceGod3 as usize;
-//-------
-const PAUSED: usize = PicType::Paused as usize;
-const GET_PSYCHED: usize = PicType::GetPsyched as usize;
-const TOTAL_PICS: usize = GET_PSYCHED + 1;
-const BAD_IDX: usize = 0xFFFF;
+const TOTAL_PICS: usize = PIC_NAMES.len();
+
+// see GFXV_WL1.H
+const WL1_TABLE: &str = "
+HelpBJ..HelpGame = 0
+OptionTitle..Episode6 = 19
+FloorEndGuy..StatusFaceGod1 = 52
+Paused..GetPsyched = 142
+";
+
+// see GFXV_WL6.H (WL3 has the same pics)
+const WL6_TABLE: &str = "
+HelpBJ..HelpCastle = 0
+HelpBlaze = 2
+HelpTopWindow..HelpBottomInfo = 3
+OptionTitle..Episode6 = 7
+FloorEndGuy..StatusFaceGod1 = 40
+Paused..GetPsyched = 130
+";
+
+// see GFXV_SDM.H (the options have no floppy and no episode pics)
+const SDM_TABLE: &str = "
+BackDropScreen = 0
+MoveSelBack = 1
+Cursor1..SelectionOn = 2
+CustomizeTitle = 6
+ControlTitle = 12
+OptionTitle = 13
+SoundFxTitle..MusicTitle = 14
+DifficultyBaby..DifficultyHard = 18
+DiskLoading1..DiskLoading2 = 22
+LoadGameTitle..SaveGameTitle = 24
+HighScoresTitle = 26
+FloorEndGuy..FloorEndWinnerBJ = 28
+TitleScreen = 71
+TitleScreen.lower = 72
+StatusBar = 73
+Pg13Pic..CreditsScreen = 74
+StatusKnife..StatusFaceGod3 = 76
+Paused..GetPsyched = 123
+";
+
+// see GFXV_SOD.H (the title screen is split in TITLE1PIC + TITLE2PIC, and its palette is TITLEPALETTE)
+const SOD_TABLE: &str = "
+BackDropScreen = 0
+MoveSelBack = 1
+Cursor1..SelectionOn = 2
+CustomizeTitle = 6
+ControlTitle = 12
+OptionTitle = 13
+SoundFxTitle..MusicTitle = 14
+DifficultyBaby..DifficultyHard = 18
+DiskLoading1..DiskLoading2 = 22
+LoadGameTitle..SaveGameTitle = 24
+HighScoresTitle = 26
+FloorEndGuy..FloorEndWinnerBJ = 33
+TitleScreen = 79
+TitleScreen.lower = 80
+StatusBar = 87
+Pg13Pic..CreditsScreen = 88
+StatusKnife..StatusFaceGod3 = 98
+Paused..GetPsyched = 145
+TitleScreen.palette = 153
+";
 
 /// Move the pics from the input vector into a new vector,
-/// so that they are orderec exactly like in the `PicType` enum.
-fn into_ordered_pics_vec(game_ext: &str, input: Vec<GfxData>) -> Vec<GfxData> {
-    let mut mapped_vec = vec![GfxData::new_empty(); TOTAL_PICS];
-    // get translation func
-    let transl_func = match game_ext {
-        "WL1" => translate_wolf1,
-        "WL3" => translate_wolf3or6,
-        "WL6" => translate_wolf3or6,
-        "SDM" => translate_sdm,
-        _ => translate_sod,
-    };
-    // map inputs to outputs
-    let mut in_to_out = vec![BAD_IDX; input.len()];
-    for out_idx in 0..TOTAL_PICS {
-        let in_idx = transl_func(out_idx);
-        if in_idx < BAD_IDX {
-            in_to_out[in_idx] = out_idx;
+/// so that they are ordered exactly like in the `PicType` enum.
+fn into_ordered_pics_vec(table: &PicTable, input: Vec<GfxData>) -> Vec<GfxData> {
+    let mut input: Vec<Option<GfxData>> = input.into_iter().map(Some).collect();
+    let mut mapped_vec: Vec<GfxData> = (0..TOTAL_PICS)
+        .map(|idx| {
+            table.indexes[idx]
+                .and_then(|in_idx| input.get_mut(in_idx))
+                .and_then(|pic| pic.take())
+                .unwrap_or_else(GfxData::new_empty)
+        })
+        .collect();
+
+    // put the split pics together (e.g. the SOD title screen)
+    for &(typ, lower_idx) in &table.lower_pics {
+        let lower = input.get_mut(lower_idx).and_then(|pic| pic.take());
+        if let Some(pic) = lower.and_then(|lower| stack_pics(&mapped_vec[typ as usize], &lower)) {
+            mapped_vec[typ as usize] = pic;
         }
     }
-    // convert input vector
-    let mut idx = 0;
-    for pic in input.into_iter() {
-        let out_idx = in_to_out[idx];
-        idx += 1;
-        if out_idx < BAD_IDX {
-            mapped_vec[out_idx] = pic;
+
+    // take care of gaps (e.g. WLx has no backdrop and only one "god mode" face)
+    let is_empty = |pic: &GfxData| pic.size() == (0, 0);
+    let god1 = PicType::StatusFaceGod1 as usize;
+    for god in [PicType::StatusFaceGod2, PicType::StatusFaceGod3] {
+        if is_empty(&mapped_vec[god as usize]) {
+            mapped_vec[god as usize] = mapped_vec[god1].clone();
         }
     }
-    // take care of gaps, in case of WLx
-    let ch = game_ext.bytes().next().unwrap_or(0);
-    if ch == ('W' as u8) {
-        mapped_vec[STATUS_GOD2] = mapped_vec[STATUS_GOD1].clone();
-        mapped_vec[STATUS_GOD3] = mapped_vec[STATUS_GOD1].clone();
-        mapped_vec[BACK_DROP] = convert_w3d_title_screen_to_back_drop(&mapped_vec[TITLE_SCREEN]);
+    let back_drop = PicType::BackDropScreen as usize;
+    let title_screen = PicType::TitleScreen as usize;
+    if is_empty(&mapped_vec[back_drop]) && !is_empty(&mapped_vec[title_screen]) {
+        mapped_vec[back_drop] = convert_w3d_title_screen_to_back_drop(&mapped_vec[title_screen]);
     }
     mapped_vec
 }
 
-// see GFXV_WL1.H
-fn translate_wolf1(enum_idx: usize) -> usize {
-    match enum_idx {
-        OPTION_TITLE..=EPISODE_6 => enum_idx + 18,
-        FLOOR_END_GUY..=STATUS_GOD1 => enum_idx + 52 - FLOOR_END_GUY,
-        PAUSED => 142,
-        GET_PSYCHED => 143,
-        _ => BAD_IDX,
-    }
-}
-
-// see GFXV_WL6.H
-fn translate_wolf3or6(enum_idx: usize) -> usize {
-    match enum_idx {
-        OPTION_TITLE..=EPISODE_6 => enum_idx + 6,
-        FLOOR_END_GUY..=STATUS_GOD1 => enum_idx + 40 - FLOOR_END_GUY,
-        PAUSED => 130,
-        GET_PSYCHED => 131,
-        _ => BAD_IDX,
+/// Put the lower pic under the upper one, if they have the same width.
+fn stack_pics(upper: &GfxData, lower: &GfxData) -> Option<GfxData> {
+    let ((width, upper_height), (lower_width, lower_height)) = (upper.size(), lower.size());
+    if width == 0 || width != lower_width {
+        return None;
     }
-}
-
-fn translate_sdm(enum_idx: usize) -> usize {
-    match enum_idx {
-        FLOOR_END_GUY..=FLOOR_END_WINNER => enum_idx + 28 - FLOOR_END_GUY,
-        STATUS_BAR => 73,
-        TITLE_SCREEN => 71,
-        PG13_PIC => 74,
-        CREDITS_SCREEN => 75,
-        STATUS_KNIFE..=STATUS_GOD3 => enum_idx + 76 - STATUS_KNIFE,
-        PAUSED => 123,
-        GET_PSYCHED => 124,
-        _ => translate_spear_common(enum_idx),
-    }
-}
-
-fn translate_sod(enum_idx: usize) -> usize {
-    match enum_idx {
-        FLOOR_END_GUY..=FLOOR_END_WINNER => enum_idx + 33 - FLOOR_END_GUY,
-        STATUS_BAR => 87,
-        TITLE_SCREEN => 79,
-        PG13_PIC => 88,
-        CREDITS_SCREEN => 89,
-        STATUS_KNIFE..=STATUS_GOD3 => enum_idx + 98 - STATUS_KNIFE,
-        PAUSED => 145,
-        GET_PSYCHED => 146,
-        _ => translate_spear_common(enum_idx),
-    }
-}
-
-// TODO also fix title screen for SOD !!!
-fn translate_spear_common(enum_idx: usize) -> usize {
-    match enum_idx {
-        BACK_DROP => 0,
-        HIGH_SCORES => 26,
-        // TODO FIX THESE !!!
-        // (the option pics are BAD, but I'm too tired to fix them :/ )
-        OPTION_TITLE..=SAVE_TITLE => enum_idx + 1,
-        _ => BAD_IDX,
+    // the pics are stored column by column
+    let (upper_height, lower_height) = (upper_height as usize, lower_height as usize);
+    let mut texels = Vec::with_capacity(width as usize * (upper_height + lower_height));
+    for x in 0..width as usize {
+        texels.extend_from_slice(&upper.texels()[x * upper_height..(x + 1) * upper_height]);
+        texels.extend_from_slice(&lower.texels()[x * lower_height..(x + 1) * lower_height]);
     }
+    Some(GfxData::new_pic(width, (upper_height + lower_height) as u16, texels))
 }
 
 fn convert_w3d_title_screen_to_back_drop(input: &GfxData) -> GfxData {
@@ -307,6 +500,6 @@ fn convert_w3d_title_screen_to_back_drop(input: &GfxData) -> GfxData {
     }
 
     let mut pic = input.clone();
-    pic.grayscale(&grays);
+    pic.grayscale(&Palette::wolf3d(), &grays);
     pic
 }
@@ -1,6 +1,6 @@
 //! Screen bufer - collects what needs to be painted and paints it using the palette.
 
-use crate::{GfxData, Painter, RGB};
+use crate::{ColorMaps, FizzleFade, Fog, GfxData, Painter, Palette, PaletteEffects};
 
 // Special scaler, for correctly rendering walls and sprites in 3D view
 const PIC_HEIGHT_SCALER: f64 = 1.1;
@@ -14,15 +14,22 @@ pub struct ScreenBuffer {
     screen_y_start: i32,
     view_height: i32,
     bytes: Vec<u8>,
-    use_sod_palette: bool,
+    palette: Palette,
     dist_from_screen: f64,
     hfov: f64,
     wall_heights: Vec<i32>,
+    /// The fog of the current level, and its color maps (only when the lighting is enabled)
+    fog: Fog,
+    colormaps: Option<ColorMaps>,
+    /// The palette flashes and fades, applied when painting
+    effects: PaletteEffects,
+    /// The screen transition in progress - while it runs, it is painted instead of the buffer
+    transition: Option<FizzleFade>,
 }
 
 impl ScreenBuffer {
-    /// Create a new screen buffer.
-    pub fn new(scr_width: i32, scr_height: i32, use_sod_palette: bool) -> Self {
+    /// Create a new screen buffer, which paints using the given palette.
+    pub fn new(scr_width: i32, scr_height: i32, palette: Palette) -> Self {
         // adjust width and height, so that it is 4/3
         let scale = if (scr_width * 3) > (scr_height * 4) {
             // too wide => use height as basis
@@ -45,13 +52,90 @@ impl ScreenBuffer {
             screen_y_start,
             view_height: height,
             bytes: vec![0; len],
-            use_sod_palette,
+            palette,
             dist_from_screen,
             hfov,
             wall_heights: vec![0; width as usize],
+            fog: Fog::default(),
+            colormaps: None,
+            effects: PaletteEffects::new(),
+            transition: None,
         }
     }
 
+    /// The palette used for painting.
+    #[inline]
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Switch to another palette (e.g. for showing a pic which has its own palette).
+    #[inline]
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        if self.colormaps.is_some() {
+            self.colormaps = Some(ColorMaps::new(&self.palette, self.fog));
+        }
+    }
+
+    /// Enable/disable the lighting: the 3D view is shaded with the distance, into the fog color.
+    pub fn enable_lighting(&mut self, enabled: bool) {
+        self.colormaps = enabled.then(|| ColorMaps::new(&self.palette, self.fog));
+    }
+
+    #[inline]
+    pub fn lighting_enabled(&self) -> bool {
+        self.colormaps.is_some()
+    }
+
+    /// Set the fog (e.g. of the current level). The color maps are rebuilt only if the fog changes.
+    pub fn set_fog(&mut self, fog: Fog) {
+        if fog != self.fog {
+            self.fog = fog;
+            if self.colormaps.is_some() {
+                self.colormaps = Some(ColorMaps::new(&self.palette, fog));
+            }
+        }
+    }
+
+    /// The palette effects (flashes and fades).
+    #[inline]
+    pub fn effects(&self) -> &PaletteEffects {
+        &self.effects
+    }
+
+    #[inline]
+    pub fn effects_mut(&mut self) -> &mut PaletteEffects {
+        &mut self.effects
+    }
+
+    /// A copy of what is in the buffer now (e.g. the source or target frame of a transition).
+    #[inline]
+    pub fn frame(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    /// Start a FizzleFade transition from `source` to `target` (both frames from `frame`), over `duration` seconds.
+    /// Once the transition is finished, the buffer contains the target frame.
+    pub fn start_transition(&mut self, source: Vec<u8>, target: Vec<u8>, duration: f64) {
+        self.transition = Some(FizzleFade::new(source, target, duration));
+    }
+
+    pub fn update_transition(&mut self, elapsed_time: f64) {
+        if let Some(transition) = &mut self.transition {
+            transition.update(elapsed_time);
+            if transition.is_finished() {
+                self.bytes.copy_from_slice(transition.target());
+                self.transition = None;
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_transition_running(&self) -> bool {
+        self.transition.is_some()
+    }
+
     /// Screen buffer width.
     #[inline]
     pub fn scr_width(&self) -> i32 {
@@ -140,12 +224,28 @@ impl ScreenBuffer {
         }
     } */
 
-    /// Init 3D view - paint sky and floor.
-    pub fn clear_3d_view(&mut self, sky_color: u8) {
-        const FLOOR_COLOR: u8 = 0x19;
+    /// Init 3D view - paint ceiling and floor.
+    /// With lighting, each row is shaded with the distance where a wall would touch it.
+    pub fn clear_3d_view(&mut self, ceiling_color: u8, floor_color: u8) {
         let halfh = self.view_height >> 1;
-        self.fill_rect(0, 0, self.width, halfh, sky_color);
-        self.fill_rect(0, halfh, self.width, halfh, FLOOR_COLOR);
+        let Some(colormaps) = &self.colormaps else {
+            self.fill_rect(0, 0, self.width, halfh, ceiling_color);
+            self.fill_rect(0, halfh, self.width, halfh, floor_color);
+            return;
+        };
+        let mut rows = Vec::with_capacity(self.view_height as usize);
+        for y in 0..self.view_height {
+            let (color, dy) = if y < halfh {
+                (ceiling_color, halfh - y)
+            } else {
+                (floor_color, y - halfh + 1)
+            };
+            let dist = (self.height as f64) * PIC_HEIGHT_SCALER / ((2 * dy) as f64);
+            rows.push(colormaps.shade(color, dist));
+        }
+        for (y, color) in rows.into_iter().enumerate() {
+            self.fill_rect(0, y as i32, self.width, 1, color);
+        }
     }
 
     /// Render one column of a texture, centered vertically and proportionally scaled, in 3D mode.
@@ -167,12 +267,17 @@ impl ScreenBuffer {
         let scaled_height = ((self.height as f64) * height_scale + ADJUST_EPSILON) as i32;
         self.wall_heights[screen_x as usize] = scaled_height;
 
+        let level = self.colormaps.as_ref().map(|colormaps| colormaps.level(dist));
+
         let dystep = 1.0 / (scaled_height as f64);
         let mut dy = 0.0;
         let mut y = (self.view_height - scaled_height) / 2;
         for _ in 0..scaled_height {
             if y >= 0 && y < self.view_height {
-                let texel = texture.texel(tex_x_rel_ofs, dy);
+                let mut texel = texture.texel(tex_x_rel_ofs, dy);
+                if let (Some(colormaps), Some(level)) = (&self.colormaps, level) {
+                    texel = colormaps.map(level)[texel as usize];
+                }
                 self.put_pixel(screen_x, y, texel);
             }
             y += 1;
@@ -210,6 +315,35 @@ impl ScreenBuffer {
 
     /// Draw a picture proportionally scaled, in 2D mode.
     pub fn draw_scaled_pic(&mut self, x: i32, y: i32, scaled_width: i32, scaled_height: i32, sprite: &GfxData) {
+        self.draw_scaled_pic_mapped(x, y, scaled_width, scaled_height, sprite, |c| c);
+    }
+
+    /// Draw a picture which has its own palette (e.g. the SOD title screen), proportionally scaled, in 2D mode.
+    /// The colors of the picture are mapped to the nearest colors of the screen palette.
+    pub fn draw_scaled_pic_with_palette(
+        &mut self,
+        x: i32,
+        y: i32,
+        scaled_width: i32,
+        scaled_height: i32,
+        sprite: &GfxData,
+        pic_palette: &Palette,
+    ) {
+        let color_map: Vec<u8> = (0..=255)
+            .map(|c| self.palette.nearest_color(pic_palette.color(c), None))
+            .collect();
+        self.draw_scaled_pic_mapped(x, y, scaled_width, scaled_height, sprite, |c| color_map[c as usize]);
+    }
+
+    fn draw_scaled_pic_mapped(
+        &mut self,
+        x: i32,
+        y: i32,
+        scaled_width: i32,
+        scaled_height: i32,
+        sprite: &GfxData,
+        map_color: impl Fn(u8) -> u8,
+    ) {
         let spr_size = sprite.size();
         if spr_size.0 <= 0 || spr_size.1 <= 0 {
             // Trying to paint empty sprite
@@ -228,7 +362,7 @@ impl ScreenBuffer {
             if scr_x >= 0 && scr_x < self.width {
                 let mut dy = 0.0;
                 for scr_y in y..y + scaled_height {
-                    self.put_pixel(scr_x, scr_y, sprite.texel(dx, dy));
+                    self.put_pixel(scr_x, scr_y, map_color(sprite.texel(dx, dy)));
                     dy += y_step;
                 }
             }
@@ -246,10 +380,16 @@ impl ScreenBuffer {
 
     /// Paint the buffer onto the screen.
     pub fn paint(&self, painter: &mut dyn Painter) {
+        let shifted = self.effects.apply(&self.palette);
+        let palette = shifted.as_ref().unwrap_or(&self.palette);
+        let bytes = match &self.transition {
+            Some(transition) => transition.frame(),
+            None => &self.bytes,
+        };
         let mut idx = 0;
         for y in 0..(self.height as i32) {
             for x in 0..(self.width as i32) {
-                let color = palette_to_rgb(self.bytes[idx], self.use_sod_palette);
+                let color = palette.color(bytes[idx]);
                 painter.draw_pixel(x + self.screen_x_start, y + self.screen_y_start, color);
                 idx += 1;
             }
@@ -275,26 +415,6 @@ impl ScreenBuffer {
     }
 }
 
-// NOTE: the palettes of Wolf3D and SOD are different for only 2 colors:
-//      166 => RGB(0, 56, 0)
-//      167 => RGB(0, 40, 0)
-#[inline]
-pub fn palette_to_rgb(c: u8, sod: bool) -> RGB {
-    if sod {
-        match c {
-            166 => {
-                return RGB::from(0, 56, 0);
-            }
-            167 => {
-                return RGB::from(0, 40, 0);
-            }
-            _ => {}
-        }
-    }
-    let idx = (c as usize) * 3;
-    RGB::from(PALETTE[idx], PALETTE[idx + 1], PALETTE[idx + 2])
-}
-
 //--------------------------
 //  Internal stuff
 
@@ -308,47 +428,3 @@ fn compute_dist_from_screen_and_hfov(width: i32, height: i32) -> (f64, f64) {
     let hfov = half_width.atan2(dist_from_screen);
     (dist_from_screen, hfov)
 }
-
-const PALETTE: &[u8] = &[
-    0x00, 0x00, 0x00, 0x00, 0x00, 0xA8, 0x00, 0xA8, 0x00, 0x00, 0xA8, 0xA8, 0xA8, 0x00, 0x00, 0xA8, 0x00, 0xA8, 0xA8,
-    0x54, 0x00, 0xA8, 0xA8, 0xA8, 0x54, 0x54, 0x54, 0x54, 0x54, 0xFF, 0x54, 0xFF, 0x54, 0x54, 0xFF, 0xFF, 0xFF, 0x54,
-    0x54, 0xFF, 0x54, 0xFF, 0xFF, 0xFF, 0x54, 0xFF, 0xFF, 0xFF, 0xEC, 0xEC, 0xEC, 0xDC, 0xDC, 0xDC, 0xD0, 0xD0, 0xD0,
-    0xC0, 0xC0, 0xC0, 0xB4, 0xB4, 0xB4, 0xA8, 0xA8, 0xA8, 0x98, 0x98, 0x98, 0x8C, 0x8C, 0x8C, 0x7C, 0x7C, 0x7C, 0x70,
-    0x70, 0x70, 0x64, 0x64, 0x64, 0x54, 0x54, 0x54, 0x48, 0x48, 0x48, 0x38, 0x38, 0x38, 0x2C, 0x2C, 0x2C, 0x20, 0x20,
-    0x20, 0xFF, 0x00, 0x00, 0xEC, 0x00, 0x00, 0xE0, 0x00, 0x00, 0xD4, 0x00, 0x00, 0xC8, 0x00, 0x00, 0xBC, 0x00, 0x00,
-    0xB0, 0x00, 0x00, 0xA4, 0x00, 0x00, 0x98, 0x00, 0x00, 0x88, 0x00, 0x00, 0x7C, 0x00, 0x00, 0x70, 0x00, 0x00, 0x64,
-    0x00, 0x00, 0x58, 0x00, 0x00, 0x4C, 0x00, 0x00, 0x40, 0x00, 0x00, 0xFF, 0xD8, 0xD8, 0xFF, 0xB8, 0xB8, 0xFF, 0x9C,
-    0x9C, 0xFF, 0x7C, 0x7C, 0xFF, 0x5C, 0x5C, 0xFF, 0x40, 0x40, 0xFF, 0x20, 0x20, 0xFF, 0x00, 0x00, 0xFF, 0xA8, 0x5C,
-    0xFF, 0x98, 0x40, 0xFF, 0x88, 0x20, 0xFF, 0x78, 0x00, 0xE4, 0x6C, 0x00, 0xCC, 0x60, 0x00, 0xB4, 0x54, 0x00, 0x9C,
-    0x4C, 0x00, 0xFF, 0xFF, 0xD8, 0xFF, 0xFF, 0xB8, 0xFF, 0xFF, 0x9C, 0xFF, 0xFF, 0x7C, 0xFF, 0xF8, 0x5C, 0xFF, 0xF4,
-    0x40, 0xFF, 0xF4, 0x20, 0xFF, 0xF4, 0x00, 0xE4, 0xD8, 0x00, 0xCC, 0xC4, 0x00, 0xB4, 0xAC, 0x00, 0x9C, 0x9C, 0x00,
-    0x84, 0x84, 0x00, 0x70, 0x6C, 0x00, 0x58, 0x54, 0x00, 0x40, 0x40, 0x00, 0xD0, 0xFF, 0x5C, 0xC4, 0xFF, 0x40, 0xB4,
-    0xFF, 0x20, 0xA0, 0xFF, 0x00, 0x90, 0xE4, 0x00, 0x80, 0xCC, 0x00, 0x74, 0xB4, 0x00, 0x60, 0x9C, 0x00, 0xD8, 0xFF,
-    0xD8, 0xBC, 0xFF, 0xB8, 0x9C, 0xFF, 0x9C, 0x80, 0xFF, 0x7C, 0x60, 0xFF, 0x5C, 0x40, 0xFF, 0x40, 0x20, 0xFF, 0x20,
-    0x00, 0xFF, 0x00, 0x00, 0xFF, 0x00, 0x00, 0xEC, 0x00, 0x00, 0xE0, 0x00, 0x00, 0xD4, 0x00, 0x04, 0xC8, 0x00, 0x04,
-    0xBC, 0x00, 0x04, 0xB0, 0x00, 0x04, 0xA4, 0x00, 0x04, 0x98, 0x00, 0x04, 0x88, 0x00, 0x04, 0x7C, 0x00, 0x04, 0x70,
-    0x00, 0x04, 0x64, 0x00, 0x04, 0x58, 0x00, 0x04, 0x4C, 0x00, 0x04, 0x40, 0x00, 0xD8, 0xFF, 0xFF, 0xB8, 0xFF, 0xFF,
-    0x9C, 0xFF, 0xFF, 0x7C, 0xFF, 0xF8, 0x5C, 0xFF, 0xFF, 0x40, 0xFF, 0xFF, 0x20, 0xFF, 0xFF, 0x00, 0xFF, 0xFF, 0x00,
-    0xE4, 0xE4, 0x00, 0xCC, 0xCC, 0x00, 0xB4, 0xB4, 0x00, 0x9C, 0x9C, 0x00, 0x84, 0x84, 0x00, 0x70, 0x70, 0x00, 0x58,
-    0x58, 0x00, 0x40, 0x40, 0x5C, 0xBC, 0xFF, 0x40, 0xB0, 0xFF, 0x20, 0xA8, 0xFF, 0x00, 0x9C, 0xFF, 0x00, 0x8C, 0xE4,
-    0x00, 0x7C, 0xCC, 0x00, 0x6C, 0xB4, 0x00, 0x5C, 0x9C, 0xD8, 0xD8, 0xFF, 0xB8, 0xBC, 0xFF, 0x9C, 0x9C, 0xFF, 0x7C,
-    0x80, 0xFF, 0x5C, 0x60, 0xFF, 0x40, 0x40, 0xFF, 0x20, 0x24, 0xFF, 0x00, 0x04, 0xFF, 0x00, 0x00, 0xFF, 0x00, 0x00,
-    0xEC, 0x00, 0x00, 0xE0, 0x00, 0x00, 0xD4, 0x00, 0x00, 0xC8, 0x00, 0x00, 0xBC, 0x00, 0x00, 0xB0, 0x00, 0x00, 0xA4,
-    0x00, 0x00, 0x98, 0x00, 0x00, 0x88, 0x00, 0x00, 0x7C, 0x00, 0x00, 0x70, 0x00, 0x00, 0x64, 0x00, 0x00, 0x58, 0x00,
-    0x00, 0x4C, 0x00, 0x00, 0x40, 0x28, 0x28, 0x28, 0xFF, 0xE0, 0x34, 0xFF, 0xD4, 0x24, 0xFF, 0xCC, 0x18, 0xFF, 0xC0,
-    0x08, 0xFF, 0xB4, 0x00, 0xB4, 0x20, 0xFF, 0xA8, 0x00, 0xFF, 0x98, 0x00, 0xE4, 0x80, 0x00, 0xCC, 0x74, 0x00, 0xB4,
-    0x60, 0x00, 0x9C, 0x50, 0x00, 0x84, 0x44, 0x00, 0x70, 0x34, 0x00, 0x58, 0x28, 0x00, 0x40, 0xFF, 0xD8, 0xFF, 0xFF,
-    0xB8, 0xFF, 0xFF, 0x9C, 0xFF, 0xFF, 0x7C, 0xFF, 0xFF, 0x5C, 0xFF, 0xFF, 0x40, 0xFF, 0xFF, 0x20, 0xFF, 0xFF, 0x00,
-    0xFF, 0xE0, 0x00, 0xE4, 0xC8, 0x00, 0xCC, 0xB4, 0x00, 0xB4, 0x9C, 0x00, 0x9C, 0x84, 0x00, 0x84, 0x6C, 0x00, 0x70,
-    0x58, 0x00, 0x58, 0x40, 0x00, 0x40, 0xFF, 0xE8, 0xDC, 0xFF, 0xE0, 0xD0, 0xFF, 0xD8, 0xC4, 0xFF, 0xD4, 0xBC, 0xFF,
-    0xCC, 0xB0, 0xFF, 0xC4, 0xA4, 0xFF, 0xBC, 0x9C, 0xFF, 0xB8, 0x90, 0xFF, 0xB0, 0x80, 0xFF, 0xA4, 0x70, 0xFF, 0x9C,
-    0x60, 0xF0, 0x94, 0x5C, 0xE8, 0x8C, 0x58, 0xDC, 0x88, 0x54, 0xD0, 0x80, 0x50, 0xC8, 0x7C, 0x4C, 0xBC, 0x78, 0x48,
-    0xB4, 0x70, 0x44, 0xA8, 0x68, 0x40, 0xA0, 0x64, 0x3C, 0x9C, 0x60, 0x38, 0x90, 0x5C, 0x34, 0x88, 0x58, 0x30, 0x80,
-    0x50, 0x2C, 0x74, 0x4C, 0x28, 0x6C, 0x48, 0x24, 0x5C, 0x40, 0x20, 0x54, 0x3C, 0x1C, 0x48, 0x38, 0x18, 0x40, 0x30,
-    0x18, 0x38, 0x2C, 0x14, 0x28, 0x20, 0x0C, 0x60, 0x00, 0x64, 0x00, 0x64, 0x64, 0x00, 0x60, 0x60, 0x00, 0x00, 0x1C,
-    0x00, 0x00, 0x2C, 0x30, 0x24, 0x10, 0x48, 0x00, 0x48, 0x50, 0x00, 0x50, 0x00, 0x00, 0x34, 0x1C, 0x1C, 0x1C, 0x4C,
-    0x4C, 0x4C, 0x5C, 0x5C, 0x5C, 0x40, 0x40, 0x40, 0x30, 0x30, 0x30, 0x34, 0x34, 0x34, 0xD8, 0xF4, 0xF4, 0xB8, 0xE8,
-    0xE8, 0x9C, 0xDC, 0xDC, 0x74, 0xC8, 0xC8, 0x48, 0xC0, 0xC0, 0x20, 0xB4, 0xB4, 0x20, 0xB0, 0xB0, 0x00, 0xA4, 0xA4,
-    0x00, 0x98, 0x98, 0x00, 0x8C, 0x8C, 0x00, 0x84, 0x84, 0x00, 0x7C, 0x7C, 0x00, 0x78, 0x78, 0x00, 0x74, 0x74, 0x00,
-    0x70, 0x70, 0x00, 0x6C, 0x6C, 0xFF, 0x00, 0xFF,
-];
@@ -1,5 +1,7 @@
 //! SDL2 wrapper, to simplify using SDL2
 
+use crate::{AudioSink, NullAudioSink, DEFAULT_SAMPLE_RATE};
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
 use sdl2::event::{Event, WindowEvent};
 use sdl2::pixels::PixelFormatEnum;
 use std::time::{Duration, Instant};
@@ -7,7 +9,7 @@ use std::time::{Duration, Instant};
 //-----------------
 
 /// Structure for an RGB color.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct RGB {
     pub r: u8,
     pub g: u8,
@@ -104,6 +106,9 @@ pub trait GraphicsLoop {
     /// Called at the beginning of every graphic loop.
     /// Can be used to e.g. reset some data, like mouse relative motion.
     fn on_start_loop(&mut self) {}
+
+    /// Generate the audio for the elapsed time, and push it into the audio sink.
+    fn play_audio(&mut self, _sink: &mut dyn AudioSink, _elapsed_time: f64) {}
 }
 
 /// Main function to run the continuous game loop.
@@ -134,6 +139,15 @@ pub fn run_game_loop(cfg: &SdlConfiguration, gfx_loop: &mut dyn GraphicsLoop) ->
         .create_texture_streaming(PixelFormatEnum::RGB24, scr_width, scr_height)
         .map_err(|e| e.to_string())?;
 
+    // open the audio device - the game can also run without it
+    let mut audio_sink: Box<dyn AudioSink> = match SdlAudioSink::open(&sdl_context, DEFAULT_SAMPLE_RATE) {
+        Ok(sink) => Box::new(sink),
+        Err(msg) => {
+            println!("[ROLF3D] Audio is not available: {msg}");
+            Box::new(NullAudioSink::new(DEFAULT_SAMPLE_RATE))
+        }
+    };
+
     let mut timer = FpsAndElapsedCounter::new();
     let mut last_fps = 42;
     let mut event_pump = sdl_context.event_pump()?;
@@ -179,6 +193,7 @@ pub fn run_game_loop(cfg: &SdlConfiguration, gfx_loop: &mut dyn GraphicsLoop) ->
         if !gfx_loop.update_state(elapsed_time) {
             break 'running;
         }
+        gfx_loop.play_audio(audio_sink.as_mut(), elapsed_time);
 
         // paint the screen, using a SDL2 streaming texture
         // - see: https://github.com/Rust-SDL2/rust-sdl2/blob/master/examples/renderer-texture.rs
@@ -244,6 +259,40 @@ impl<'a> Painter for InternalTexturePainter<'a> {
 
 //--------------------------
 
+/// Audio sink for the SDL2 audio device, using an audio queue.
+struct SdlAudioSink {
+    queue: AudioQueue<i16>,
+}
+
+impl SdlAudioSink {
+    fn open(sdl_context: &sdl2::Sdl, sample_rate: u32) -> Result<Self, String> {
+        let audio_subsystem = sdl_context.audio()?;
+        let desired_spec = AudioSpecDesired {
+            freq: Some(sample_rate as i32),
+            channels: Some(1),
+            samples: Some(1024),
+        };
+        let queue = audio_subsystem.open_queue::<i16, _>(None, &desired_spec)?;
+        // start with some silence, so that the queue does not run dry between frames
+        let latency = vec![0; (queue.spec().freq / 10) as usize];
+        queue.queue_audio(&latency)?;
+        queue.resume();
+        Ok(Self { queue })
+    }
+}
+
+impl AudioSink for SdlAudioSink {
+    fn sample_rate(&self) -> u32 {
+        self.queue.spec().freq as u32
+    }
+
+    fn queue(&mut self, samples: &[i16]) -> Result<(), String> {
+        self.queue.queue_audio(samples)
+    }
+}
+
+//--------------------------
+
 struct FpsAndElapsedCounter {
     time_sum: f64,
     time_cnt: u32,
@@ -0,0 +1,235 @@
+//! Sound dictionary - the sound effects, and where to find their digitized version.
+//! The PC speaker and AdLib sounds are indexed directly by `SoundEffect`,
+//! but the digitized sounds have their own (game-dependent) indexes.
+
+/// All the sound effects, in the same order as their chunks in AUDIOT.
+/// -> see [soundnames in AUDIOWL6.H](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/AUDIOWL6.H)
+///
+/// **Note:** this is the order for Wolf3D - Spear of Destiny has a different list of sounds.
+/// The sounds after `MissileHit` exist only in Spear of Destiny (they are here for the SOD digitized sounds).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundEffect {
+    HitWall,
+    SelectWeapon,
+    SelectItem,
+    HeartBeat,
+    MoveGun2,
+    MoveGun1,
+    NoWay,
+    NaziHitPlayer,
+    SchabbsThrow,
+    PlayerDeath,
+    DogDeath,
+    AtkGatling,
+    GetKey,
+    NoItem,
+    Walk1,
+    Walk2,
+    TakeDamage,
+    GameOver,
+    OpenDoor,
+    CloseDoor,
+    DoNothing,
+    Halt,
+    DeathScream2,
+    AtkKnife,
+    AtkPistol,
+    DeathScream3,
+    AtkMachineGun,
+    HitEnemy,
+    ShootDoor,
+    DeathScream1,
+    GetMachineGun,
+    GetAmmo,
+    Shoot,
+    Health1,
+    Health2,
+    Bonus1,
+    Bonus2,
+    Bonus3,
+    GetGatling,
+    EscPressed,
+    LevelDone,
+    DogBark,
+    EndBonus1,
+    EndBonus2,
+    Bonus1Up,
+    Bonus4,
+    PushWall,
+    NoBonus,
+    Percent100,
+    BossActive,
+    Mutti,
+    Schutzad,
+    Ahhhg,
+    Die,
+    Eva,
+    GutenTag,
+    Leben,
+    Scheist,
+    NaziFire,
+    BossFire,
+    SsFire,
+    Slurpie,
+    TotHund,
+    MeinGott,
+    SchabbsHa,
+    HitlerHa,
+    Spion,
+    NeinSoVas,
+    DogAttack,
+    FlameThrower,
+    MechStep,
+    Goobs,
+    Yeah,
+    DeathScream4,
+    DeathScream5,
+    DeathScream6,
+    DeathScream7,
+    DeathScream8,
+    DeathScream9,
+    Donner,
+    Eine,
+    Erlauben,
+    Kein,
+    Mein,
+    Rose,
+    MissileFire,
+    MissileHit,
+    // Spear of Destiny only
+    TransSight,
+    TransDeath,
+    WilhelmSight,
+    WilhelmDeath,
+    UberDeath,
+    KnightSight,
+    KnightDeath,
+    AngelSight,
+    AngelDeath,
+    GetSpear,
+}
+
+/// Total number of sound effects (Wolf3D + the ones which exist only in Spear of Destiny).
+pub const NUM_SOUND_EFFECTS: usize = SoundEffect::GetSpear as usize + 1;
+
+/// Get the index of the digitized version of a sound, if it exists for the given game type.
+pub fn digi_sound_index(game_ext: &str, sound: SoundEffect) -> Option<usize> {
+    let digi_map = match game_ext {
+        // the SOD demo has only the first part of the SOD digitized sounds
+        "SDM" => &SOD_DIGI_MAP[..SOD_DEMO_DIGI_SOUNDS],
+        _ if game_ext.starts_with('S') => SOD_DIGI_MAP,
+        _ => WOLF_DIGI_MAP,
+    };
+    digi_map
+        .iter()
+        .find(|(snd, _)| *snd == sound)
+        .map(|(_, digi_idx)| *digi_idx)
+}
+
+//--------------------------------
+//  Internal stuff
+
+/// Which digitized sound to play for each sound effect.
+/// -> see [wolfdigimap in WL_MAIN.C](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/WL_MAIN.C)
+const WOLF_DIGI_MAP: &[(SoundEffect, usize)] = &[
+    // these first sounds are in the shareware version
+    (SoundEffect::Halt, 0),
+    (SoundEffect::DogBark, 1),
+    (SoundEffect::CloseDoor, 2),
+    (SoundEffect::OpenDoor, 3),
+    (SoundEffect::AtkMachineGun, 4),
+    (SoundEffect::AtkPistol, 5),
+    (SoundEffect::AtkGatling, 6),
+    (SoundEffect::Schutzad, 7),
+    (SoundEffect::GutenTag, 8),
+    (SoundEffect::Mutti, 9),
+    (SoundEffect::BossFire, 10),
+    (SoundEffect::SsFire, 11),
+    (SoundEffect::DeathScream1, 12),
+    (SoundEffect::DeathScream2, 13),
+    (SoundEffect::DeathScream3, 13),
+    (SoundEffect::TakeDamage, 14),
+    (SoundEffect::PushWall, 15),
+    (SoundEffect::Leben, 20),
+    (SoundEffect::NaziFire, 21),
+    (SoundEffect::Slurpie, 22),
+    (SoundEffect::Yeah, 32),
+    // these are in all other episodes
+    (SoundEffect::DogDeath, 16),
+    (SoundEffect::Ahhhg, 17),
+    (SoundEffect::Die, 18),
+    (SoundEffect::Eva, 19),
+    (SoundEffect::TotHund, 23),
+    (SoundEffect::MeinGott, 24),
+    (SoundEffect::SchabbsHa, 25),
+    (SoundEffect::HitlerHa, 26),
+    (SoundEffect::Spion, 27),
+    (SoundEffect::NeinSoVas, 28),
+    (SoundEffect::DogAttack, 29),
+    (SoundEffect::LevelDone, 30),
+    (SoundEffect::MechStep, 31),
+    (SoundEffect::Scheist, 33),
+    (SoundEffect::DeathScream4, 34),
+    (SoundEffect::DeathScream5, 35),
+    (SoundEffect::Donner, 36),
+    (SoundEffect::Eine, 37),
+    (SoundEffect::Erlauben, 38),
+    (SoundEffect::DeathScream6, 39),
+    (SoundEffect::DeathScream7, 40),
+    (SoundEffect::DeathScream8, 41),
+    (SoundEffect::DeathScream9, 42),
+    (SoundEffect::Kein, 43),
+    (SoundEffect::Mein, 44),
+    (SoundEffect::Rose, 45),
+];
+
+/// Which digitized sound to play for each sound effect, in Spear of Destiny.
+/// -> see [sodigimap in WL_MAIN.C](https://github.com/id-Software/wolf3d/blob/master/WOLFSRC/WL_MAIN.C)
+const SOD_DIGI_MAP: &[(SoundEffect, usize)] = &[
+    // these first sounds are also in the SOD demo
+    (SoundEffect::Halt, 0),
+    (SoundEffect::CloseDoor, 2),
+    (SoundEffect::OpenDoor, 3),
+    (SoundEffect::AtkMachineGun, 4),
+    (SoundEffect::AtkPistol, 5),
+    (SoundEffect::AtkGatling, 6),
+    (SoundEffect::Schutzad, 7),
+    (SoundEffect::BossFire, 8),
+    (SoundEffect::SsFire, 9),
+    (SoundEffect::DeathScream1, 10),
+    (SoundEffect::DeathScream2, 11),
+    (SoundEffect::TakeDamage, 12),
+    (SoundEffect::PushWall, 13),
+    (SoundEffect::Ahhhg, 15),
+    (SoundEffect::Leben, 16),
+    (SoundEffect::NaziFire, 17),
+    (SoundEffect::Slurpie, 18),
+    (SoundEffect::LevelDone, 22),
+    (SoundEffect::DeathScream4, 23),
+    (SoundEffect::DeathScream3, 23),
+    (SoundEffect::DeathScream5, 24),
+    (SoundEffect::DeathScream6, 25),
+    (SoundEffect::DeathScream7, 26),
+    (SoundEffect::DeathScream8, 27),
+    (SoundEffect::DeathScream9, 28),
+    (SoundEffect::GetGatling, 38),
+    // these are only in the full game
+    (SoundEffect::DogBark, 1),
+    (SoundEffect::DogDeath, 14),
+    (SoundEffect::Spion, 19),
+    (SoundEffect::NeinSoVas, 20),
+    (SoundEffect::DogAttack, 21),
+    (SoundEffect::TransSight, 29),
+    (SoundEffect::TransDeath, 30),
+    (SoundEffect::WilhelmSight, 31),
+    (SoundEffect::WilhelmDeath, 32),
+    (SoundEffect::UberDeath, 33),
+    (SoundEffect::KnightSight, 34),
+    (SoundEffect::KnightDeath, 35),
+    (SoundEffect::AngelSight, 36),
+    (SoundEffect::AngelDeath, 37),
+    (SoundEffect::GetSpear, 39),
+];
+
+/// How many entries of `SOD_DIGI_MAP` are in the SOD demo (the rest are `#ifndef SPEARDEMO`).
+const SOD_DEMO_DIGI_SOUNDS: usize = 26;
@@ -422,13 +422,16 @@ fn _temp_slideshow(assets: &GameAssets, scrbuf: &mut ScreenBuffer, y: i32, w: i3
     let piclen = PicDict::pic_count();
     let picidx = tidx % PicDict::pic_count();
     let pic = assets.pics.pic_by_index(picidx);
+    let picenum = PicType::from_index(picidx).unwrap();
     let (pic_w, pic_h) = pic.size();
     let scaled_w = Ord::min(pic_w as i32, 128);
     let scaled_h = (pic_h as i32) * scaled_w / (pic_w as i32);
-    scrbuf.draw_scaled_pic(w - 320, y + 20, scaled_w, scaled_h, pic);
-    // let picenum = PicType::from_repr(picidx).unwrap();
-    // let str = format!("{picenum} {picidx}/{piclen}");
-    let str = format!("PIC {picidx}/{piclen}");
+    // the pics which have their own palette (e.g. the SOD title screen) are shown with it
+    match assets.pics.pic_palette(picenum) {
+        Some(palette) => scrbuf.draw_scaled_pic_with_palette(w - 320, y + 20, scaled_w, scaled_h, pic, palette),
+        None => scrbuf.draw_scaled_pic(w - 320, y + 20, scaled_w, scaled_h, pic),
+    }
+    let str = format!("{picenum} {picidx}/{piclen}");
     assets.font1.draw_text(w - 320, y + 6, &str, 14, scrbuf);
 }
 
@@ -436,7 +439,7 @@ fn _temp_paint_pic(
     x: i32,
     y: i32,
     tidx: usize,
-    gfx: &[GfxData],
+    gfx: &GfxPages,
     msg: &str,
     assets: &GameAssets,
     scrbuf: &mut ScreenBuffer,
@@ -1,6 +1,10 @@
 //!  Various utilities
 
-use std::{fs::File, io::Read};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 #[inline]
 pub fn buf_to_u16(buf: &[u8]) -> u16 {
@@ -41,11 +45,38 @@ pub fn file_exist(filename: &str) -> bool {
     read_result.is_ok()
 }
 
-/// Read an entire binary file into a vector of bytes.
-pub fn read_file_to_bytes(filename: &str, outbuf: &mut [u8]) -> Result<usize, String> {
-    let mut f = File::open(&filename).map_err(|_| format!("File not found: {filename}"))?;
-    let metadata = std::fs::metadata(&filename).map_err(|_| format!("Cannot read file metadata: {filename}"))?;
-    let len = metadata.len() as usize;
-    f.read(outbuf).map_err(|_| format!("Cannot read file: {filename}"))?;
-    Ok(len)
+/// Find a file inside a directory, ignoring the case of the file name (and of its subdirectories, if the name
+/// has some - e.g. `mods/VSWAP.WL1`). An exact match is preferred, if it exists (e.g. `VSWAP.WL1` vs `vswap.wl1`).
+pub fn find_file_ignore_case(dir: &Path, filename: &str) -> Option<PathBuf> {
+    let exact = dir.join(filename);
+    if exact.is_file() {
+        return Some(exact);
+    }
+    // no exact match => scan the directories, one name part at a time
+    let mut path = dir.to_path_buf();
+    let mut parts = filename.split('/').filter(|part| !part.is_empty()).peekable();
+    while let Some(part) = parts.next() {
+        let is_last = parts.peek().is_none();
+        let entries = std::fs::read_dir(&path).ok()?;
+        let found = entries.filter_map(|entry| entry.ok()).find(|entry| {
+            let name_matches = entry.file_name().to_str().is_some_and(|n| n.eq_ignore_ascii_case(part));
+            let path = entry.path();
+            name_matches && if is_last { path.is_file() } else { path.is_dir() }
+        })?;
+        path = found.path();
+    }
+    path.is_file().then_some(path)
+}
+
+/// Compute the CRC-32 of a buffer (the same one as in ZIP, PNG, `crc32` etc.)
+pub fn crc32(buf: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF_u32;
+    for &b in buf {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
 }
@@ -0,0 +1,187 @@
+//! Article tests: parsing the markup, laying out the pages, and the help/end texts of the shareware version.
+//! The WL1 tests need the shareware files (see `common::find_data_dir`) - without them, they are skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::rc::Rc;
+
+#[test]
+fn parse_markup() {
+    let text = b"^P\r\n^C0AHello  world\n^G16,8,3\n^;comment ^P\n^L40,20\n^B16,8,32,10\n^T50,160,4,35\n\tx ^> y\n^E";
+    let article = Article::parse(text).unwrap();
+    assert_eq!(article.page_count(), 1);
+    assert_eq!(
+        article.page(0),
+        &[
+            ArticleElem::Color(0x0A),
+            word("Hello", 2),
+            word("world", 0),
+            ArticleElem::NewLine,
+            ArticleElem::Pic { x: 8, y: 16, chunk: 3 },
+            ArticleElem::Locate { x: 20, y: 40 },
+            ArticleElem::Bar {
+                x: 8,
+                y: 16,
+                width: 32,
+                height: 10
+            },
+            ArticleElem::TimedPic {
+                x: 160,
+                y: 50,
+                chunk: 4,
+                tics: 35
+            },
+            ArticleElem::Tab,
+            word("x", 1),
+            ArticleElem::Center,
+            word("y", 0),
+            ArticleElem::NewLine,
+        ]
+    );
+}
+
+#[test]
+fn parse_pages_and_errors() {
+    let article = Article::parse(b"^P\nOne\n^P  ignored\nTwo ^cZZ^E\n^PThree").unwrap();
+    assert_eq!(article.page_count(), 2);
+    assert_eq!(article.page(0), &[word("One", 0), ArticleElem::NewLine]);
+    // invalid hex digits count as 0
+    assert_eq!(article.page(1), &[word("Two", 1), ArticleElem::Color(0)]);
+
+    // unknown commands are printed as words, with the caret
+    let article = Article::parse(b"^P\nA ^Xyz  b ^E").unwrap();
+    assert_eq!(article.page(0), &[word("A", 1), word("^Xyz", 2), word("b", 1)]);
+
+    assert!(Article::is_article(b"\r\n^p\n"));
+    assert!(!Article::is_article(b"Hello"));
+    assert!(Article::parse(b"No page start").is_err());
+    assert!(Article::parse(format!("^P\n{}", "x".repeat(100)).as_bytes()).is_err());
+    assert!(Article::parse(b"^P\n^G10,10\n").is_err());
+}
+
+#[test]
+fn layout_wraps_words_and_pics_push_margins() {
+    let font = test_font();
+    // 288px between the margins => 24 words of 5px + a 7px space fit in a row
+    let text = format!("^P\n{}", "a ".repeat(50));
+    let article = Article::parse(text.as_bytes()).unwrap();
+    let layout = article.layout_page(0, &font, |_| None);
+    let positions = text_positions(&layout);
+    assert_eq!(positions.len(), 50);
+    assert_eq!(positions[0], (16, 16));
+    assert_eq!(positions[23], (16 + 23 * 12, 16));
+    assert_eq!(positions[24], (16, 26));
+
+    // a pic on the left pushes the left margin of the rows next to it
+    let article = Article::parse(b"^P\n^G16,16,3\nab\ncd\n\n\nef").unwrap();
+    let layout = article.layout_page(0, &font, |chunk| (chunk == 3).then_some((64, 20)));
+    assert_eq!(layout[0], PageItem::Pic { x: 16, y: 16, chunk: 3 });
+    assert_eq!(text_positions(&layout)[0..3], [(88, 16), (88, 26), (16, 56)]);
+    // the pic is moved to a byte boundary, and so is the margin next to it
+    let article = Article::parse(b"^P\n^G16,20,3\nab").unwrap();
+    let layout = article.layout_page(0, &font, |chunk| (chunk == 3).then_some((64, 20)));
+    assert_eq!(layout[0], PageItem::Pic { x: 16, y: 16, chunk: 3 });
+    assert_eq!(text_positions(&layout)[0], (88, 16));
+
+    // the text which does not fit on the page is dropped, but the page number is always there
+    let article = Article::parse(format!("^P\n{}", "a\n".repeat(30)).as_bytes()).unwrap();
+    let layout = article.layout_page(0, &font, |_| None);
+    assert_eq!(text_positions(&layout).len(), 15);
+    let Some(PageItem::Text { text, .. }) = layout.last() else {
+        panic!("no page number");
+    };
+    assert_eq!(text, "pg 1 of 1");
+}
+
+#[test]
+fn help_and_end_articles_wl1() {
+    let Some(dir) = find_data_dir("VGAGRAPH.WL1") else {
+        println!("VGAGRAPH.WL1 not found => skipping the article test");
+        return;
+    };
+    let assets = Rc::new(GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets"));
+    let help = assets.help_article.as_ref().expect("no help article");
+    assert_eq!(help.page_count(), 41);
+    assert_eq!(assets.end_articles.len(), 1);
+    assert_eq!(assets.end_articles[0].page_count(), 2);
+
+    // the 3rd help page has the guard pic on the left, and the text next to it
+    let pic_size = |chunk: usize| {
+        assets
+            .pics
+            .pic_by_file_index(chunk - FIRST_PIC_CHUNK)
+            .map(|pic| pic.size())
+    };
+    let layout = help.layout_page(2, &assets.font1, pic_size);
+    let pics = layout.iter().filter_map(|item| match item {
+        PageItem::Pic { x, y, chunk } => Some((*x, *y, *chunk)),
+        _ => None,
+    });
+    let (x, y, chunk) = pics.min_by_key(|(x, _, _)| *x).expect("no pic on the help page");
+    let (pic_width, _) = pic_size(chunk).unwrap();
+    assert!(text_positions(&layout)
+        .iter()
+        .filter(|(_, ty)| *ty < 100)
+        .all(|(tx, _)| *tx >= x + pic_width as i32));
+
+    let page = render_article_page(&layout, &assets, 0.0);
+    assert_eq!(page.size(), (320, 200));
+    let pic = assets.pics.pic_by_file_index(chunk - FIRST_PIC_CHUNK).unwrap();
+    assert_eq!(page.texels()[(x * 200 + y) as usize], pic.texels()[0]);
+}
+
+#[test]
+fn article_viewer_wl1() {
+    let Some(dir) = find_data_dir("VGAGRAPH.WL1") else {
+        println!("VGAGRAPH.WL1 not found => skipping the article viewer test");
+        return;
+    };
+    let assets = Rc::new(GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets"));
+    let mut viewer = ArticleViewer::new(Rc::clone(&assets));
+    assert!(viewer.has_help());
+    assert!(!viewer.is_open());
+
+    // the shareware has only the end text of the first episode
+    assert!(!viewer.show_episode_end(1));
+    assert!(!viewer.is_open());
+    assert!(viewer.show_episode_end(0));
+    assert!(viewer.is_open());
+
+    // the end text is painted over the whole screen, with the window frame
+    let mut scrbuf = ScreenBuffer::new(320, 200, Palette::wolf3d());
+    viewer.paint(&mut scrbuf);
+    let frame = scrbuf.frame();
+    assert!(frame.iter().any(|c| *c != ARTICLE_BACK_COLOR));
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+fn word(text: &str, spaces: usize) -> ArticleElem {
+    ArticleElem::Word {
+        text: text.to_string(),
+        spaces,
+    }
+}
+
+/// A font with 95 chars, all 5x10 and fully set.
+fn test_font() -> FontData {
+    let offs_widths = (0..95).flat_map(|idx| [idx * 50, 5]).collect();
+    FontData::new(10, 4, offs_widths, vec![1; 95 * 50])
+}
+
+/// The positions of the text items, without the page number.
+fn text_positions(layout: &[PageItem]) -> Vec<(i32, i32)> {
+    let mut positions: Vec<(i32, i32)> = layout
+        .iter()
+        .filter_map(|item| match item {
+            PageItem::Text { x, y, .. } => Some((*x, *y)),
+            _ => None,
+        })
+        .collect();
+    positions.pop();
+    positions
+}
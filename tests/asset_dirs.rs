@@ -0,0 +1,53 @@
+//! Asset directory tests: the file names are matched ignoring their case, and an explicit directory is the only
+//! one searched. The first test needs the shareware files (see `common::find_data_dir`), otherwise it is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+
+const WL1_FILES: &[&str] = &[
+    "MAPHEAD", "GAMEMAPS", "VGADICT", "VGAHEAD", "VGAGRAPH", "VSWAP", "AUDIOHED", "AUDIOT",
+];
+
+#[test]
+fn file_names_ignore_case() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the case-insensitive loading test");
+        return;
+    };
+    // copy the game files with lowercase (and mixed case) names
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-case-{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    for (idx, name) in WL1_FILES.iter().enumerate() {
+        let path = find_file_ignore_case(&dir, &format!("{name}.WL1")).unwrap();
+        let new_name = if idx % 2 == 0 {
+            format!("{name}.wl1").to_lowercase()
+        } else {
+            format!("{}{}.Wl1", &name[..1], name[1..].to_lowercase())
+        };
+        std::fs::copy(path, outdir.join(new_name)).unwrap();
+    }
+
+    let found = find_file_ignore_case(&outdir, "VSWAP.WL1");
+    let assets = GameAssets::load_from(outdir.to_str().unwrap());
+    std::fs::remove_dir_all(&outdir).unwrap();
+
+    assert_eq!(found.unwrap().file_name().unwrap(), "Vswap.Wl1");
+    let assets = assets.expect("failed to load the lowercase WL1 files");
+    assert_eq!(assets.game_type, "WL1");
+    assert!(!assets.maps.is_empty());
+}
+
+#[test]
+fn explicit_dir_has_no_fallback() {
+    // the game files may be in the current directory, but a wrong path must not load them
+    let missing = std::env::temp_dir().join(format!("rolf3d-test-missing-{}", std::process::id()));
+    let missing = missing.to_str().unwrap();
+    match GameAssets::load_from(missing) {
+        Err(AssetError::FilesNotFound { dirs }) => assert_eq!(dirs, missing),
+        Err(err) => panic!("unexpected error: {err}"),
+        Ok(_) => panic!("loaded the assets from another directory"),
+    }
+    assert!(GameAssets::load_with_mods(missing, &[]).is_err());
+}
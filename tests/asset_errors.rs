@@ -0,0 +1,122 @@
+//! Asset error tests: corrupt game files are reported as `AssetError`s, which say which file, which chunk
+//! and which offset failed - never as panics. The corrupt files are modified copies of the shareware files
+//! (see `common::find_data_dir`) - without them, the tests are skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::path::{Path, PathBuf};
+
+#[test]
+fn corrupt_vgadict() {
+    let Some(dir) = copy_wl1_files("vgadict") else {
+        return;
+    };
+    std::fs::write(dir.join("VGADICT.WL1"), [0; 1000]).unwrap();
+    let result = GameAssets::load_from(dir.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_bad_data(result.err(), "VGADICT.WL1", None, 0);
+}
+
+#[test]
+fn corrupt_fonts() {
+    let Some(dir) = copy_wl1_files("font") else {
+        return;
+    };
+    // the first character of the font is past the end of the font chunk
+    const FONT_HEADER_LEN: usize = 2 + 512 + 256;
+    let mut bad_font = vec![0; FONT_HEADER_LEN];
+    bad_font[0] = 10;
+    bad_font[2 + 2 * 33..4 + 2 * 33].copy_from_slice(&(FONT_HEADER_LEN as u16 + 5).to_le_bytes());
+    bad_font[2 + 512 + 33] = 4;
+    // a tall font is fine
+    let offs_widths = (0..95).flat_map(|idx| [idx * 30 * 8, 8]).collect();
+    let tall_font = FontData::new(30, 4, offs_widths, vec![1; 95 * 30 * 8]);
+
+    let chunk_offsets = write_vga_files(&dir, &[vec![], encode_font(&tall_font), bad_font]);
+    let result = VgaGraph::load_from(dir.to_str().unwrap());
+    let err = result.err();
+    assert_bad_data(err, "VGAGRAPH.WL1", Some(2), chunk_offsets[2] + FONT_HEADER_LEN + 5);
+
+    let tall_font = encode_font(&tall_font);
+    write_vga_files(&dir, &[vec![], tall_font.clone(), tall_font]);
+    let result = VgaGraph::load_from(dir.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+    let vga = result.expect("failed to load the tall fonts");
+    assert_eq!(vga.font1.font_height(), 30);
+    assert_eq!(vga.font2.glyph(b'A').unwrap().0, 8);
+}
+
+#[test]
+fn corrupt_map() {
+    let Some(dir) = copy_wl1_files("map") else {
+        return;
+    };
+    // the header of the 3rd map is past the end of GAMEMAPS
+    let gamemaps_len = std::fs::metadata(dir.join("GAMEMAPS.WL1")).unwrap().len() as usize;
+    let mut maphead = std::fs::read(dir.join("MAPHEAD.WL1")).unwrap();
+    maphead[10..14].copy_from_slice(&(gamemaps_len as u32 - 10).to_le_bytes());
+    std::fs::write(dir.join("MAPHEAD.WL1"), maphead).unwrap();
+    let result = GameAssets::load_from(dir.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_bad_data(result.err(), "GAMEMAPS.WL1", Some(2), gamemaps_len - 10);
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// Copy the shareware files into a new temp directory.
+fn copy_wl1_files(test_name: &str) -> Option<PathBuf> {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the asset error test");
+        return None;
+    };
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-errors-{test_name}-{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    for name in [
+        "MAPHEAD", "GAMEMAPS", "VGADICT", "VGAHEAD", "VGAGRAPH", "VSWAP", "AUDIOHED", "AUDIOT",
+    ] {
+        let filename = format!("{name}.WL1");
+        std::fs::copy(find_file_ignore_case(&dir, &filename).unwrap(), outdir.join(filename)).unwrap();
+    }
+    Some(outdir)
+}
+
+/// Write the given raw chunks as VGADICT, VGAHEAD and VGAGRAPH. Returns the offset of each chunk in VGAGRAPH.
+fn write_vga_files(dir: &Path, chunks: &[Vec<u8>]) -> Vec<usize> {
+    let refs: Vec<&[u8]> = chunks.iter().map(|c| c.as_slice()).collect();
+    let dict = huff_build_dict(&refs);
+    let (mut vgahead, mut vgagraph, mut offsets) = (vec![], vec![], vec![]);
+    for chunk in chunks {
+        offsets.push(vgagraph.len());
+        vgahead.extend_from_slice(&(vgagraph.len() as u32).to_le_bytes()[0..3]);
+        vgagraph.extend(huff_encode_chunk(chunk, &dict).unwrap());
+    }
+    vgahead.extend_from_slice(&(vgagraph.len() as u32).to_le_bytes()[0..3]);
+    let vgadict: Vec<u8> = dict.iter().flat_map(|w| w.to_le_bytes()).collect();
+    std::fs::write(dir.join("VGADICT.WL1"), vgadict).unwrap();
+    std::fs::write(dir.join("VGAHEAD.WL1"), vgahead).unwrap();
+    std::fs::write(dir.join("VGAGRAPH.WL1"), vgagraph).unwrap();
+    offsets
+}
+
+fn assert_bad_data(err: Option<AssetError>, file_name: &str, chunk: Option<usize>, offset: usize) {
+    match err {
+        Some(AssetError::BadData {
+            file,
+            chunk: err_chunk,
+            offset: err_offset,
+            reason,
+        }) => {
+            assert!(
+                file.ends_with(file_name),
+                "error in {file}, instead of {file_name}: {reason}"
+            );
+            assert_eq!((err_chunk, err_offset), (chunk, offset), "{reason}");
+        }
+        Some(err) => panic!("unexpected error: {err}"),
+        None => panic!("the corrupt {file_name} was loaded"),
+    }
+}
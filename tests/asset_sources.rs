@@ -0,0 +1,78 @@
+//! Asset source tests: load the game with mods layered over it (from memory and from a ZIP archive).
+//! Needs the shareware files (see `common::find_data_dir`) - without them, the tests are skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::io::Write;
+
+#[test]
+fn memory_layer_overrides_the_maps() {
+    let Some(dir) = find_data_dir("GAMEMAPS.WL1") else {
+        println!("GAMEMAPS.WL1 not found => skipping the memory source test");
+        return;
+    };
+    let base = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+
+    // a "level pack" with only one map, which has a different name
+    let map = &base.maps[1];
+    let level = MapData::new(
+        "MODDED".to_string(),
+        map.width,
+        map.height,
+        map.tiles().to_vec(),
+        map.things().to_vec(),
+    )
+    .unwrap();
+    let (maphead, gamemaps) = encode_maps(&[level], DEFAULT_RLEW_TAG).unwrap();
+    let mut levels = MemorySource::new("levels");
+    levels.insert("maphead.wl1", maphead);
+    levels.insert("GAMEMAPS.WL1", gamemaps);
+    assert!(levels.contains("MapHead.Wl1"));
+    assert!(!levels.contains("VSWAP.WL1"));
+
+    let mut source = LayeredSource::new();
+    source.push(Box::new(DirSource::new(&dir)));
+    source.push(Box::new(levels));
+    let modded = GameAssets::load_from_source(&source).expect("failed to load the modded assets");
+    assert_eq!(modded.maps.len(), 1);
+    assert_eq!(modded.maps[0].name, "MODDED");
+    assert_eq!(modded.maps[0].tiles(), base.maps[1].tiles());
+    // everything else comes from the base game
+    assert_eq!(modded.walls.len(), base.walls.len());
+    assert_eq!(modded.sprites[10].texels(), base.sprites[10].texels());
+}
+
+#[test]
+fn zip_layer_replaces_a_wall() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the ZIP source test");
+        return;
+    };
+    let base = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+
+    // a PK3 with wall #3 replaced by wall #7 (in a subdirectory, with a different case)
+    let mut png = vec![];
+    encode_png(&mut png, &base.walls[7], &base.palette, false).unwrap();
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-zip-{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    let pk3_path = outdir.join("mod.pk3");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&pk3_path).unwrap());
+    zip.start_file("Walls/WALL_003.png", zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(&png).unwrap();
+    zip.finish().unwrap();
+
+    let pk3 = open_asset_source(&pk3_path).unwrap();
+    assert!(pk3.contains("walls/wall_003.png"));
+    assert_eq!(pk3.read("walls/wall_003.png").unwrap(), png);
+
+    let modded = GameAssets::load_with_mods(dir.to_str().unwrap(), &[pk3_path.to_str().unwrap()])
+        .expect("failed to load the modded assets");
+    // the palette may contain the same color twice => compare the colors, not the indexes
+    let colors = |gfx: &GfxData| -> Vec<RGB> { gfx.texels().iter().map(|&c| base.palette.color(c)).collect() };
+    assert_eq!(colors(&modded.walls[3]), colors(&base.walls[7]));
+    assert_eq!(modded.walls[4].texels(), base.walls[4].texels());
+    std::fs::remove_dir_all(outdir).unwrap();
+}
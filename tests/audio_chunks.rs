@@ -0,0 +1,117 @@
+//! Audio loader tests: split the shareware AUDIOT into PC speaker sounds, AdLib sounds and music,
+//! and report corrupt audio headers as errors. The malformed AUDIOHED files are layered over the
+//! shareware files (see `common::find_data_dir`) - without them, the tests are skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::path::PathBuf;
+
+#[test]
+fn load_audio_wl1() {
+    let Some(dir) = wl1_dir() else {
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+    assert_eq!(assets.pc_sounds.len(), 87);
+    assert_eq!(assets.adlib_sounds.len(), 87);
+    assert_eq!(assets.music.len(), 27);
+
+    // (sound, priority, PC length, AdLib length)
+    let sounds = [
+        (SoundEffect::HitWall, 1, 8, 8),
+        (SoundEffect::NoWay, 1, 36, 36),
+        (SoundEffect::GetAmmo, 80, 63, 63),
+        (SoundEffect::OpenDoor, 20, 34, 101),
+    ];
+    for (snd, priority, pc_len, adlib_len) in sounds {
+        let (pc, adlib) = (&assets.pc_sounds[snd as usize], &assets.adlib_sounds[snd as usize]);
+        assert_eq!((pc.priority, pc.len()), (priority, pc_len), "PC {snd:?}");
+        assert_eq!((adlib.priority, adlib.len()), (priority, adlib_len), "AdLib {snd:?}");
+        assert_eq!(adlib.block, 4, "AdLib {snd:?}");
+    }
+    assert!(assets.pc_sounds.iter().all(|snd| !snd.is_empty()));
+    assert!(assets.adlib_sounds.iter().all(|snd| !snd.is_empty()));
+
+    // the shareware episode only has some of the songs
+    let music_lengths: Vec<usize> = assets.music.iter().map(|mus| mus.len()).collect();
+    assert_eq!(
+        music_lengths,
+        [
+            1864, 0, 3736, 4661, 0, 0, 0, 2107, 0, 1769, 0, 3222, 2555, 0, 2084, 0, 1732, 0, 0, 0, 0, 0, 0, 2118, 1756,
+            0, 0
+        ]
+    );
+}
+
+#[test]
+fn malformed_audio_headers() {
+    let Some(dir) = wl1_dir() else {
+        return;
+    };
+    let audiohed = std::fs::read(dir.join("AUDIOHED.WL1")).unwrap();
+    let audiot_len = std::fs::metadata(dir.join("AUDIOT.WL1")).unwrap().len() as usize;
+    let offset_at = |idx: usize| u32::from_le_bytes(audiohed[4 * idx..4 * idx + 4].try_into().unwrap()) as usize;
+
+    // too short for even one chunk
+    let err = load_with_audiohed(&dir, audiohed[..6].to_vec());
+    assert_bad_data(err, "AUDIOHED.WL1", None, 0);
+
+    // truncated: the music and part of the digitized group are missing
+    let err = load_with_audiohed(&dir, audiohed[..4 * 200].to_vec());
+    assert_bad_data(err, "AUDIOHED.WL1", None, 0);
+
+    // the end of chunk 4 is past the end of AUDIOT
+    let mut bad = audiohed.clone();
+    bad[20..24].copy_from_slice(&(audiot_len as u32 + 100).to_le_bytes());
+    let err = load_with_audiohed(&dir, bad);
+    assert_bad_data(err, "AUDIOT.WL1", Some(4), offset_at(4));
+
+    // chunk 9 ends before it starts
+    let mut bad = audiohed.clone();
+    bad[40..44].copy_from_slice(&(offset_at(9) as u32 - 1).to_le_bytes());
+    let err = load_with_audiohed(&dir, bad);
+    assert_bad_data(err, "AUDIOHED.WL1", Some(9), 40);
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+fn wl1_dir() -> Option<PathBuf> {
+    let dir = find_data_dir("AUDIOT.WL1");
+    if dir.is_none() {
+        println!("AUDIOT.WL1 not found => skipping the audio loader test");
+    }
+    dir
+}
+
+/// Load the shareware files, with another AUDIOHED.
+fn load_with_audiohed(dir: &PathBuf, audiohed: Vec<u8>) -> Option<AssetError> {
+    let mut header = MemorySource::new("audiohed");
+    header.insert("AUDIOHED.WL1", audiohed);
+    let mut source = LayeredSource::new();
+    source.push(Box::new(DirSource::new(dir)));
+    source.push(Box::new(header));
+    GameAssets::load_from_source(&source).err()
+}
+
+fn assert_bad_data(err: Option<AssetError>, file_name: &str, chunk: Option<usize>, offset: usize) {
+    match err {
+        Some(AssetError::BadData {
+            file,
+            chunk: err_chunk,
+            offset: err_offset,
+            reason,
+        }) => {
+            assert!(
+                file.ends_with(file_name),
+                "error in {file}, instead of {file_name}: {reason}"
+            );
+            assert_eq!((err_chunk, err_offset), (chunk, offset), "{reason}");
+        }
+        Some(err) => panic!("unexpected error: {err}"),
+        None => panic!("the corrupt {file_name} was loaded"),
+    }
+}
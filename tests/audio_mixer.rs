@@ -0,0 +1,72 @@
+//! Audio tests: mixing sounds into a WAV file, and playing the game audio into a sink with another sample rate.
+//! The last test needs the shareware files (see `common::find_data_dir`) - without them, it is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::rc::Rc;
+
+#[test]
+fn mix_sounds_into_wav() {
+    let path = std::env::temp_dir().join(format!("rolf3d-test-mix-{}.wav", std::process::id()));
+    let mut mixer = AudioMixer::new(8000);
+    mixer.play_sound(Rc::from(vec![1000; 100]));
+    let mut out = vec![0; 60];
+    mixer.mix(&mut out);
+    // a second sound starts while the first one plays, and a loud one is clamped
+    mixer.play_sound(Rc::from(vec![3000; 20]));
+    mixer.play_sound(Rc::from(vec![32000; 10]));
+    let mut out2 = vec![0; 60];
+    mixer.mix(&mut out2);
+    assert!(!mixer.is_playing());
+    out.extend(out2);
+
+    let mut sink = WavFileSink::create(&path, 8000).unwrap();
+    assert_eq!(sink.sample_rate(), 8000);
+    sink.queue(&out).unwrap();
+    sink.finish().unwrap();
+    assert!(sink.queue(&out).is_err());
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(bytes.len(), 44 + 120 * 2);
+    assert_eq!(bytes[0..44], wav_header(8000, 240));
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(&bytes[36..40], b"data");
+    let samples: Vec<i16> = bytes[44..]
+        .chunks(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    let mut expected = vec![1000; 60];
+    expected.extend([32767; 10]);
+    expected.extend([4000; 10]);
+    expected.extend([1000; 20]);
+    expected.extend([0; 20]);
+    assert_eq!(samples, expected);
+}
+
+#[test]
+fn sound_player_keeps_the_music_wl1() {
+    let Some(dir) = find_data_dir("AUDIOT.WL1") else {
+        println!("AUDIOT.WL1 not found => skipping the sound player test");
+        return;
+    };
+    let assets = Rc::new(GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets"));
+    let track = assets
+        .music
+        .iter()
+        .position(|music| !music.is_empty())
+        .expect("no music");
+    let mut player = SoundPlayer::new(assets, 22050);
+    player.play_music(track, true);
+
+    // the sink has another sample rate => the music keeps playing, resampled
+    let mut sink = NullAudioSink::new(44100);
+    for _ in 0..10 {
+        player.update(&mut sink, 0.05).unwrap();
+    }
+    assert!(player.mixer().is_playing());
+    assert_eq!(player.mixer().sample_rate(), 22050);
+    assert!((sink.cnt_samples() as i64 - 22050).abs() <= 2);
+}
@@ -0,0 +1,101 @@
+//! Color map tests: building the shaded color maps from a palette, and the distance shading of the 3D view.
+
+use rolf3d::*;
+
+#[test]
+fn build_color_maps() {
+    let palette = Palette::wolf3d();
+    let fog = Fog {
+        color: 0,
+        falloff: 10.0,
+    };
+    let colormaps = ColorMaps::new(&palette, fog);
+    assert_eq!(colormaps.fog(), fog);
+    assert_eq!(colormaps.level(0.0), 0);
+    assert_eq!(colormaps.level(5.0), COLORMAP_LEVELS / 2 - 1);
+    assert_eq!(colormaps.level(10.0), COLORMAP_LEVELS - 1);
+    assert_eq!(colormaps.level(100.0), COLORMAP_LEVELS - 1);
+
+    for c in 0..=254 {
+        // full brightness keeps the colors, the last level is all fog
+        assert_eq!(palette.color(colormaps.map(0)[c as usize]), palette.color(c));
+        assert_eq!(palette.color(colormaps.shade(c, 10.0)), palette.color(0));
+    }
+    // transparent stays transparent
+    assert!((0..COLORMAP_LEVELS).all(|level| colormaps.map(level)[0xFF] == 0xFF));
+
+    // white gets darker with the distance
+    let brightness = |c: u8| {
+        let rgb = palette.color(c);
+        (rgb.r as u32) + (rgb.g as u32) + (rgb.b as u32)
+    };
+    let whites: Vec<u32> = (0..COLORMAP_LEVELS)
+        .map(|level| brightness(colormaps.map(level)[15]))
+        .collect();
+    assert!(whites.windows(2).all(|w| w[0] >= w[1]));
+    assert!(whites[0] > whites[COLORMAP_LEVELS / 2]);
+}
+
+#[test]
+fn lighting_shades_the_3d_view() {
+    let palette = Palette::wolf3d();
+    let white = GfxData::new_pic(1, 1, vec![15]);
+    let mut scrbuf = ScreenBuffer::new(320, 240, palette.clone());
+    assert!(!scrbuf.lighting_enabled());
+    scrbuf.set_fog(Fog { color: 0, falloff: 4.0 });
+
+    // without lighting, the distance does not matter
+    scrbuf.render_texture_column(10, 4.0, 0.0, &white);
+    assert_eq!(column_center(&scrbuf, 10), palette.color(15));
+
+    // with lighting, far walls fade into the fog, and the floor gets darker towards the horizon
+    scrbuf.enable_lighting(true);
+    scrbuf.clear_3d_view(0x1D, 0x19);
+    scrbuf.render_texture_column(10, 4.0, 0.0, &white);
+    scrbuf.render_texture_column(20, 0.5, 0.0, &white);
+    assert_eq!(column_center(&scrbuf, 10), palette.color(0));
+    assert_ne!(column_center(&scrbuf, 20), palette.color(0));
+    let pixels = capture(&scrbuf);
+    let bottom = pixels[(239 * 320) as usize];
+    let near_horizon = pixels[(125 * 320) as usize];
+    assert_eq!(near_horizon, palette.color(0));
+    assert!(bottom.grayscale() > near_horizon.grayscale());
+    assert!(bottom.grayscale() <= palette.color(0x19).grayscale());
+
+    scrbuf.enable_lighting(false);
+    assert!(!scrbuf.lighting_enabled());
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+struct Capture {
+    pixels: Vec<RGB>,
+}
+
+impl Painter for Capture {
+    fn get_screen_width(&self) -> i32 {
+        320
+    }
+
+    fn get_screen_height(&self) -> i32 {
+        240
+    }
+
+    fn draw_pixel(&mut self, x: i32, y: i32, color: RGB) {
+        self.pixels[(y * 320 + x) as usize] = color;
+    }
+}
+
+fn capture(scrbuf: &ScreenBuffer) -> Vec<RGB> {
+    let mut capture = Capture {
+        pixels: vec![RGB::from(0, 0, 0); 320 * 240],
+    };
+    scrbuf.paint(&mut capture);
+    capture.pixels
+}
+
+fn column_center(scrbuf: &ScreenBuffer, x: i32) -> RGB {
+    capture(scrbuf)[(120 * 320 + x) as usize]
+}
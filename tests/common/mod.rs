@@ -0,0 +1,13 @@
+//! Helpers shared by the integration tests.
+
+use rolf3d::find_file_ignore_case;
+use std::path::PathBuf;
+
+/// Find the directory with the game files: ROLF3D_DATA, or one of the usual asset directories.
+/// The tests which need the game files are skipped if they are not found.
+pub fn find_data_dir(filename: &str) -> Option<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var("ROLF3D_DATA").into_iter().map(PathBuf::from).collect();
+    dirs.extend([".", "data", "assets"].map(PathBuf::from));
+    dirs.into_iter()
+        .find(|dir| find_file_ignore_case(dir, filename).is_some())
+}
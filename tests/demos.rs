@@ -0,0 +1,196 @@
+//! Demo tests: parsing the demo chunks, converting the demo steps to player controls, and playing the
+//! shareware demos as a regression test of the simulation.
+//! The WL1 test needs the shareware files (see `common::find_data_dir`) - without them, it is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::rc::Rc;
+
+#[test]
+fn parse_demo() {
+    let bytes = [3, 10, 0, 0, 0x08, 0xDD, 0, 0x03, 35, 0xBA];
+    assert!(Demo::is_demo(&bytes));
+    let demo = Demo::parse(&bytes).unwrap();
+    assert_eq!(demo.map, 3);
+    assert_eq!(
+        demo.steps,
+        vec![
+            DemoStep {
+                buttons: DEMO_BTN_USE,
+                dx: -35,
+                dy: 0
+            },
+            DemoStep {
+                buttons: DEMO_BTN_ATTACK | DEMO_BTN_STRAFE,
+                dx: 35,
+                dy: -70
+            },
+        ]
+    );
+    assert_eq!(demo.to_bytes(), bytes);
+
+    // the length must match the chunk, and the steps must be complete
+    assert!(!Demo::is_demo(&bytes[0..9]));
+    assert!(!Demo::is_demo(&[3, 9, 0, 0, 1, 2, 3, 4, 5]));
+    assert!(Demo::parse(b"^P\r\n").is_err());
+}
+
+#[test]
+fn demo_step_controls() {
+    let idle = DemoStep::default();
+    let walk = DemoStep {
+        buttons: DEMO_BTN_USE,
+        dx: 35,
+        dy: -35,
+    };
+    // walking for 4 tics turns by 35 * 4 / ANGLESCALE = 7 degrees => exactly a step of turning
+    let mut angle_frac = 0;
+    let controls = walk.controls(&idle, &mut angle_frac);
+    assert_eq!((controls.forward, controls.strafe, controls.turn), (1.0, 0.0, 1.0));
+    assert_eq!(angle_frac, 0);
+    assert!(controls.use_pressed && !controls.attack_pressed);
+    // held buttons act only once
+    assert!(!walk.controls(&walk, &mut angle_frac).use_pressed);
+
+    // turning slowly: the fractions of a degree are carried to the next steps
+    let creep = DemoStep {
+        buttons: 0,
+        dx: 3,
+        dy: 0,
+    };
+    let turns: Vec<f64> = (0..5)
+        .map(|_| creep.controls(&idle, &mut angle_frac).turn * 7.0)
+        .collect();
+    assert_eq!(turns, vec![0.0, 1.0, 0.0, 1.0, 1.0]);
+    assert_eq!(angle_frac, 0);
+
+    let strafe_back = DemoStep {
+        buttons: DEMO_BTN_STRAFE | (DEMO_BTN_FIRST_WEAPON << 2),
+        dx: -70,
+        dy: 105,
+    };
+    let controls = strafe_back.controls(&idle, &mut angle_frac);
+    assert_eq!((controls.forward, controls.strafe, controls.turn), (-3.0, -2.0, 0.0));
+    assert_eq!(controls.weapon, Some(2));
+}
+
+#[test]
+fn player_speeds() {
+    // like the original: walking moves ~5.6 tiles per second, and turns 122.5 degrees per second
+    assert!((PLAYER_MOVE_SPEED - 5.6076).abs() < 0.0001);
+    assert!((PLAYER_TURN_SPEED.to_degrees() - 122.5).abs() < 1e-9);
+}
+
+#[test]
+fn play_demos_wl1() {
+    let Some(dir) = find_data_dir("VGAGRAPH.WL1") else {
+        println!("VGAGRAPH.WL1 not found => skipping the demo test");
+        return;
+    };
+    let assets = Rc::new(GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets"));
+    let maps: Vec<usize> = assets.demos.iter().map(|demo| demo.map).collect();
+    assert_eq!(maps, vec![0, 2, 4, 6]);
+
+    // a demo on a map which does not exist cannot be played
+    let bad_demo = Demo {
+        map: assets.maps.len(),
+        steps: vec![],
+    };
+    assert!(DemoPlayer::new(Rc::clone(&assets), bad_demo).is_err());
+
+    // where each demo ends: (x, y, angle in degrees) + the status (there are no actors to kill yet)
+    let final_states = [
+        ((33.377, 45.474, 268.0), "K: 0/0   S: 0/5   T: 0/23"),
+        ((37.593, 7.284, 225.0), "K: 0/0   S: 0/10   T: 0/66"),
+        ((32.933, 39.753, 326.0), "K: 0/0   S: 0/5   T: 0/73"),
+        ((38.497, 56.756, 184.0), "K: 0/0   S: 0/2   T: 0/18"),
+    ];
+    for (demo, (final_pos, final_status)) in assets.demos.iter().zip(final_states) {
+        let mut player = DemoPlayer::new(Rc::clone(&assets), demo.clone()).unwrap();
+        let mut angle_frac = 0;
+        let (mut moving_steps, mut free_steps) = (0, 0);
+        for step in demo.steps.iter() {
+            let (old_x, old_y, old_angle) = player.livemap().player_pos();
+            assert!(player.step());
+            let (x, y, angle) = player.livemap().player_pos();
+
+            // the player never walks into a wall, or out of the map
+            let cell = player.livemap().cell(x as i32, y as i32);
+            assert!(
+                cell.is_some_and(|cell| !cell.is_wall()),
+                "demo on map {}: in a wall",
+                demo.map
+            );
+
+            // each step turns by whole degrees, with the fractions carried over (like ControlMovement)
+            let strafing = step.buttons & DEMO_BTN_STRAFE != 0;
+            let mut degrees = 0;
+            if !strafing {
+                angle_frac += step.dx as i32 * 4;
+                degrees = angle_frac / 20;
+                angle_frac -= degrees * 20;
+            }
+            let turned = (angle - old_angle).to_degrees().rem_euclid(360.0);
+            let expected = (degrees as f64).rem_euclid(360.0);
+            let diff = (turned - expected).abs();
+            assert!(
+                diff.min(360.0 - diff) < 1e-6,
+                "demo on map {}: turned {turned} instead of {expected}",
+                demo.map
+            );
+
+            // each step moves by (control * tics * MOVESCALE) / TILEGLOBAL tiles, at most (walls may block it)
+            let back = step.dy > 0;
+            let forward = (step.dy as f64).abs() * 4.0 * if back { 100.0 } else { 150.0 } / 65536.0;
+            let sideways = if strafing {
+                (step.dx as f64).abs() * 4.0 * 150.0 / 65536.0
+            } else {
+                0.0
+            };
+            let moved = (x - old_x).hypot(y - old_y);
+            assert!(
+                moved <= forward + sideways + 1e-9,
+                "demo on map {}: moved too far",
+                demo.map
+            );
+            if forward + sideways > 0.0 && (forward == 0.0 || sideways == 0.0) {
+                moving_steps += 1;
+                if (moved - forward - sideways).abs() < 1e-9 {
+                    free_steps += 1;
+                }
+            }
+        }
+        assert!(player.is_finished());
+        // most of the time, the player is not blocked by walls
+        assert!(
+            free_steps * 2 > moving_steps,
+            "demo on map {}: {free_steps} of {moving_steps}",
+            demo.map
+        );
+
+        // playing in real time (with uneven frames) gives the same result
+        let mut realtime = DemoPlayer::new(Rc::clone(&assets), demo.clone()).unwrap();
+        while !realtime.is_finished() {
+            realtime.update(0.03);
+        }
+        assert_eq!(realtime.livemap().player_pos(), player.livemap().player_pos());
+
+        let (x, y, angle) = player.livemap().player_pos();
+        let angle = angle.to_degrees().rem_euclid(360.0);
+        let (final_x, final_y, final_angle) = final_pos;
+        assert!(
+            (x - final_x).abs() < 0.001 && (y - final_y).abs() < 0.001 && (angle - final_angle).abs() < 1e-6,
+            "demo on map {}: ended at ({x}, {y}, {angle})",
+            demo.map
+        );
+        assert_eq!(
+            player.livemap().get_secrets_msg(),
+            final_status,
+            "demo on map {}",
+            demo.map
+        );
+        assert!(!player.livemap().is_player_dead());
+    }
+}
@@ -0,0 +1,104 @@
+//! FizzleFade tests: the LFSR order visits every pixel exactly once, and the transitions reveal the target frame.
+
+use rolf3d::*;
+
+#[test]
+fn fizzle_order_visits_each_pixel_once() {
+    // one size for each register length, up to 22 bits (= more than 4 million pixels)
+    let mut lens: Vec<usize> = (1..=22).map(|bits| (1 << bits) - 1).collect();
+    lens.extend([0, 2, 5, 100, 320 * 200, 640 * 480]);
+    for len in lens {
+        let mut visited = vec![false; len];
+        let order = FizzleOrder::new(len);
+        assert_eq!(order.size_hint(), (len, Some(len)));
+        for idx in order {
+            assert!(!visited[idx], "pixel {idx} visited twice, for {len} pixels");
+            visited[idx] = true;
+        }
+        assert!(visited.iter().all(|v| *v), "not all of the {len} pixels were visited");
+    }
+}
+
+#[test]
+fn fizzle_order_is_not_sequential() {
+    let order: Vec<usize> = FizzleOrder::new(320 * 200).take(1000).collect();
+    let in_sequence = order.windows(2).filter(|w| w[1] == w[0] + 1).count();
+    assert!(in_sequence < 100);
+}
+
+#[test]
+fn fizzle_fade_reveals_the_target() {
+    let len = 320 * 200;
+    let mut fizzle = FizzleFade::new(vec![1; len], vec![2; len], 1.0);
+    assert!(!fizzle.is_finished());
+    assert_eq!(fizzle.progress(), 0.0);
+
+    fizzle.update(0.25);
+    let revealed = fizzle.frame().iter().filter(|&&c| c == 2).count();
+    assert_eq!(revealed, len / 4);
+    // the revealed pixels are spread all over the frame
+    let top_half = fizzle.frame()[..len / 2].iter().filter(|&&c| c == 2).count();
+    assert!(top_half > len / 16 && top_half < len * 3 / 16);
+
+    fizzle.update(0.5);
+    assert_eq!(fizzle.progress(), 0.75);
+    fizzle.update(1.0);
+    assert!(fizzle.is_finished());
+    assert_eq!(fizzle.frame(), fizzle.target());
+}
+
+#[test]
+fn screen_buffer_transition() {
+    let palette = Palette::wolf3d();
+    let mut scrbuf = ScreenBuffer::new(320, 240, palette.clone());
+    scrbuf.fill_rect(0, 0, 320, 240, 15);
+    let source = scrbuf.frame();
+    scrbuf.fill_rect(0, 0, 320, 240, 4);
+    let target = scrbuf.frame();
+    assert_ne!(source, target);
+
+    // while the transition runs, it is painted instead of the buffer
+    scrbuf.start_transition(source, target.clone(), 2.0);
+    assert!(scrbuf.is_transition_running());
+    assert!(paint(&scrbuf).iter().all(|c| *c == palette.color(15)));
+    scrbuf.update_transition(1.0);
+    let pixels = paint(&scrbuf);
+    let reds = pixels.iter().filter(|c| **c == palette.color(4)).count();
+    assert_eq!(reds, pixels.len() / 2);
+
+    // at the end, the buffer contains the target
+    scrbuf.fill_rect(0, 0, 320, 240, 0);
+    scrbuf.update_transition(1.5);
+    assert!(!scrbuf.is_transition_running());
+    assert_eq!(scrbuf.frame(), target);
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+struct Capture {
+    pixels: Vec<RGB>,
+}
+
+impl Painter for Capture {
+    fn get_screen_width(&self) -> i32 {
+        320
+    }
+
+    fn get_screen_height(&self) -> i32 {
+        240
+    }
+
+    fn draw_pixel(&mut self, x: i32, y: i32, color: RGB) {
+        self.pixels[(y * 320 + x) as usize] = color;
+    }
+}
+
+fn paint(scrbuf: &ScreenBuffer) -> Vec<RGB> {
+    let mut capture = Capture {
+        pixels: vec![RGB::from(0, 0, 0); 320 * 240],
+    };
+    scrbuf.paint(&mut capture);
+    capture.pixels
+}
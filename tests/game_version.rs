@@ -0,0 +1,92 @@
+//! Game version tests: identify the shareware files by their fingerprint, and the rules which depend on the release.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+
+#[test]
+fn crc32_check_value() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn release_rules() {
+    let fingerprint = Fingerprint {
+        vgahead: 0,
+        maphead: 0,
+        vswap_header: 0,
+    };
+    let wl6 = GameVersion::identify("WL6", fingerprint);
+    assert!(!wl6.is_known() && !wl6.is_sod() && wl6.has_help());
+    assert!(GameVersion::identify("SOD", fingerprint).is_sod());
+    assert!(!GameVersion::identify("SDM", fingerprint).has_help());
+    // F1 is the boss key in the GT and Activision releases
+    assert!(GameRelease::Wolf3dApogee14.has_help());
+    assert!(!GameRelease::Wolf3dGT.has_help());
+    assert!(!GameRelease::Wolf3dActivision.has_help());
+}
+
+#[test]
+fn identify_wl1_release() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the game version test");
+        return;
+    };
+    let read = |name: &str| std::fs::read(dir.join(name)).unwrap();
+    let (vgahead, maphead, vswap) = (read("VGAHEAD.WL1"), read("MAPHEAD.WL1"), read("VSWAP.WL1"));
+
+    let fingerprint = Fingerprint::new(&vgahead, &maphead, &vswap).unwrap();
+    assert_eq!(fingerprint.maphead, 0x088F7551);
+    let version = GameVersion::identify("WL1", fingerprint);
+    assert_eq!(version.release, GameRelease::Wolf3dApogee14);
+    assert!(!version.modified_maps);
+    assert_eq!(version.to_string(), "Wolfenstein 3D v1.4 (Apogee) (WL1)");
+    // only the VSWAP header is fingerprinted
+    assert_eq!(
+        Fingerprint::new(&vgahead, &maphead, &vswap[0..6 + 663 * 6]),
+        Some(fingerprint)
+    );
+    assert_eq!(Fingerprint::new(&vgahead, &maphead, &vswap[0..100]), None);
+
+    // same files, but another extension, or a changed header => unknown
+    assert!(!GameVersion::identify("WL6", fingerprint).is_known());
+    let mut changed = vgahead.clone();
+    changed[2] ^= 1;
+    let changed = Fingerprint::new(&changed, &maphead, &vswap).unwrap();
+    assert_eq!(GameVersion::identify("WL1", changed).release, GameRelease::Unknown);
+
+    // another MAPHEAD => same release, with modified maps
+    let mut changed = maphead.clone();
+    changed[38] ^= 1;
+    let changed = Fingerprint::new(&vgahead, &changed, &vswap).unwrap();
+    assert_ne!(changed, fingerprint);
+    let version = GameVersion::identify("WL1", changed);
+    assert_eq!(version.release, GameRelease::Wolf3dApogee14);
+    assert!(version.modified_maps);
+    assert_eq!(
+        version.to_string(),
+        "Wolfenstein 3D v1.4 (Apogee) (WL1) with modified maps"
+    );
+
+    // a level pack (which replaces the maps) is still the same release
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-levelpack-{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    for name in [
+        "GAMEMAPS", "VGADICT", "VGAHEAD", "VGAGRAPH", "VSWAP", "AUDIOHED", "AUDIOT",
+    ] {
+        std::fs::copy(dir.join(format!("{name}.WL1")), outdir.join(format!("{name}.WL1"))).unwrap();
+    }
+    // (here, the pack drops the last map)
+    let mut maphead = read("MAPHEAD.WL1");
+    maphead[38..42].copy_from_slice(&[0, 0, 0, 0]);
+    std::fs::write(outdir.join("MAPHEAD.WL1"), maphead).unwrap();
+    let assets = GameAssets::load_from(outdir.to_str().unwrap());
+    std::fs::remove_dir_all(&outdir).unwrap();
+    let assets = assets.expect("failed to load the level pack");
+    assert_eq!(assets.maps.len(), 9);
+    assert_eq!(assets.version.release, GameRelease::Wolf3dApogee14);
+    assert!(assets.version.modified_maps);
+    assert!(assets.help_article.is_some());
+}
@@ -0,0 +1,98 @@
+//! Export tests: export the shareware graphics to PNG files, then decode some of them and check the pixels.
+//! Needs the shareware files (see `common::find_data_dir`) - without them, the test is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::path::Path;
+
+#[test]
+fn export_graphics_wl1() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the export test");
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-export-{}", std::process::id()));
+    let cnt = export_graphics(&assets, &outdir).expect("failed to export the graphics");
+
+    // one file per non-empty graphic + the 2 font sheets
+    let is_empty = |gfx: &GfxData| gfx.size().0 == 0 || gfx.size().1 == 0;
+    let cnt_walls = assets.walls.iter().filter(|gfx| !is_empty(gfx)).count();
+    let cnt_sprites = assets.sprites.iter().filter(|gfx| !is_empty(gfx)).count();
+    let cnt_pics = (0..PicDict::pic_count())
+        .filter(|&idx| !is_empty(assets.pics.pic_by_index(idx)))
+        .count();
+    assert_eq!((cnt_walls, cnt_sprites), (64, 226));
+    assert_eq!(cnt, cnt_walls + cnt_sprites + cnt_pics + 2);
+    let files_in = |subdir: &str| std::fs::read_dir(outdir.join(subdir)).unwrap().count();
+    assert_eq!(files_in("walls"), cnt_walls);
+    assert_eq!(files_in("sprites"), cnt_sprites);
+    assert_eq!(files_in("pics"), cnt_pics);
+
+    // the PNG keeps the original color indexes (row by row) and the game palette
+    let (width, height, indexes, palette) = decode_indexed_png(&outdir.join("walls/wall_000.png"));
+    let wall = &assets.walls[0];
+    assert_eq!((width, height), (64, 64));
+    for x in 0..64 {
+        for y in 0..64 {
+            assert_eq!(indexes[y * 64 + x], wall.texels()[x * 64 + y], "pixel ({x}, {y})");
+        }
+    }
+    assert_eq!(palette, assets.palette.to_rgb_bytes());
+
+    // the glyph of 'A' is in the 2nd cell of the 3rd row
+    let sheet = font_glyph_sheet(&assets.font1);
+    let font_height = assets.font1.font_height() as usize;
+    let cell_width = (32..=127)
+        .filter_map(|ch| assets.font1.glyph(ch))
+        .map(|(w, _)| w as usize)
+        .max()
+        .unwrap();
+    assert_eq!(sheet.size(), ((cell_width * 16) as u16, (font_height * 6) as u16));
+    let sheet_height = font_height * 6;
+    let (glyph_width, glyph) = assets.font1.glyph(b'A').unwrap();
+    let (x0, y0) = (cell_width, 2 * font_height);
+    for x in 0..cell_width {
+        for y in 0..font_height {
+            let texel = sheet.texels()[(x0 + x) * sheet_height + y0 + y];
+            let expected = match x < glyph_width as usize {
+                true if glyph[x * font_height + y] != 0 => glyph[x * font_height + y],
+                _ => TRANSPARENT_COLOR,
+            };
+            assert_eq!(texel, expected, "glyph pixel ({x}, {y})");
+        }
+    }
+    assert!(glyph.iter().any(|&c| c != 0), "the glyph is empty");
+    // ... and the exported sheet is the same
+    let (width, height, indexes, _) = decode_indexed_png(&outdir.join("font1.png"));
+    assert_eq!((width, height), (sheet.size().0 as usize, sheet_height));
+    for x in 0..width {
+        for y in 0..height {
+            assert_eq!(
+                indexes[y * width + x],
+                sheet.texels()[x * height + y],
+                "sheet pixel ({x}, {y})"
+            );
+        }
+    }
+    std::fs::remove_dir_all(outdir).unwrap();
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+/// Decode an 8-bit indexed PNG => width, height, color indexes (row by row), RGB palette.
+fn decode_indexed_png(path: &Path) -> (usize, usize, Vec<u8>, Vec<u8>) {
+    let decoder = png::Decoder::new(std::fs::File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut indexes = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut indexes).unwrap();
+    assert_eq!(frame.color_type, png::ColorType::Indexed);
+    assert_eq!(frame.bit_depth, png::BitDepth::Eight);
+    indexes.truncate(frame.buffer_size());
+    let palette = reader.info().palette.as_ref().unwrap().to_vec();
+    (frame.width as usize, frame.height as usize, indexes, palette)
+}
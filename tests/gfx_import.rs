@@ -0,0 +1,78 @@
+//! Import tests: export graphics to PNG/BMP, import them back, and check that nothing changed.
+//! Needs the shareware files (see `common::find_data_dir`) - without them, the test is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::path::PathBuf;
+
+#[test]
+fn png_export_import_roundtrip_wl1() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the PNG round-trip test");
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+    let outdir = temp_dir("png");
+
+    // walls are opaque, sprites have transparent pixels
+    for (gfx, transparent) in [(&assets.walls[0], false), (&assets.sprites[50], true)] {
+        let path = outdir.join("gfx.png");
+        write_png(&path, gfx, &assets.palette, transparent).unwrap();
+        let imported = import_image(&path, &assets.palette, transparent).unwrap();
+        assert_eq!(imported.size(), gfx.size());
+        // the palette may contain the same color twice => compare the colors, not the indexes
+        for (&a, &b) in imported.texels().iter().zip(gfx.texels()) {
+            if transparent && (a == TRANSPARENT_COLOR || b == TRANSPARENT_COLOR) {
+                assert_eq!(a, b);
+            } else {
+                assert_eq!(assets.palette.color(a), assets.palette.color(b));
+            }
+        }
+    }
+    std::fs::remove_dir_all(outdir).unwrap();
+}
+
+#[test]
+fn bmp_import_quantizes_and_transposes() {
+    // 2x2 pixels, 24 bits, bottom-up: the first row in the file is the bottom one
+    let red = [0x00, 0x00, 0xFF];
+    let black = [0x00, 0x00, 0x00];
+    let white = [0xFF, 0xFF, 0xFF];
+    let almost_blue = [0xA8, 0x00, 0x02];
+    let mut pixels = vec![];
+    for row in [[white, almost_blue], [red, black]] {
+        row.iter().for_each(|p| pixels.extend_from_slice(p));
+        pixels.extend_from_slice(&[0, 0]); // pad each row to 4 bytes
+    }
+    let mut bmp = b"BM".to_vec();
+    bmp.extend_from_slice(&((54 + pixels.len()) as u32).to_le_bytes());
+    bmp.extend_from_slice(&[
+        0, 0, 0, 0, 54, 0, 0, 0, 40, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 1, 0, 24, 0,
+    ]);
+    bmp.extend_from_slice(&[0; 24]);
+    bmp.extend_from_slice(&pixels);
+
+    let outdir = temp_dir("bmp");
+    let path = outdir.join("gfx.bmp");
+    std::fs::write(&path, bmp).unwrap();
+    let palette = Palette::wolf3d();
+    let gfx = import_image(&path, &palette, false).unwrap();
+    std::fs::remove_dir_all(outdir).unwrap();
+
+    assert_eq!(gfx.size(), (2, 2));
+    // column by column: (0,0)=red, (0,1)=white, (1,0)=black, (1,1)=almost blue
+    let rgb = |c: u8| palette.color(c);
+    let texels = gfx.texels();
+    assert_eq!(rgb(texels[0]), RGB::from(0xFF, 0x00, 0x00));
+    assert_eq!(rgb(texels[1]), RGB::from(0xFF, 0xFF, 0xFF));
+    assert_eq!(rgb(texels[2]), RGB::from(0x00, 0x00, 0x00));
+    assert_eq!(rgb(texels[3]), RGB::from(0x00, 0x00, 0xA8));
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rolf3d-test-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,115 @@
+//! Level color tests: the built-in ceiling tables, overriding them (+ the fog) from text, and a mod which changes them.
+//! The mod test needs the shareware files (see `common::find_data_dir`) - without them, it is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::rc::Rc;
+
+#[test]
+fn builtin_level_colors() {
+    let wl1 = LevelColorTable::builtin("WL1");
+    assert_eq!(wl1.len(), 10);
+    assert_eq!(
+        wl1.colors(0),
+        LevelColors {
+            ceiling: 0x1D,
+            floor: 0x19,
+            ..Default::default()
+        }
+    );
+    assert_eq!(wl1.colors(9).ceiling, 0xBF);
+    // the maps which are not in the table get the default colors
+    assert_eq!(wl1.colors(10), LevelColors::default());
+
+    let wl6 = LevelColorTable::builtin("WL6");
+    assert_eq!(wl6.len(), 60);
+    assert_eq!(wl6.colors(14).ceiling, 0x8D);
+    assert_eq!(wl6.colors(59).ceiling, 0xDD);
+    assert_eq!(LevelColorTable::builtin("WL3").len(), 30);
+
+    let sod = LevelColorTable::builtin("SOD");
+    assert_eq!(sod.len(), 21);
+    assert_eq!(sod.colors(0).ceiling, 0x6F);
+    assert_eq!(LevelColorTable::builtin("SDM"), sod);
+}
+
+#[test]
+fn apply_level_colors() {
+    let mut table = LevelColorTable::builtin("WL1");
+    let text = "# a comment\n0 = 0x4E\n\n2..3 = 45, 0X20  # ceiling + floor\n12 = 0xDD\n";
+    table.apply(text).unwrap();
+    assert_eq!(table.colors(0).ceiling, 0x4E);
+    assert_eq!(table.colors(0).floor, 0x19);
+    assert_eq!(table.colors(1).ceiling, 0x1D);
+    for map in [2, 3] {
+        assert_eq!(
+            table.colors(map),
+            LevelColors {
+                ceiling: 45,
+                floor: 0x20,
+                ..Default::default()
+            }
+        );
+    }
+    assert_eq!(table.colors(9).ceiling, 0xBF);
+    // the table grows, if needed
+    assert_eq!(table.len(), 13);
+    assert_eq!(table.colors(11), LevelColors::default());
+    assert_eq!(table.colors(12).ceiling, 0xDD);
+
+    // errors contain the line number
+    let mut table = LevelColorTable::default();
+    assert_eq!(table.apply("0 = 1\n1 = 256").unwrap_err().0, 2);
+    assert_eq!(table.apply("0 1").unwrap_err().0, 1);
+    assert_eq!(table.apply("x = 1").unwrap_err().0, 1);
+    assert_eq!(table.apply("5..2 = 1").unwrap_err().0, 1);
+    assert_eq!(table.apply("0 = 1, 0xZZ").unwrap_err().0, 1);
+    assert_eq!(table.apply("0.fog = 1").unwrap_err().0, 1);
+    assert_eq!(table.apply("0.fog = 1, 0").unwrap_err().0, 1);
+    assert_eq!(table.apply("0.fog = 1, far").unwrap_err().0, 1);
+}
+
+#[test]
+fn apply_level_fog() {
+    let mut table = LevelColorTable::builtin("WL1");
+    table.apply("1..2.fog = 0x00, 8.5\n2 = 0x4E\n").unwrap();
+    let fog = Fog { color: 0, falloff: 8.5 };
+    assert_eq!(table.colors(1).fog, fog);
+    assert_eq!(table.colors(1).ceiling, 0x1D);
+    // the fog and the colors are set separately
+    assert_eq!(table.colors(2).fog, fog);
+    assert_eq!(table.colors(2).ceiling, 0x4E);
+    assert_eq!(table.colors(3).fog, Fog::default());
+}
+
+#[test]
+fn mod_overrides_level_colors() {
+    let Some(dir) = find_data_dir("GAMEMAPS.WL1") else {
+        println!("GAMEMAPS.WL1 not found => skipping the level colors test");
+        return;
+    };
+    let mut colors = MemorySource::new("colors");
+    colors.insert("levelcol.wl1", b"0 = 0x8D, 0x18\n0.fog = 0x1D, 6\n".to_vec());
+    let mut source = LayeredSource::new();
+    source.push(Box::new(DirSource::new(&dir)));
+    source.push(Box::new(colors));
+    let assets = GameAssets::load_from_source(&source).expect("failed to load the modded assets");
+
+    // the live map picks up the colors of each floor
+    let mut livemap = LiveMap::new(Rc::new(assets), 0);
+    assert_eq!(
+        livemap.level_colors(),
+        LevelColors {
+            ceiling: 0x8D,
+            floor: 0x18,
+            fog: Fog {
+                color: 0x1D,
+                falloff: 6.0
+            }
+        }
+    );
+    livemap.go_to_next_floor();
+    assert_eq!(livemap.level_colors(), LevelColors::default());
+}
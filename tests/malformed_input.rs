@@ -0,0 +1,89 @@
+//! Regression tests for the decoders, with fixed malformed inputs (truncated data, bad back-references etc).
+//! They must return a `DecodeError` - never panic, and never read outside of the input.
+
+use rolf3d::*;
+
+#[test]
+fn carmack_malformed() {
+    // truncated: the length announces 2 words, but the data ends in the middle of the first one
+    assert!(carmack_decompress(&[4, 0, 1]).is_err());
+    assert!(carmack_decompress(&[4]).is_err());
+    // near pointer, further back than the decoded words
+    let err = carmack_decompress(&[8, 0, 1, 0, 0x02, 0xA7, 0x05]).unwrap_err();
+    assert_eq!(err.offset, 4);
+    // near pointer, 0 words back
+    assert!(carmack_decompress(&[8, 0, 1, 0, 0x02, 0xA7, 0x00]).is_err());
+    // far pointer, to a word which is not decoded yet
+    let err = carmack_decompress(&[8, 0, 1, 0, 0x02, 0xA8, 5, 0]).unwrap_err();
+    assert_eq!(err.offset, 4);
+    // escape sequence without its byte
+    assert!(carmack_decompress(&[2, 0, 0x00, 0xA7]).is_err());
+
+    // valid: an overlapping near pointer, which copies more than the announced length
+    assert_eq!(carmack_decompress(&[8, 0, 7, 0, 9, 0xA7, 1]).unwrap(), vec![7, 7, 7, 7]);
+}
+
+#[test]
+fn rlew_malformed() {
+    let tag = DEFAULT_RLEW_TAG;
+    // truncated RLEW sequence (no value)
+    let err = rlew_decompress(&[8, tag, 4], tag).unwrap_err();
+    assert_eq!(err.offset, 3);
+    // truncated normal words
+    assert!(rlew_decompress(&[8, 1, 2], tag).is_err());
+    assert!(rlew_decompress(&[], tag).is_err());
+    // a run longer than the announced length is cut
+    assert_eq!(rlew_decompress(&[4, tag, 100, 9], tag).unwrap(), vec![9, 9]);
+    // map planes: RLEW errors are reported at the start of the chunk
+    let err = decompress_map_plane(&[4, 0, 8, 0, 0xCD, 0xAB], tag).unwrap_err();
+    assert_eq!(err.offset, 0);
+}
+
+#[test]
+fn huffman_malformed() {
+    let dict = huff_build_dict(&[b"hello huffman"]);
+    let encoded = huff_encode_chunk(b"hello huffman", &dict).unwrap();
+    assert_eq!(huff_decode_chunk(&encoded, &dict).unwrap(), b"hello huffman");
+
+    // truncated data, or a missing size
+    assert!(huff_decode_chunk(&encoded[..encoded.len() - 2], &dict).is_err());
+    assert!(huff_decode_chunk(&encoded[..3], &dict).is_err());
+    // a garbage size must not allocate a huge buffer
+    let mut huge = encoded.clone();
+    huge[0..4].copy_from_slice(&0x7FFF_FFFF_u32.to_le_bytes());
+    assert!(huff_decode_chunk(&huge, &dict).is_err());
+    // a dictionary which is too small
+    assert!(huff_decode_chunk(&encoded, &dict[..100]).is_err());
+}
+
+#[test]
+fn sprite_malformed() {
+    // one column (x = 0), with one post for y = 0..2
+    let sprite = [0, 0, 0, 0, 8, 0, 3, 5, 4, 0, 0, 0, 0, 0, 0, 0];
+    let pixels = decode_sprite(&sprite).unwrap();
+    assert_eq!(&pixels[0..3], &[3, 5, 0xFF]);
+
+    // texels can have any value (even 0xFF), like in the original
+    let mut with_ff = sprite;
+    with_ff[6] = 0xFF;
+    assert_eq!(&decode_sprite(&with_ff).unwrap()[0..2], &[0xFF, 5]);
+
+    // truncated, at each possible length
+    for len in 0..sprite.len() - 2 {
+        assert!(decode_sprite(&sprite[..len]).is_err(), "truncated to {len} bytes");
+    }
+    // invalid extents
+    assert!(decode_sprite(&[5, 0, 4, 0, 0, 0]).is_err());
+    assert!(decode_sprite(&[0, 0, 64, 0, 0, 0]).is_err());
+    // column offset outside of the data
+    let mut bad_ofs = sprite;
+    bad_ofs[4] = 200;
+    assert!(decode_sprite(&bad_ofs).is_err());
+    // post past the bottom of the sprite, or upside down
+    let mut bad_post = sprite;
+    bad_post[8] = 130;
+    assert!(decode_sprite(&bad_post).is_err());
+    let mut upside_down = sprite;
+    upside_down[12] = 6;
+    assert!(decode_sprite(&upside_down).is_err());
+}
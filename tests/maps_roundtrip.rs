@@ -0,0 +1,130 @@
+//! Round-trip test for the maps: load GAMEMAPS/MAPHEAD, encode them back, and compare the bytes.
+//! Needs the shareware files (MAPHEAD.WL1, GAMEMAPS.WL1 etc) - set ROLF3D_DATA to their directory,
+//! or put them into one of the usual asset directories. Without them, the test is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+
+#[test]
+fn maps_roundtrip_wl1() {
+    let Some(dir) = find_data_dir("GAMEMAPS.WL1") else {
+        println!("GAMEMAPS.WL1 not found => skipping the maps round-trip test");
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+    assert_eq!(assets.game_type, "WL1");
+    let maphead = std::fs::read(find_file_ignore_case(&dir, "MAPHEAD.WL1").unwrap()).unwrap();
+    let gamemaps = std::fs::read(find_file_ignore_case(&dir, "GAMEMAPS.WL1").unwrap()).unwrap();
+
+    let rlew_tag = buf_to_u16(&maphead);
+    assert_eq!(rlew_tag, DEFAULT_RLEW_TAG);
+    let (new_maphead, new_gamemaps) = encode_maps(&assets.maps, rlew_tag).unwrap();
+    assert!(new_maphead == maphead, "MAPHEAD differs");
+    assert!(new_gamemaps == gamemaps, "GAMEMAPS differs");
+}
+
+#[test]
+fn map_planes_roundtrip() {
+    // a plane with runs, repeated strings, tag-like words and the RLEW tag itself
+    let mut plane = vec![0_u16; 4096];
+    for (i, w) in plane.iter_mut().enumerate() {
+        *w = match i % 97 {
+            0..=9 => 1,
+            10..=20 => (i % 7) as u16,
+            21 => 0xA700 | (i as u16 & 0xFF),
+            22 => 0xA8FF,
+            23 => DEFAULT_RLEW_TAG,
+            _ => (i / 300) as u16,
+        };
+    }
+    let compressed = compress_map_plane(&plane, DEFAULT_RLEW_TAG).unwrap();
+    let decompressed = decompress_map_plane(&compressed, DEFAULT_RLEW_TAG).unwrap();
+    assert_eq!(decompressed, plane);
+}
+
+#[test]
+fn too_large_planes_are_rejected() {
+    // the largest map, with many RLEW tags (each one becomes 3 words) => the RLEW output has more than 0x7FFF words
+    let (width, height) = (181, 181);
+    let tiles: Vec<u16> = (0..width * height)
+        .map(|i| if i % 2 == 0 { DEFAULT_RLEW_TAG } else { 1 })
+        .collect();
+    assert!(rlew_compress(&tiles, DEFAULT_RLEW_TAG).unwrap().len() > MAX_COMPRESSED_WORDS);
+    assert!(compress_map_plane(&tiles, DEFAULT_RLEW_TAG).is_err());
+    assert!(carmack_compress(&vec![1; MAX_COMPRESSED_WORDS + 1]).is_err());
+    assert!(rlew_compress(&vec![1; MAX_COMPRESSED_WORDS + 1], DEFAULT_RLEW_TAG).is_err());
+
+    let things = vec![0; width * height];
+    let map = MapData::new("TAGS".to_string(), width as u16, height as u16, tiles, things).unwrap();
+    let err = encode_maps(&[map], DEFAULT_RLEW_TAG)
+        .err()
+        .expect("a corrupt GAMEMAPS was written");
+    assert!(err.contains("TAGS"), "unexpected error: {err}");
+}
+
+#[test]
+fn map_sizes_and_info_plane() {
+    let plane = |w: usize, h: usize, base: u16| (0..w * h).map(|i| base + (i % 1000) as u16).collect::<Vec<u16>>();
+    // bigger than 64x64, with tiles > 0xFF and things > 0x1FF
+    let map = MapData::new("BIG".to_string(), 100, 80, plane(100, 80, 0x100), plane(100, 80, 0x200))
+        .unwrap()
+        .with_info_plane(plane(100, 80, 7))
+        .unwrap();
+    assert_eq!(map.tile(99, 79), 0x100 + 7999 % 1000);
+    assert_eq!(map.thing(1, 1), 0x200 + 101);
+    assert_eq!(map.info(2, 0), 9);
+    assert_eq!(map.info(100, 0), 0);
+
+    // the original maps have an empty info plane
+    let map = MapData::new("SMALL".to_string(), 8, 4, plane(8, 4, 1), plane(8, 4, 1)).unwrap();
+    assert!(map.infos().iter().all(|w| *w == 0));
+    assert!(map.with_info_plane(plane(4, 4, 1)).is_err());
+
+    assert!(MapData::new("WRONG".to_string(), 8, 4, plane(8, 4, 1), plane(4, 4, 1)).is_err());
+    assert!(MapData::new("EMPTY".to_string(), 0, 4, vec![], vec![]).is_err());
+    assert!(MapData::new("HUGE".to_string(), 200, 200, plane(200, 200, 1), plane(200, 200, 1)).is_err());
+    assert!(MapData::new("LARGEST".to_string(), 181, 181, plane(181, 181, 1), plane(181, 181, 1)).is_ok());
+}
+
+#[test]
+fn big_maps_with_info_plane_load() {
+    let Some(dir) = find_data_dir("GAMEMAPS.WL1") else {
+        println!("GAMEMAPS.WL1 not found => skipping the big maps test");
+        return;
+    };
+    // a 96x72 map, surrounded by walls, with the player in the middle and some info in plane 3
+    let (width, height) = (96, 72);
+    let mut tiles = vec![108_u16; width * height];
+    let mut things = vec![0_u16; width * height];
+    let mut infos = vec![0_u16; width * height];
+    for x in 0..width {
+        tiles[x] = 1;
+        tiles[(height - 1) * width + x] = 1;
+    }
+    for y in 0..height {
+        tiles[y * width] = 1;
+        tiles[y * width + width - 1] = 1;
+    }
+    things[(height / 2) * width + width / 2] = 19;
+    infos[width + 1] = 0x1234;
+    let map = MapData::new("BIG MAP".to_string(), width as u16, height as u16, tiles, things)
+        .unwrap()
+        .with_info_plane(infos)
+        .unwrap();
+    let (maphead, gamemaps) = encode_maps(&[map], DEFAULT_RLEW_TAG).unwrap();
+    let mut levels = MemorySource::new("levels");
+    levels.insert("MAPHEAD.WL1", maphead);
+    levels.insert("GAMEMAPS.WL1", gamemaps);
+
+    let mut source = LayeredSource::new();
+    source.push(Box::new(DirSource::new(&dir)));
+    source.push(Box::new(levels));
+    let assets = GameAssets::load_from_source(&source).expect("failed to load the big map");
+    let map = &assets.maps[0];
+    assert_eq!((map.width, map.height), (96, 72));
+    assert_eq!(map.info(1, 1), 0x1234);
+    assert_eq!(map.thing(48, 36), 19);
+    assert_eq!(map.tile(95, 71), 1);
+}
@@ -0,0 +1,74 @@
+//! OPL2 tests: render some of the shareware AdLib sounds and music, and compare the PCM with known checksums.
+//! The tests need the shareware files (see `common::find_data_dir`) - without them, they are skipped.
+//! If the emulation is changed on purpose, listen to the new output, then update the checksums.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+
+#[test]
+fn render_adlib_sounds_wl1() {
+    let Some(assets) = load_wl1() else {
+        return;
+    };
+    let sounds = [
+        (SoundEffect::HitWall, 0x2A8BEAC0),
+        (SoundEffect::NoWay, 0xEE5C59FC),
+        (SoundEffect::GetAmmo, 0xD0D69DAF),
+        (SoundEffect::OpenDoor, 0xBEE5C7C3),
+    ];
+    for (snd, crc) in sounds {
+        let sound = &assets.adlib_sounds[snd as usize];
+        let pcm = render_adlib_sound(sound, 22050);
+        // one note per tick (at 140 Hz), plus the release tail
+        assert!(pcm.len() >= sound.len() * 22050 / 140, "{snd:?} is too short");
+        assert!(pcm.iter().any(|s| *s != 0), "{snd:?} is silent");
+        assert_eq!(pcm_crc(&pcm), crc, "{snd:?} sounds different");
+    }
+}
+
+#[test]
+fn render_imf_music_wl1() {
+    let Some(assets) = load_wl1() else {
+        return;
+    };
+    // the first few seconds of the first song are enough
+    let music = assets.music.iter().find(|music| !music.is_empty()).expect("no music");
+    let music = ImfMusic {
+        commands: music.commands[..music.len().min(1000)].to_vec(),
+    };
+    let pcm = render_imf_music(&music, 22050);
+    assert_eq!(
+        pcm.len() as u64,
+        (music.duration_ticks() as u64) * 22050 / (IMF_TICK_RATE as u64)
+    );
+    assert!(pcm.iter().any(|s| *s != 0));
+    assert_eq!(pcm_crc(&pcm), 0xA059299C);
+
+    // the streaming player generates the same samples, in any chunk sizes
+    let mut player = ImfPlayer::new(music, 22050, false);
+    let mut streamed = vec![0; pcm.len()];
+    for chunk in streamed.chunks_mut(1234) {
+        player.generate(chunk);
+    }
+    assert!(player.is_finished());
+    assert!(streamed == pcm);
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+fn load_wl1() -> Option<GameAssets> {
+    let Some(dir) = find_data_dir("AUDIOT.WL1") else {
+        println!("AUDIOT.WL1 not found => skipping the OPL2 test");
+        return None;
+    };
+    Some(GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets"))
+}
+
+fn pcm_crc(pcm: &[i16]) -> u32 {
+    let bytes: Vec<u8> = pcm.iter().flat_map(|s| s.to_le_bytes()).collect();
+    crc32(&bytes)
+}
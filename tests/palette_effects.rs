@@ -0,0 +1,148 @@
+//! Palette effects tests: the damage and pickup flashes (with the curves from the original), and the fades.
+
+use rolf3d::*;
+
+const TIC: f64 = 1.0 / TICS_PER_SECOND;
+
+#[test]
+fn damage_flash_follows_the_original_curve() {
+    let mut effects = PaletteEffects::new();
+    assert_eq!(effects.red_amount(), 0.0);
+    assert!(effects.apply(&Palette::wolf3d()).is_none());
+
+    // 25 damage => red = 25/10 + 1 = 3 => 3/8
+    effects.start_flash(PaletteFlash::Damage(25));
+    assert_eq!(effects.red_amount(), 3.0 / 8.0);
+    // damage adds up, the strength is capped at 6/8
+    effects.start_flash(PaletteFlash::Damage(100));
+    assert_eq!(effects.red_amount(), 6.0 / 8.0);
+
+    // the flash lasts (damage) tics, getting weaker
+    effects.update(100.0 * TIC);
+    assert_eq!(effects.red_amount(), 3.0 / 8.0);
+    effects.update(20.0 * TIC);
+    assert_eq!(effects.red_amount(), 1.0 / 8.0);
+    effects.update(5.5 * TIC);
+    assert_eq!(effects.red_amount(), 0.0);
+}
+
+#[test]
+fn bonus_flash_and_red_wins() {
+    let palette = Palette::wolf3d();
+    let mut effects = PaletteEffects::new();
+
+    // a pickup flashes for 18 tics: 3/20, then 2/20, then 1/20
+    effects.start_flash(PaletteFlash::Bonus);
+    assert_eq!(effects.gold_amount(), 3.0 / 20.0);
+    effects.update(6.5 * TIC);
+    assert_eq!(effects.gold_amount(), 2.0 / 20.0);
+    effects.update(6.0 * TIC);
+    assert_eq!(effects.gold_amount(), 1.0 / 20.0);
+
+    let gold = effects.apply(&palette).unwrap();
+    assert!(gold.color(0).r > 0 && gold.color(0).g > 0);
+    assert_eq!(gold.color(0).b, 0);
+
+    // red wins over gold
+    effects.start_flash(PaletteFlash::Damage(5));
+    let red = effects.apply(&palette).unwrap();
+    assert!(red.color(0).r > 0);
+    assert_eq!(red.color(0).g, 0);
+
+    effects.clear_flashes();
+    assert!(effects.apply(&palette).is_none());
+}
+
+#[test]
+fn fade_out_and_in() {
+    let palette = Palette::wolf3d();
+    let white = palette.color(15);
+    let mut effects = PaletteEffects::new();
+
+    effects.fade_out();
+    assert!(effects.is_fading());
+    effects.update(15.5 * TIC);
+    assert_eq!(effects.black_amount(), 0.5);
+    let half = effects.apply(&palette).unwrap();
+    assert!(half.color(15).grayscale() < white.grayscale());
+    assert!(half.color(15).grayscale() > 0);
+
+    // the screen stays black, until faded in
+    effects.update(20.0 * TIC);
+    assert!(effects.is_black());
+    assert!(!effects.is_fading());
+    effects.update(1.0);
+    assert_eq!(effects.apply(&palette).unwrap().color(15), RGB::from(0, 0, 0));
+
+    effects.fade_in();
+    effects.update(10.0 * TIC);
+    assert!(effects.is_fading());
+    assert!(effects.black_amount() > 0.6 && effects.black_amount() < 0.7);
+    effects.update(20.0 * TIC);
+    assert!(!effects.is_fading() && !effects.is_black());
+    assert!(effects.apply(&palette).is_none());
+}
+
+#[test]
+fn screen_buffer_paints_with_the_effects() {
+    let palette = Palette::wolf3d();
+    let mut scrbuf = ScreenBuffer::new(320, 240, palette.clone());
+    scrbuf.fill_rect(0, 0, 320, 240, 15);
+    assert_eq!(capture(&scrbuf), palette.color(15));
+
+    scrbuf.effects_mut().start_flash(PaletteFlash::Damage(60));
+    let flashed = capture(&scrbuf);
+    assert!(flashed.r >= flashed.g && flashed.g < palette.color(15).g);
+
+    scrbuf.effects_mut().clear_flashes();
+    scrbuf.effects_mut().fade_out();
+    scrbuf.effects_mut().update(1.0);
+    assert_eq!(capture(&scrbuf), RGB::from(0, 0, 0));
+    // the palette itself is not changed
+    assert_eq!(scrbuf.palette().color(15), palette.color(15));
+}
+
+#[test]
+fn pickups_and_damage_trigger_flashes() {
+    let mut notifier = Notifier::new();
+    notifier.notify_collectible(Collectible::AmmoClipSmall);
+    notifier.notify_damage(10);
+    assert_eq!(
+        notifier.take_flashes(),
+        vec![PaletteFlash::Bonus, PaletteFlash::Damage(10)]
+    );
+    assert!(notifier.take_flashes().is_empty());
+}
+
+//----------------------
+//  Internal stuff
+//----------------------
+
+struct Capture {
+    pixel: RGB,
+}
+
+impl Painter for Capture {
+    fn get_screen_width(&self) -> i32 {
+        320
+    }
+
+    fn get_screen_height(&self) -> i32 {
+        240
+    }
+
+    fn draw_pixel(&mut self, x: i32, y: i32, color: RGB) {
+        if x == 160 && y == 120 {
+            self.pixel = color;
+        }
+    }
+}
+
+/// The color at the center of the screen.
+fn capture(scrbuf: &ScreenBuffer) -> RGB {
+    let mut capture = Capture {
+        pixel: RGB::from(0, 0, 0),
+    };
+    scrbuf.paint(&mut capture);
+    capture.pixel
+}
@@ -0,0 +1,125 @@
+//! Palette tests: the built-in palettes, parsing palette files and the per-pic palettes from the pic table.
+
+use rolf3d::*;
+
+#[test]
+fn builtin_palettes() {
+    let wolf3d = Palette::wolf3d();
+    assert_eq!(wolf3d.color(0), RGB::from(0, 0, 0));
+    assert_eq!(wolf3d.color(15), RGB::from(255, 255, 255));
+    assert_eq!(wolf3d.to_rgb_bytes().len(), PALETTE_SIZE);
+
+    // SOD differs in only 2 colors
+    let sod = Palette::sod();
+    let diffs: Vec<u8> = (0..=255).filter(|&c| wolf3d.color(c) != sod.color(c)).collect();
+    assert_eq!(diffs, vec![166, 167]);
+    assert_eq!(Palette::builtin(true), sod);
+    assert_eq!(Palette::builtin(false), wolf3d);
+}
+
+#[test]
+fn parse_raw_palettes() {
+    let wolf3d = Palette::wolf3d();
+    let vga = vga_bytes(&wolf3d);
+    assert_eq!(Palette::from_vga_bytes(&vga).unwrap(), wolf3d);
+    assert_eq!(Palette::parse(&vga).unwrap(), wolf3d);
+    assert_eq!(Palette::parse(&wolf3d.to_rgb_bytes()).unwrap(), wolf3d);
+
+    assert!(Palette::parse(&vga[0..100]).is_err());
+    assert!(Palette::from_vga_bytes(&[64; PALETTE_SIZE]).is_err());
+}
+
+#[test]
+fn parse_jasc_palette() {
+    let mut text = "JASC-PAL\r\n0100\r\n3\r\n".to_string();
+    text.push_str("255 0 0\r\n0 255 0\r\n0 0 255\r\n");
+    let palette = Palette::parse(text.as_bytes()).unwrap();
+    assert_eq!(palette.color(0), RGB::from(255, 0, 0));
+    assert_eq!(palette.color(2), RGB::from(0, 0, 255));
+    // the missing colors are black
+    assert_eq!(palette.color(3), RGB::from(0, 0, 0));
+
+    assert!(Palette::parse(b"JASC-PAL\n0100\n1\n1 2\n").is_err());
+}
+
+#[test]
+fn parse_omf_palette() {
+    // GAMEPAL.OBJ: THEADR, then the palette split in 2 LEDATA records (at offsets 0 and 400)
+    let wolf3d = Palette::wolf3d();
+    let vga = vga_bytes(&wolf3d);
+    let mut obj = omf_record(0x80, b"\x0bGAMEPAL.ASM");
+    for (offset, data) in [(0_u16, &vga[0..400]), (400, &vga[400..])] {
+        let mut contents = vec![1];
+        contents.extend_from_slice(&offset.to_le_bytes());
+        contents.extend_from_slice(data);
+        obj.extend(omf_record(0xA0, &contents));
+    }
+    obj.extend(omf_record(0x8A, &[0]));
+    assert_eq!(Palette::parse(&obj).unwrap(), wolf3d);
+}
+
+#[test]
+fn nearest_palette_color() {
+    let palette = Palette::wolf3d();
+    assert_eq!(palette.nearest_color(RGB::from(250, 250, 250), None), 15);
+    // the palette may contain the same color twice => compare the colors, not the indexes
+    for c in 0..=255 {
+        let nearest = palette.nearest_color(palette.color(c), None);
+        assert_eq!(palette.color(nearest), palette.color(c));
+    }
+    let transparent = palette.color(TRANSPARENT_COLOR);
+    assert_ne!(
+        palette.nearest_color(transparent, Some(TRANSPARENT_COLOR)),
+        TRANSPARENT_COLOR
+    );
+}
+
+#[test]
+fn pic_palettes_in_pic_table() {
+    let table = PicTable::parse("TitleScreen = 5\nTitleScreen.palette = 153\n").unwrap();
+    assert_eq!(table.pic_index(PicType::TitleScreen), Some(5));
+    assert_eq!(table.palette_chunks(), &[(PicType::TitleScreen, 153)]);
+    assert_eq!(PicTable::parse("NoSuchPic.palette = 1").unwrap_err().0, 1);
+
+    let mut pics = PicDict::new(&table, vec![]);
+    assert_eq!(pics.pic_palette(PicType::TitleScreen), None);
+    pics.set_pic_palette(PicType::TitleScreen, Palette::sod());
+    assert_eq!(pics.pic_palette(PicType::TitleScreen), Some(&Palette::sod()));
+}
+
+#[test]
+fn draw_pics_with_their_own_palette() {
+    let palette = Palette::wolf3d();
+    let mut scrbuf = ScreenBuffer::new(320, 240, palette.clone());
+    // in the pic palette, color 1 is white (it is blue in the game palette)
+    let mut rgb = vec![0; 768];
+    rgb[3..6].copy_from_slice(&[252, 252, 252]);
+    let pic_palette = Palette::from_rgb_bytes(&rgb).unwrap();
+    let pic = GfxData::new_pic(4, 4, vec![1; 16]);
+
+    scrbuf.draw_scaled_pic(0, 0, 4, 4, &pic);
+    assert_eq!(scrbuf.frame()[0], 1);
+    scrbuf.draw_scaled_pic_with_palette(0, 0, 4, 4, &pic, &pic_palette);
+    let white = palette.nearest_color(RGB::from(252, 252, 252), None);
+    assert_eq!(palette.color(white), palette.color(15));
+    assert_eq!(scrbuf.frame()[0], white);
+    // the screen palette is not changed
+    assert_eq!(scrbuf.palette(), &palette);
+}
+
+/// Convert a palette back to 6-bit VGA values.
+fn vga_bytes(palette: &Palette) -> Vec<u8> {
+    palette
+        .to_rgb_bytes()
+        .iter()
+        .map(|&v| if v == 255 { 63 } else { v / 4 })
+        .collect()
+}
+
+fn omf_record(rec_type: u8, contents: &[u8]) -> Vec<u8> {
+    let mut rec = vec![rec_type];
+    rec.extend_from_slice(&(contents.len() as u16 + 1).to_le_bytes());
+    rec.extend_from_slice(contents);
+    rec.push(0); // checksum (not checked)
+    rec
+}
@@ -0,0 +1,42 @@
+//! PC speaker tests: the tone frequencies, the tick length of the sounds, and the silence.
+
+use rolf3d::*;
+
+#[test]
+fn tone_frequencies() {
+    // the PIT divisor is tone * 60, and 0 is silence
+    assert_eq!(pc_tone_frequency(0), None);
+    assert_eq!(pc_tone_frequency(1), Some(PIT_FREQUENCY / 60.0));
+    let freq = pc_tone_frequency(100).unwrap();
+    assert!((freq - 198.86).abs() < 0.01);
+
+    // the square wave has that frequency: two sign changes per period
+    let mut speaker = PcSpeaker::new(44100);
+    speaker.set_tone(100);
+    let mut out = vec![0; 44100];
+    speaker.generate(&mut out);
+    let sign_changes = out.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count() as f64;
+    assert!((sign_changes - 2.0 * freq).abs() <= 2.0, "{sign_changes} sign changes");
+    assert!(out.iter().all(|s| (*s as f64).abs() <= PC_SPEAKER_VOLUME));
+}
+
+#[test]
+fn sound_ticks_and_silence() {
+    let sound = PcSound {
+        priority: 0,
+        tones: vec![50, 0, 50, 0],
+    };
+    // one tone per tick, at 140 Hz => 50 samples per tick, at 7000 Hz
+    let pcm = render_pc_sound(&sound, 7000);
+    assert_eq!(pcm.len(), 4 * 50);
+    for (tick, samples) in pcm.chunks(50).enumerate() {
+        if tick % 2 == 0 {
+            assert!(samples.iter().any(|s| *s != 0), "tick {tick} is silent");
+        } else {
+            assert!(samples.iter().all(|s| *s == 0), "tick {tick} is not silent");
+        }
+    }
+    // the ticks are split exactly, even when a tick is not a whole number of samples
+    assert_eq!(render_pc_sound(&sound, 22050).len(), 4 * 22050 / 140);
+    assert_eq!(render_pc_sound(&sound, 44100).len(), 4 * 315);
+}
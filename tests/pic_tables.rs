@@ -0,0 +1,172 @@
+//! Pic table tests: parsing the tables, looking up pic types by name, and mapping the WL1 pics.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+
+#[test]
+fn pic_types_by_name_and_index() {
+    for idx in 0..PicDict::pic_count() {
+        let typ = PicType::from_index(idx).unwrap();
+        assert_eq!(typ as usize, idx);
+        assert_eq!(PicType::from_name(typ.name()), Some(typ));
+    }
+    assert_eq!(PicType::from_name("titlescreen"), Some(PicType::TitleScreen));
+    assert_eq!(PicType::from_name("NoSuchPic"), None);
+    assert_eq!(PicType::from_index(PicDict::pic_count()), None);
+    assert_eq!(PicType::GetPsyched.to_string(), "GetPsyched");
+}
+
+#[test]
+fn parse_pic_tables() {
+    let text = "# a comment\nTitleScreen = 5\n\nStatusNum0..StatusNum2 = 10  # a range\n";
+    let table = PicTable::parse(text).unwrap();
+    assert_eq!(table.pic_index(PicType::TitleScreen), Some(5));
+    assert_eq!(table.pic_index(PicType::StatusNum0), Some(10));
+    assert_eq!(table.pic_index(PicType::StatusNum2), Some(12));
+    assert_eq!(table.pic_index(PicType::StatusNum3), None);
+
+    // errors contain the line number
+    assert_eq!(PicTable::parse("TitleScreen = 1\nNoSuchPic = 2").unwrap_err().0, 2);
+    assert_eq!(PicTable::parse("TitleScreen 1").unwrap_err().0, 1);
+    assert_eq!(PicTable::parse("TitleScreen = x").unwrap_err().0, 1);
+    assert_eq!(PicTable::parse("StatusNum2..StatusNum0 = 1").unwrap_err().0, 1);
+
+    // WL3 uses the same pics as WL6
+    let unknown = GameRelease::Unknown;
+    assert_eq!(PicTable::builtin("WL3", unknown), PicTable::builtin("WL6", unknown));
+    assert_eq!(PicTable::builtin("SD2", unknown), PicTable::builtin("SOD", unknown));
+    // a known release gets its own table, whatever the extension
+    let wl6 = PicTable::builtin("WL6", unknown);
+    assert_eq!(PicTable::builtin("SOD", GameRelease::Wolf3dGT), wl6);
+    assert_eq!(
+        PicTable::builtin("WL1", GameRelease::Wolf3dApogee14),
+        PicTable::builtin("WL1", unknown)
+    );
+
+    // the SOD title screen has its own palette (TITLEPALETTE), the other games use the game palette
+    assert_eq!(
+        PicTable::builtin("SOD", unknown).palette_chunks(),
+        &[(PicType::TitleScreen, 153)]
+    );
+    assert!(wl6.palette_chunks().is_empty());
+}
+
+#[test]
+fn split_pics_are_stacked() {
+    let table = PicTable::parse("TitleScreen = 1\nTitleScreen.lower = 2\n").unwrap();
+    assert_eq!(table.lower_pics(), &[(PicType::TitleScreen, 2)]);
+    assert_eq!(PicTable::parse("NoSuchPic.lower = 1").unwrap_err().0, 1);
+    // 2 columns each: the upper pic has 2 rows, the lower one has 3 rows
+    let pics = vec![
+        GfxData::new_pic(1, 1, vec![9]),
+        GfxData::new_pic(2, 2, vec![1, 2, 3, 4]),
+        GfxData::new_pic(2, 3, vec![5, 6, 7, 8, 9, 10]),
+    ];
+    let pics = PicDict::new(&table, pics);
+    let title = pics.pic(PicType::TitleScreen, 0);
+    assert_eq!(title.size(), (2, 5));
+    assert_eq!(title.texels(), &[1, 2, 5, 6, 7, 3, 4, 8, 9, 10]);
+
+    // a lower pic with another width is ignored
+    let pics = vec![
+        GfxData::new_pic(1, 1, vec![9]),
+        GfxData::new_pic(2, 2, vec![1, 2, 3, 4]),
+        GfxData::new_pic(3, 1, vec![5, 6, 7]),
+    ];
+    let pics = PicDict::new(&table, pics);
+    assert_eq!(pics.pic(PicType::TitleScreen, 0).size(), (2, 2));
+
+    // the Spear title screens are split
+    assert_eq!(
+        PicTable::builtin("SOD", GameRelease::Unknown).lower_pics(),
+        &[(PicType::TitleScreen, 80)]
+    );
+    assert_eq!(
+        PicTable::builtin("SDM", GameRelease::Unknown).lower_pics(),
+        &[(PicType::TitleScreen, 72)]
+    );
+    assert!(PicTable::builtin("WL6", GameRelease::Unknown).lower_pics().is_empty());
+}
+
+#[test]
+fn builtin_tables() {
+    let unknown = GameRelease::Unknown;
+    // (game, pic type, pic index = chunk index - 3, see the GFXV_xxx.H headers)
+    let pics = [
+        ("WL1", PicType::HelpBJ, Some(0)),
+        ("WL1", PicType::OptionTitle, Some(19)),
+        ("WL1", PicType::Episode6, Some(44)),
+        ("WL1", PicType::FloorEndGuy, Some(52)),
+        ("WL1", PicType::StatusBar, Some(95)),
+        ("WL1", PicType::TitleScreen, Some(96)),
+        ("WL1", PicType::GetPsyched, Some(143)),
+        ("WL1", PicType::BackDropScreen, None),
+        ("WL6", PicType::HelpBlaze, Some(2)),
+        ("WL6", PicType::OptionTitle, Some(7)),
+        ("WL6", PicType::Episode6, Some(32)),
+        ("WL6", PicType::StatusBar, Some(83)),
+        ("WL6", PicType::TitleScreen, Some(84)),
+        ("WL6", PicType::GetPsyched, Some(131)),
+        // the option pics of Spear are ordered differently (C_MOUSELBACKPIC .. C_SAVEGAMEPIC)
+        ("SDM", PicType::BackDropScreen, Some(0)),
+        ("SDM", PicType::MoveSelBack, Some(1)),
+        ("SDM", PicType::Cursor1, Some(2)),
+        ("SDM", PicType::SelectionOn, Some(5)),
+        ("SDM", PicType::CustomizeTitle, Some(6)),
+        ("SDM", PicType::ControlTitle, Some(12)),
+        ("SDM", PicType::OptionTitle, Some(13)),
+        ("SDM", PicType::MusicTitle, Some(16)),
+        ("SDM", PicType::DifficultyHard, Some(21)),
+        ("SDM", PicType::DiskLoading2, Some(23)),
+        ("SDM", PicType::SaveGameTitle, Some(25)),
+        ("SDM", PicType::HighScoresTitle, Some(26)),
+        ("SDM", PicType::FloorEndGuy, Some(28)),
+        ("SDM", PicType::TitleScreen, Some(71)),
+        ("SDM", PicType::LoadSaveFloppy, None),
+        ("SDM", PicType::Episode1, None),
+        ("SOD", PicType::MoveSelBack, Some(1)),
+        ("SOD", PicType::OptionTitle, Some(13)),
+        ("SOD", PicType::SoundFxTitle, Some(14)),
+        ("SOD", PicType::DifficultyBaby, Some(18)),
+        ("SOD", PicType::LoadGameTitle, Some(24)),
+        ("SOD", PicType::HighScoresTitle, Some(26)),
+        ("SOD", PicType::FloorEndGuy, Some(33)),
+        ("SOD", PicType::TitleScreen, Some(79)),
+        ("SOD", PicType::StatusBar, Some(87)),
+        ("SOD", PicType::GetPsyched, Some(146)),
+        ("SOD", PicType::LoadSaveFloppy, None),
+        ("SOD", PicType::Episode1, None),
+    ];
+    for (game, typ, idx) in pics {
+        assert_eq!(PicTable::builtin(game, unknown).pic_index(typ), idx, "{game} {typ}");
+    }
+
+    // no pic is mapped twice
+    for game in ["WL1", "WL6", "SDM", "SOD"] {
+        let table = PicTable::builtin(game, unknown);
+        let mut indexes: Vec<usize> = (0..PicDict::pic_count())
+            .filter_map(|idx| table.pic_index(PicType::from_index(idx).unwrap()))
+            .collect();
+        let cnt = indexes.len();
+        indexes.sort();
+        indexes.dedup();
+        assert_eq!(indexes.len(), cnt, "{game} maps a pic twice");
+    }
+}
+
+#[test]
+fn wl1_pics_are_mapped() {
+    let Some(dir) = find_data_dir("VGAGRAPH.WL1") else {
+        println!("VGAGRAPH.WL1 not found => skipping the pic table test");
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+    assert_eq!(assets.pics.pic(PicType::TitleScreen, 0).size(), (320, 200));
+    assert_eq!(assets.pics.pic(PicType::StatusBar, 0).size(), (320, 40));
+    // the gaps are filled: WL1 has no backdrop and only one "god mode" face
+    assert_eq!(assets.pics.pic(PicType::BackDropScreen, 0).size(), (320, 200));
+    let god1 = assets.pics.pic(PicType::StatusFaceGod1, 0);
+    assert_eq!(assets.pics.pic(PicType::StatusFaceGod3, 0).texels(), god1.texels());
+}
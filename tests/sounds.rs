@@ -0,0 +1,31 @@
+//! Sound dictionary tests: the digitized sound indexes, like in the original digi maps.
+
+use rolf3d::*;
+
+#[test]
+fn wolf3d_digi_sounds() {
+    assert_eq!(digi_sound_index("WL1", SoundEffect::Halt), Some(0));
+    assert_eq!(digi_sound_index("WL6", SoundEffect::DeathScream3), Some(13));
+    assert_eq!(digi_sound_index("WL6", SoundEffect::Rose), Some(45));
+    assert_eq!(digi_sound_index("WL6", SoundEffect::HitWall), None);
+    assert_eq!(digi_sound_index("WL6", SoundEffect::GetSpear), None);
+}
+
+#[test]
+fn spear_digi_sounds() {
+    for ext in ["SOD", "SD1", "SD2", "SD3", "SDM"] {
+        assert_eq!(digi_sound_index(ext, SoundEffect::Halt), Some(0));
+        assert_eq!(digi_sound_index(ext, SoundEffect::BossFire), Some(8));
+        // doubly-mapped, like in the original
+        assert_eq!(digi_sound_index(ext, SoundEffect::DeathScream3), Some(23));
+        assert_eq!(digi_sound_index(ext, SoundEffect::DeathScream4), Some(23));
+        assert_eq!(digi_sound_index(ext, SoundEffect::GetGatling), Some(38));
+        assert_eq!(digi_sound_index(ext, SoundEffect::Rose), None);
+    }
+    assert_eq!(digi_sound_index("SOD", SoundEffect::DogBark), Some(1));
+    assert_eq!(digi_sound_index("SOD", SoundEffect::AngelDeath), Some(37));
+    assert_eq!(digi_sound_index("SOD", SoundEffect::GetSpear), Some(39));
+    // the demo does not have these
+    assert_eq!(digi_sound_index("SDM", SoundEffect::DogBark), None);
+    assert_eq!(digi_sound_index("SDM", SoundEffect::GetSpear), None);
+}
@@ -0,0 +1,61 @@
+//! Round-trip test for VGAGRAPH: encode the fonts, pics and other chunks, load them back and compare.
+//! Needs the shareware files (see `common::find_data_dir`) - without them, the test is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+
+#[test]
+fn vgagraph_roundtrip_wl1() {
+    let Some(dir) = find_data_dir("VGAGRAPH.WL1") else {
+        println!("VGAGRAPH.WL1 not found => skipping the VGAGRAPH round-trip test");
+        return;
+    };
+    let vga = VgaGraph::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 VGAGRAPH");
+
+    // write the new files next to copies of the other game files
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-vgagraph-{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    for name in ["MAPHEAD", "GAMEMAPS", "VSWAP", "AUDIOHED", "AUDIOT"] {
+        let filename = format!("{name}.WL1");
+        let path = find_file_ignore_case(&dir, &filename).unwrap();
+        std::fs::copy(path, outdir.join(filename)).unwrap();
+    }
+    write_vgagraph(&outdir, "WL1", &vga).unwrap();
+    let reloaded = VgaGraph::load_from(outdir.to_str().unwrap());
+    let assets = GameAssets::load_from_dirs(&[outdir.to_str().unwrap()]);
+    std::fs::remove_dir_all(&outdir).unwrap();
+    let reloaded = reloaded.expect("failed to load the new VGAGRAPH");
+    assert!(assets.is_ok(), "the game cannot load the new VGAGRAPH");
+
+    assert_same_font(&vga.font1, &reloaded.font1);
+    assert_same_font(&vga.font2, &reloaded.font2);
+    assert_eq!(vga.pics.len(), reloaded.pics.len());
+    for (idx, (a, b)) in vga.pics.iter().zip(&reloaded.pics).enumerate() {
+        assert_eq!(a.size(), b.size(), "size of pic #{idx} differs");
+        assert!(a.texels() == b.texels(), "pic #{idx} differs");
+    }
+    assert_eq!(vga.other_chunks.len(), reloaded.other_chunks.len());
+    for (idx, (a, b)) in vga.other_chunks.iter().zip(&reloaded.other_chunks).enumerate() {
+        assert!(a == b, "chunk #{idx} after the pics differs");
+    }
+}
+
+#[test]
+fn huffman_encode_decode() {
+    let data: Vec<u8> = (0..5000_u32).map(|i| ((i * i) % 37 + (i % 3) * 100) as u8).collect();
+    let huff_dict = huff_build_dict(&[&data]);
+    assert_eq!(huff_dict.len(), 512);
+    let encoded = huff_encode_chunk(&data, &huff_dict).unwrap();
+    assert!(encoded.len() < data.len());
+    assert_eq!(huff_decode_chunk(&encoded, &huff_dict).unwrap(), data);
+}
+
+fn assert_same_font(expected: &FontData, actual: &FontData) {
+    assert_eq!(expected.font_height(), actual.font_height());
+    assert_eq!(expected.space_width(), actual.space_width());
+    for ch in 33..128 {
+        assert_eq!(expected.glyph(ch), actual.glyph(ch), "character {ch} differs");
+    }
+}
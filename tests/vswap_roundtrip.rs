@@ -0,0 +1,124 @@
+//! Round-trip test for VSWAP: encode the walls, sprites and digitized sounds, load them back and compare.
+//! Needs the shareware files (see `common::find_data_dir`) - without them, the test is skipped.
+
+mod common;
+
+use common::find_data_dir;
+use rolf3d::*;
+use std::rc::Rc;
+
+#[test]
+fn vswap_roundtrip_wl1() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the VSWAP round-trip test");
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+
+    // copy the other game files next to the new VSWAP
+    let outdir = std::env::temp_dir().join(format!("rolf3d-test-vswap-{}", std::process::id()));
+    std::fs::create_dir_all(&outdir).unwrap();
+    for name in [
+        "MAPHEAD", "GAMEMAPS", "VGADICT", "VGAHEAD", "VGAGRAPH", "AUDIOHED", "AUDIOT",
+    ] {
+        let filename = format!("{name}.WL1");
+        let path = find_file_ignore_case(&dir, &filename).unwrap();
+        std::fs::copy(path, outdir.join(filename)).unwrap();
+    }
+    write_vswap(&outdir, "WL1", &assets.walls, &assets.sprites, &assets.digi_sounds).unwrap();
+    let reloaded = GameAssets::load_from_dirs(&[outdir.to_str().unwrap()]);
+    std::fs::remove_dir_all(&outdir).unwrap();
+    let reloaded = reloaded.expect("failed to load the new VSWAP");
+
+    assert_same_gfx(&assets.walls, &reloaded.walls);
+    assert_same_gfx(&assets.sprites, &reloaded.sprites);
+    assert_eq!(assets.digi_sounds.len(), reloaded.digi_sounds.len());
+    for (idx, (a, b)) in assets.digi_sounds.iter().zip(&reloaded.digi_sounds).enumerate() {
+        assert!(a.samples == b.samples, "digitized sound #{idx} differs");
+    }
+}
+
+#[test]
+fn pages_are_decoded_lazily() {
+    let Some(dir) = find_data_dir("VSWAP.WL1") else {
+        println!("VSWAP.WL1 not found => skipping the lazy decoding test");
+        return;
+    };
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).expect("failed to load the WL1 assets");
+    let vswap_len = std::fs::metadata(find_file_ignore_case(&dir, "VSWAP.WL1").unwrap())
+        .unwrap()
+        .len() as usize;
+    assert_eq!(assets.load_stats.largest_file, vswap_len);
+    assert!(assets.load_stats.files_read >= 8);
+    // only the graphics part of VSWAP is kept (the digitized sounds are already parsed)
+    assert!(assets.sprites.raw_size() < vswap_len);
+
+    // nothing is decoded before first use, except for the missing pages
+    let missing = |pages: &GfxPages| pages.iter().filter(|gfx| gfx.size() == (0, 0)).count();
+    let (missing_walls, missing_sprites) = (missing(&assets.walls), missing(&assets.sprites));
+    let assets = GameAssets::load_from(dir.to_str().unwrap()).unwrap();
+    assert_eq!(assets.sprites.decoded_count(), missing_sprites);
+    assert_eq!(assets.sprites[50].size(), (64, 64));
+    assert_eq!(assets.sprites.decoded_count(), missing_sprites + 1);
+    assert_eq!(assets.walls.decoded_count(), missing_walls);
+    assert!(assets.walls.get(assets.walls.len()).is_none());
+}
+
+#[test]
+fn corrupt_pages_are_errors() {
+    fn decode_page(bytes: &[u8]) -> Result<GfxData, DecodeError> {
+        decode_sprite(bytes).map(GfxData::new_sprite)
+    }
+    // a valid sprite (one texel), then one with invalid extents
+    let raw: Vec<u8> = vec![0, 0, 0, 0, 8, 0, 7, 0, 2, 0, 0, 0, 0, 0, 0, 0, 5, 0, 4, 0];
+    let mut pages = GfxPages::new("VSWAP.TST", Rc::from(raw));
+    pages.push_lazy(10, 0, 16, decode_page);
+    pages.push_lazy(11, 16, 4, decode_page);
+    pages.push(GfxData::new_empty());
+
+    assert_eq!(pages.try_get(0).unwrap().size(), (64, 64));
+    match pages.try_get(1) {
+        Err(AssetError::BadData {
+            file, chunk, offset, ..
+        }) => {
+            assert_eq!((file.as_str(), chunk, offset), ("VSWAP.TST", Some(11), 16));
+        }
+        _ => panic!("the corrupt page was decoded"),
+    }
+    // the lenient getter returns an empty graphic, like for the missing pages
+    assert_eq!(pages.get(1).unwrap().size(), (0, 0));
+    assert_eq!(pages.try_get(2).unwrap().size(), (0, 0));
+    assert!(pages.try_get(1).is_err());
+    assert!(pages.try_get(3).is_err());
+    assert!(pages.get(3).is_none());
+}
+
+#[test]
+fn sprite_encode_decode() {
+    // posts of various lengths, an empty column inside the sprite, and a full column
+    let mut pixels = vec![0xFF_u8; 64 * 64];
+    for x in 3..60 {
+        if x == 20 {
+            continue;
+        }
+        for y in 0..64 {
+            if x == 40 || (y * x) % 7 < 3 {
+                pixels[x * 64 + y] = ((x + y) % 255) as u8;
+            }
+        }
+    }
+    let encoded = encode_sprite(&pixels).unwrap();
+    assert_eq!(decode_sprite(&encoded).unwrap(), pixels);
+
+    // a fully transparent sprite
+    let empty = vec![0xFF_u8; 64 * 64];
+    assert_eq!(decode_sprite(&encode_sprite(&empty).unwrap()).unwrap(), empty);
+}
+
+fn assert_same_gfx(expected: &GfxPages, actual: &GfxPages) {
+    assert_eq!(expected.len(), actual.len());
+    for (idx, (a, b)) in expected.iter().zip(actual.iter()).enumerate() {
+        assert_eq!(a.size(), b.size(), "size of #{idx} differs");
+        assert!(a.texels() == b.texels(), "texels of #{idx} differ");
+    }
+}